@@ -0,0 +1,182 @@
+//! Decoding of EVM logs with an ethers-core [`Abi`]
+//!
+//! Events are registered with the client from an [`Abi`] or from human-readable
+//! signatures like `Transfer(address indexed from, address indexed to, uint256
+//! value)`. [`Client::decode_logs`](crate::Client::decode_logs) then streams
+//! logs decoded into the name of their event and its parameters, and
+//! [`Client::decode_logs_as`](crate::Client::decode_logs_as) into any type
+//! implementing [`Detokenize`], like structs deriving `EthAbiType` from
+//! `ethers-contract`.
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use pangea_client::{requests::logs::GetLogsRequest, ClientBuilder, WsProvider};
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = ClientBuilder::default().build::<WsProvider>().await?;
+//! client.register_events(&[
+//!     "Transfer(address indexed from, address indexed to, uint256 value)",
+//! ])?;
+//!
+//! // `topic0__in` is filled with the registered events when left empty
+//! let mut stream = client.decode_logs(GetLogsRequest::default()).await?;
+//! while let Some(log) = stream.next().await {
+//!     let log = log?;
+//!     println!("{}: {:?}", log.name, log.params);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use ethers_core::{
+    abi::{Abi, AbiParser, Detokenize, Event, RawLog, Token},
+    types::{Bytes, H256},
+};
+use serde_json::Value;
+
+use super::error::Result;
+
+/// Parses a human-readable event signature, the `event` keyword is optional
+pub fn parse_event(signature: &str) -> Result<Event> {
+    let signature = signature.trim();
+    let declaration = if signature.starts_with("event ") {
+        signature.to_string()
+    } else {
+        format!("event {signature}")
+    };
+
+    Ok(AbiParser::default().parse_event(&declaration)?)
+}
+
+/// Computes the `topic0` of a human-readable event signature, to be used in
+/// `topic0__in` filters
+pub fn topic0(signature: &str) -> Result<H256> {
+    Ok(parse_event(signature)?.signature())
+}
+
+/// A log decoded with a registered event
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedLog<T = Vec<Token>> {
+    /// Name of the event
+    pub name: String,
+    /// Parameters of the event, indexed or not, in declaration order
+    pub params: T,
+    /// The log record as it was sent by the server
+    pub log: Value,
+}
+
+impl DecodedLog {
+    /// Converts the parameters into `T`
+    pub fn detokenize<T>(self) -> Result<DecodedLog<T>>
+    where
+        T: Detokenize,
+    {
+        Ok(DecodedLog {
+            name: self.name,
+            params: T::from_tokens(self.params)?,
+            log: self.log,
+        })
+    }
+}
+
+/// Events to decode logs with, looked up by their `topic0` and number of
+/// topics
+///
+/// The number of topics tells apart events sharing a signature but not their
+/// indexed parameters, like the ERC-20 and ERC-721 `Transfer`. Anonymous events
+/// have no `topic0` and are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct EventDecoder {
+    events: HashMap<(H256, usize), Event>,
+}
+
+impl EventDecoder {
+    pub fn from_abi(abi: &Abi) -> Self {
+        let mut decoder = Self::default();
+        decoder.add_abi(abi);
+        decoder
+    }
+
+    pub fn from_signatures(signatures: &[&str]) -> Result<Self> {
+        let mut decoder = Self::default();
+        decoder.add_signatures(signatures)?;
+        Ok(decoder)
+    }
+
+    pub fn add_abi(&mut self, abi: &Abi) {
+        for event in abi.events() {
+            self.add_event(event.clone());
+        }
+    }
+
+    pub fn add_signatures(&mut self, signatures: &[&str]) -> Result<()> {
+        for signature in signatures {
+            self.add_event(parse_event(signature)?);
+        }
+
+        Ok(())
+    }
+
+    pub fn add_event(&mut self, event: Event) {
+        if !event.anonymous {
+            let topics = 1 + event.inputs.iter().filter(|input| input.indexed).count();
+            self.events.insert((event.signature(), topics), event);
+        }
+    }
+
+    /// The `topic0` of every registered event
+    pub fn topics0(&self) -> HashSet<H256> {
+        self.events.keys().map(|(topic0, _)| *topic0).collect()
+    }
+
+    /// Decodes a JSON log record, returns `None` if it was not emitted by a
+    /// registered event
+    pub fn decode(&self, log: Value) -> Result<Option<DecodedLog>> {
+        let raw = match raw_log(&log) {
+            Some(raw) => raw,
+            None => return Ok(None),
+        };
+        let key = match raw.topics.first() {
+            Some(topic0) => (*topic0, raw.topics.len()),
+            None => return Ok(None),
+        };
+        let event = match self.events.get(&key) {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+
+        let params = event
+            .parse_log(raw)?
+            .params
+            .into_iter()
+            .map(|param| param.value)
+            .collect();
+
+        Ok(Some(DecodedLog {
+            name: event.name.clone(),
+            params,
+            log,
+        }))
+    }
+}
+
+/// Reads the topics and data of a JSON log record, the topics are either sent
+/// as a `topics` array or as `topic0` to `topic3` fields
+fn raw_log(log: &Value) -> Option<RawLog> {
+    let topic = |topic: &Value| serde_json::from_value::<H256>(topic.clone()).ok();
+
+    let topics = match log.get("topics") {
+        Some(Value::Array(topics)) => topics.iter().map(topic).collect::<Option<Vec<_>>>()?,
+        _ => (0..4)
+            .map_while(|i| log.get(format!("topic{i}")).and_then(topic))
+            .collect(),
+    };
+    let data = match log.get("data") {
+        Some(Value::String(data)) => data.parse::<Bytes>().ok()?.to_vec(),
+        _ => Vec::new(),
+    };
+
+    Some(RawLog { topics, data })
+}
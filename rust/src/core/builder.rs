@@ -1,6 +1,10 @@
 use lazy_static::lazy_static;
 
-use super::{client::Client, error::Result, provider::Provider};
+use super::{
+    client::{Client, DEFAULT_BATCH_SIZE},
+    error::Result,
+    provider::Provider,
+};
 
 lazy_static! {
     static ref USERNAME: String = std::env::var("PANGEA_USERNAME").unwrap_or_default();
@@ -37,6 +41,7 @@ pub struct ClientBuilder {
     is_secure: bool,
     username: Option<String>,
     password: Option<String>,
+    batch_size: usize,
 }
 
 /// A builder for `Client`.
@@ -72,13 +77,20 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the largest number of entries sent in one `__in` filter, see
+    /// [`Client::with_batch_size`]. Default is 500.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
     /// Creates a new `Client` with the given configuration.
     pub async fn build<T>(self) -> Result<Client<T>>
     where
         T: Provider + Send,
     {
         let inner = T::try_new(self.endpoint, self.is_secure, self.username, self.password).await?;
-        Ok(Client::new(inner))
+        Ok(Client::new(inner).with_batch_size(self.batch_size))
     }
 }
 
@@ -101,6 +113,7 @@ impl Default for ClientBuilder {
             password: Some(PASSWORD.to_string()),
             endpoint: DEFAULT_ENDPOINT.to_string(),
             is_secure: true,
+            batch_size: DEFAULT_BATCH_SIZE,
         }
     }
 }
@@ -0,0 +1,599 @@
+//! A provider that keeps the final part of responses on disk
+//!
+//! [`CacheProvider`] wraps another provider. Blocks below the finality margin
+//! of a chain never change, so the records of a request for them are stored
+//! per block range and served from disk the next time they are requested.
+//! Only the ranges missing from the cache are sent to the wrapped provider,
+//! and their responses are stitched together in block order with the cached
+//! ones.
+//!
+//! ```no_run
+//! use pangea_client::{cache::CacheProvider, ClientBuilder, WsProvider};
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = ClientBuilder::default()
+//!     .build::<CacheProvider<WsProvider>>()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    future::Future,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use ethers_core::utils::hex;
+use futures::{StreamExt, TryStreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{
+    error::{Error, ResponseError, Result},
+    provider::{
+        BtcProvider, ChainProvider, CurveProvider, Erc20Provider, FuelProvider, Provider,
+        ResponseStream, StreamResponse, UniswapV2Provider, UniswapV3Provider,
+    },
+    requests::{
+        blocks, btc, curve, erc20, fuel, logs, mira, transfers, txs, uniswap_v2, uniswap_v3,
+        BlockRange, Encoding,
+    },
+    stream::{json_lines, json_records, json_u64},
+    types::{format::Format, query::Bound, ChainId},
+    utils::deserialize_u64,
+};
+
+/// How long a chain head is trusted before it is fetched again
+///
+/// A stale head is lower than the real one, so it never lets blocks within the
+/// margin into the cache.
+const HEAD_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct Head {
+    #[serde(alias = "height", deserialize_with = "deserialize_u64")]
+    block_number: u64,
+}
+
+/// Caches the [`Format::JsonStream`] responses of single chain requests that
+/// start at an exact block, everything else is passed to the wrapped provider
+///
+/// Cached ranges are stored under
+/// `<dir>/<endpoint>/<chain>/<hash of the request>/` as one NDJSON file per
+/// range, named after its first block and the block after its last. Blocks
+/// within the margin of the chain head, by default the
+/// [`finality_depth`](ChainId::finality_depth) of the chain, are never cached.
+pub struct CacheProvider<P> {
+    inner: Arc<P>,
+    dir: PathBuf,
+    margin: Option<u64>,
+    heads: Mutex<HashMap<ChainId, (u64, Instant)>>,
+}
+
+impl<P> CacheProvider<P> {
+    /// The directory [`Provider::try_new`] caches in
+    pub const DEFAULT_DIR: &'static str = ".pangea-cache";
+
+    pub fn new(inner: P, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            dir: dir.into(),
+            margin: None,
+            heads: Mutex::default(),
+        }
+    }
+
+    /// Sets the number of blocks below the chain head that are never cached
+    pub fn with_margin(mut self, margin: u64) -> Self {
+        self.margin = Some(margin);
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Deletes every cached range
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<P> CacheProvider<P>
+where
+    P: ChainProvider + FuelProvider + BtcProvider + Send + Sync + 'static,
+{
+    /// Serves the cacheable part of `request` from the cache and the rest with
+    /// `fetch`, each missing range is only fetched once the stream reaches it
+    async fn cached<R, F, Fut>(
+        &self,
+        endpoint: &str,
+        request: R,
+        format: Format,
+        deltas: bool,
+        fetch: F,
+    ) -> StreamResponse<Vec<u8>>
+    where
+        R: BlockRange + Encoding + Clone + Send + 'static,
+        F: Fn(R) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = StreamResponse<Vec<u8>>> + Send,
+    {
+        let chain = match request.chains().iter().next() {
+            Some(chain) if request.chains().len() == 1 => *chain,
+            _ => return fetch(request).await,
+        };
+        let (Bound::Exact(from), to) = request.block_range() else {
+            return fetch(request).await;
+        };
+        if !matches!(format, Format::JsonStream) || deltas || from < 0 {
+            return fetch(request).await;
+        }
+
+        let head = self.head(chain).await?;
+        let end = match to {
+            Bound::Exact(to) if to >= 0 => to as u64,
+            Bound::Latest => head + 1,
+            _ => return fetch(request).await,
+        };
+        let margin = self.margin.unwrap_or_else(|| chain.finality_depth());
+        let cacheable = end.min((head + 1).saturating_sub(margin));
+        let from = from as u64;
+        if from >= cacheable {
+            return fetch(request).await;
+        }
+
+        let dir = self
+            .dir
+            .join(endpoint)
+            .join(chain.chain_code())
+            .join(key(&request)?);
+        let mut pieces = plan(&segments(&dir)?, from, cacheable);
+        if cacheable < end || matches!(to, Bound::Latest) {
+            pieces.push(Piece::Uncached(cacheable));
+        }
+
+        let pieces = futures::stream::iter(pieces).then(move |piece| {
+            let (fetch, dir, request) = (fetch.clone(), dir.clone(), request.clone());
+            async move {
+                match piece {
+                    Piece::Cached(path, from, to) => Ok(read_segment(path, from, to)),
+                    Piece::Missing(from, to) => {
+                        let response = fetch(range(&request, from, Some(to))).await?;
+                        store_segment(response, &dir, &request, from, to)
+                    }
+                    Piece::Uncached(from) => fetch(range(&request, from, None)).await,
+                }
+            }
+        });
+
+        Ok(pieces.try_flatten().boxed())
+    }
+
+    /// The latest block of `chain`
+    async fn head(&self, chain: ChainId) -> Result<u64> {
+        let cached = self
+            .heads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&chain)
+            .copied();
+        if let Some((head, fetched)) = cached {
+            if fetched.elapsed() < HEAD_TTL {
+                return Ok(head);
+            }
+        }
+
+        let response = match chain {
+            ChainId::FUEL | ChainId::FUELTESTNET => {
+                let request = latest::<fuel::GetFuelBlocksRequest>(chain);
+                self.inner
+                    .get_fuel_blocks_by_format(request, Format::JsonStream, false)
+                    .await?
+            }
+            ChainId::BTC => {
+                let request = latest::<btc::GetBtcBlocksRequest>(chain);
+                self.inner
+                    .get_btc_blocks_by_format(request, Format::JsonStream, false)
+                    .await?
+            }
+            _ => {
+                let request = latest::<blocks::GetBlocksRequest>(chain);
+                self.inner
+                    .get_blocks_by_format(request, Format::JsonStream, false)
+                    .await?
+            }
+        };
+        let head = json_records::<Head>(response)
+            .next()
+            .await
+            .transpose()?
+            .ok_or(Error::BlockNotFound(chain, Bound::Latest))?
+            .block_number;
+
+        self.heads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(chain, (head, Instant::now()));
+        Ok(head)
+    }
+}
+
+/// `request` restricted to the blocks `from..to`, or to the blocks from `from`
+/// on
+fn range<R>(request: &R, from: u64, to: Option<u64>) -> R
+where
+    R: BlockRange + Clone,
+{
+    let mut range = request.clone();
+    range.set_from_block(Bound::Exact(from as i64));
+    if let Some(to) = to {
+        range.set_to_block(Bound::Exact(to as i64));
+    }
+    range
+}
+
+/// A request for the latest block of `chain`
+fn latest<R>(chain: ChainId) -> R
+where
+    R: BlockRange + Default,
+{
+    let mut request = R::default();
+    request.set_chains([chain].into());
+    request.set_from_block(Bound::Latest);
+    request.set_to_block(Bound::Latest);
+    request
+}
+
+/// Hash of the request without its block range, with its sets sorted
+fn key<R>(request: &R) -> Result<String>
+where
+    R: Encoding,
+{
+    let Value::Object(mut fields) = request.to_json()? else {
+        return Err(Error::Custom("requests are JSON objects".into()));
+    };
+    fields.remove("from_block");
+    fields.remove("to_block");
+    for value in fields.values_mut() {
+        if let Value::String(set) = value {
+            let mut items = set.split(',').collect::<Vec<_>>();
+            items.sort_unstable();
+            *set = items.join(",");
+        }
+    }
+
+    let canonical = serde_json::to_vec(&fields)?;
+    Ok(hex::encode(&Sha256::digest(canonical)[..16]))
+}
+
+/// The cached ranges in `dir`, ordered by their first block
+fn segments(dir: &Path) -> Result<Vec<(u64, u64, PathBuf)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut segments = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let range = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".ndjson"))
+            .and_then(|name| name.split_once('-'))
+            .and_then(|(from, to)| Some((from.parse().ok()?, to.parse().ok()?)));
+        if let Some((from, to)) = range {
+            segments.push((from, to, path));
+        }
+    }
+    segments.sort();
+
+    Ok(segments)
+}
+
+/// A block range of a request
+enum Piece {
+    /// Blocks `from..to` of a segment
+    Cached(PathBuf, u64, u64),
+    /// Blocks `from..to` missing from the cache, stored once fetched
+    Missing(u64, u64),
+    /// The blocks from the first one within the margin on, never stored
+    Uncached(u64),
+}
+
+/// Splits the blocks `from..to` into the pieces covered by a segment and the
+/// gaps between them
+fn plan(segments: &[(u64, u64, PathBuf)], from: u64, to: u64) -> Vec<Piece> {
+    let mut plan = Vec::new();
+    let mut cursor = from;
+    for (segment_from, segment_to, path) in segments {
+        if *segment_to <= cursor || *segment_from >= to {
+            continue;
+        }
+        if *segment_from > cursor {
+            plan.push(Piece::Missing(cursor, *segment_from));
+            cursor = *segment_from;
+        }
+
+        let end = (*segment_to).min(to);
+        plan.push(Piece::Cached(path.clone(), cursor, end));
+        cursor = end;
+    }
+    if cursor < to {
+        plan.push(Piece::Missing(cursor, to));
+    }
+
+    plan
+}
+
+fn block_of(line: &[u8]) -> Option<u64> {
+    let record = serde_json::from_slice::<Value>(line).ok()?;
+    record
+        .get("block_number")
+        .or_else(|| record.get("height"))
+        .and_then(json_u64)
+}
+
+/// The lines of a segment within the blocks `from..to`
+fn read_segment(path: PathBuf, from: u64, to: u64) -> ResponseStream<Vec<u8>> {
+    futures::stream::once(async move { fs::read(&path) })
+        .flat_map(move |bytes| {
+            let lines = match bytes {
+                Ok(bytes) => bytes
+                    .split_inclusive(|b| *b == b'\n')
+                    .filter(|line| {
+                        block_of(line).is_none_or(|block| (from..to).contains(&block))
+                    })
+                    .map(|line| Ok(line.to_vec()))
+                    .collect(),
+                Err(err) => vec![Err(err.into())],
+            };
+            futures::stream::iter(lines)
+        })
+        .boxed()
+}
+
+/// A segment being written to a temporary file
+///
+/// The temporary file is removed unless the segment is completed, also when the
+/// response is dropped before its end.
+struct PendingSegment {
+    file: Option<BufWriter<File>>,
+    tmp: PathBuf,
+    path: PathBuf,
+}
+
+impl PendingSegment {
+    fn create(dir: &Path, path: PathBuf) -> Result<Self> {
+        let tmp = dir.join(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        let file = BufWriter::new(File::create(&tmp)?);
+
+        Ok(Self {
+            file: Some(file),
+            tmp,
+            path,
+        })
+    }
+
+    /// Writes a line, giving up on the segment if it fails, the response is
+    /// still delivered, just not cached
+    fn write(&mut self, line: &[u8]) {
+        let written = match &mut self.file {
+            Some(file) => file.write_all(line),
+            None => Ok(()),
+        };
+        if written.is_err() {
+            self.abandon();
+        }
+    }
+
+    /// Moves the temporary file in place of the segment, `None` if the
+    /// segment was abandoned
+    fn complete(&mut self) -> Option<std::io::Result<()>> {
+        let file = self.file.take()?;
+        let stored = file
+            .into_inner()
+            .map_err(|err| err.into_error())
+            .and_then(|file| file.sync_all())
+            .and_then(|_| fs::rename(&self.tmp, &self.path));
+        if stored.is_err() {
+            let _ = fs::remove_file(&self.tmp);
+        }
+
+        Some(stored)
+    }
+
+    fn abandon(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.tmp);
+        }
+    }
+}
+
+impl Drop for PendingSegment {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
+
+/// Passes the lines of a response on while writing them to a temporary file,
+/// which becomes the segment of `from..to` once the response completed
+fn store_segment<R>(
+    response: ResponseStream<Vec<u8>>,
+    dir: &Path,
+    request: &R,
+    from: u64,
+    to: u64,
+) -> Result<ResponseStream<Vec<u8>>>
+where
+    R: Encoding,
+{
+    fs::create_dir_all(dir)?;
+    let described = dir.join("request.json");
+    if !described.exists() {
+        fs::write(&described, serde_json::to_vec_pretty(&request.to_json()?)?)?;
+    }
+
+    let path = dir.join(format!("{from:012}-{to:012}.ndjson"));
+    let segment = PendingSegment::create(dir, path)?;
+
+    let lines = futures::stream::unfold(
+        (json_lines(response).fuse(), segment),
+        |(mut lines, mut segment)| async move {
+            match lines.next().await {
+                Some(Ok(mut line)) => {
+                    // error responses are never stored, like in an archive, and
+                    // the segment they cut short is abandoned
+                    if serde_json::from_slice::<ResponseError>(line.trim_ascii()).is_ok() {
+                        segment.abandon();
+                    }
+                    line.push(b'\n');
+                    segment.write(&line);
+                    Some((Ok(line), (lines, segment)))
+                }
+                Some(Err(err)) => {
+                    segment.abandon();
+                    Some((Err(err), (lines, segment)))
+                }
+                None => match segment.complete()? {
+                    Ok(()) => None,
+                    Err(err) => Some((Err(err.into()), (lines, segment))),
+                },
+            }
+        },
+    );
+
+    Ok(lines.boxed())
+}
+
+#[async_trait]
+impl<P> Provider for CacheProvider<P>
+where
+    P: Provider + Send + Sync,
+{
+    /// Connects the wrapped provider, caching in [`CacheProvider::DEFAULT_DIR`]
+    async fn try_new(
+        endpoint: String,
+        is_secure: bool,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<Self> {
+        let inner = P::try_new(endpoint, is_secure, username, password).await?;
+        Ok(Self::new(inner, Self::DEFAULT_DIR))
+    }
+
+    async fn get_status_by_format(&self, format: Format) -> StreamResponse<Vec<u8>> {
+        self.inner.get_status_by_format(format).await
+    }
+}
+
+macro_rules! impl_cache_provider {
+    (@endpoint $endpoint:literal) => {
+        Some($endpoint)
+    };
+    (@endpoint) => {
+        None
+    };
+    ($provider:ident {
+        $($method:ident($request:ty) $(=> $endpoint:literal)?),* $(,)?
+    }) => {
+        #[async_trait]
+        impl<P> $provider for CacheProvider<P>
+        where
+            P: $provider + ChainProvider + FuelProvider + BtcProvider + Send + Sync + 'static,
+        {
+            $(
+                async fn $method(
+                    &self,
+                    request: $request,
+                    format: Format,
+                    deltas: bool,
+                ) -> StreamResponse<Vec<u8>> {
+                    let endpoint: Option<&str> = impl_cache_provider!(@endpoint $($endpoint)?);
+                    match endpoint {
+                        Some(endpoint) => {
+                            let inner = self.inner.clone();
+                            self.cached(endpoint, request, format, deltas, move |request| {
+                                let inner = inner.clone();
+                                async move {
+                                    $provider::$method(&*inner, request, format, deltas).await
+                                }
+                            })
+                            .await
+                        }
+                        None => $provider::$method(&*self.inner, request, format, deltas).await,
+                    }
+                }
+            )*
+        }
+    };
+}
+
+impl_cache_provider!(ChainProvider {
+    get_blocks_by_format(blocks::GetBlocksRequest) => "blocks",
+    get_logs_by_format(logs::GetLogsRequest) => "logs",
+    get_txs_by_format(txs::GetTxsRequest) => "txs",
+    get_transfers_by_format(transfers::GetTransfersRequest) => "transfers",
+});
+
+impl_cache_provider!(UniswapV2Provider {
+    get_pairs_by_format(uniswap_v2::GetPairsRequest) => "uniswap_v2_pairs",
+    get_prices_by_format(uniswap_v2::GetPricesRequest) => "uniswap_v2_prices",
+});
+
+impl_cache_provider!(UniswapV3Provider {
+    get_fees_by_format(uniswap_v3::GetFeesRequest) => "uniswap_v3_fees",
+    get_pools_by_format(uniswap_v3::GetPoolsRequest) => "uniswap_v3_pools",
+    get_positions_by_format(uniswap_v3::GetPositionsRequest) => "uniswap_v3_positions",
+    get_prices_by_format(uniswap_v3::GetPricesRequest) => "uniswap_v3_prices",
+});
+
+impl_cache_provider!(CurveProvider {
+    get_tokens_by_format(curve::GetCrvTokenRequest) => "curve_tokens",
+    get_pools_by_format(curve::GetCrvPoolRequest) => "curve_pools",
+    get_prices_by_format(curve::GetCrvPriceRequest) => "curve_prices",
+});
+
+impl_cache_provider!(Erc20Provider {
+    get_erc20_by_format(erc20::GetErc20Request) => "erc20",
+    get_erc20_approval_by_format(erc20::GetErc20ApprovalsRequest) => "erc20_approvals",
+    get_erc20_transfers_by_format(erc20::GetErc20TransferssRequest) => "erc20_transfers",
+});
+
+impl_cache_provider!(FuelProvider {
+    get_fuel_blocks_by_format(fuel::GetFuelBlocksRequest) => "fuel_blocks",
+    get_fuel_logs_by_format(fuel::GetFuelLogsRequest) => "fuel_logs",
+    get_fuel_logs_decoded_by_format(fuel::GetFuelLogsRequest) => "fuel_logs_decoded",
+    get_fuel_txs_by_format(fuel::GetFuelTxsRequest) => "fuel_txs",
+    get_fuel_receipts_by_format(fuel::GetFuelReceiptsRequest) => "fuel_receipts",
+    get_fuel_messages_by_format(fuel::GetFuelMessagesRequest) => "fuel_messages",
+    // the unspent outputs of a range change with every later block
+    get_fuel_unspent_utxos_by_format(fuel::GetUtxoRequest),
+    get_fuel_spark_markets_by_format(fuel::GetSparkMarketRequest) => "fuel_spark_markets",
+    get_fuel_spark_orders_by_format(fuel::GetSparkOrderRequest) => "fuel_spark_orders",
+    get_fuel_src20_by_format(fuel::GetSrc20) => "fuel_src20",
+    get_fuel_src7_by_format(fuel::GetSrc7) => "fuel_src7",
+    get_fuel_mira_v1_pools_by_format(mira::GetMiraPoolsRequest) => "fuel_mira_v1_pools",
+    get_fuel_mira_v1_liquidity_by_format(mira::GetMiraLiquidityRequest) => "fuel_mira_v1_liquidity",
+    get_fuel_mira_v1_swaps_by_format(mira::GetMiraSwapsRequest) => "fuel_mira_v1_swaps",
+});
+
+impl_cache_provider!(BtcProvider {
+    get_btc_blocks_by_format(btc::GetBtcBlocksRequest) => "btc_blocks",
+    get_btc_txs_by_format(btc::GetBtcTxsRequest) => "btc_txs",
+});
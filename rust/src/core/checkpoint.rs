@@ -0,0 +1,298 @@
+//! Durable progress tracking for stream consumers
+//!
+//! A [`Job`] names a consumer and remembers, in a [`CheckpointStore`], the last
+//! block it fully processed. Progress is only recorded when the consumer
+//! acknowledges a block with [`Job::ack`], never on receipt, so after a crash
+//! the blocks that were in flight are delivered again (at-least-once).
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use futures::StreamExt;
+//! use pangea_client::{
+//!     checkpoint::{Checkpointed, FileCheckpointStore, Job},
+//!     requests::logs::GetLogsRequest,
+//!     ClientBuilder, WsProvider,
+//! };
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = ClientBuilder::default().build::<WsProvider>().await?;
+//! let job = Job::new("usdc-logs", Arc::new(FileCheckpointStore::new("checkpoints.json")));
+//!
+//! let mut stream = client
+//!     .resume::<_, serde_json::Value>(&job, GetLogsRequest::default())
+//!     .await?;
+//! while let Some(event) = stream.next().await {
+//!     match event? {
+//!         Checkpointed::Record(log) => println!("{log}"),
+//!         Checkpointed::BlockEnd(block_number) => job.ack(block_number).await?,
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+
+use super::{
+    error::Result, provider::ResponseStream, requests::BlockRange, stream::Position,
+    types::query::Bound,
+};
+
+/// Persists the last fully processed block of named jobs
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Returns the last block `job` fully processed, if it processed any
+    async fn load(&self, job: &str) -> Result<Option<u64>>;
+
+    /// Records `block_number` as the last block `job` fully processed
+    async fn commit(&self, job: &str, block_number: u64) -> Result<()>;
+}
+
+/// A consumer whose progress is kept in a [`CheckpointStore`]
+#[derive(Clone)]
+pub struct Job {
+    name: String,
+    store: Arc<dyn CheckpointStore>,
+}
+
+impl Job {
+    pub fn new(name: impl Into<String>, store: Arc<dyn CheckpointStore>) -> Self {
+        Self {
+            name: name.into(),
+            store,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The last block the job fully processed
+    pub async fn checkpoint(&self) -> Result<Option<u64>> {
+        self.store.load(&self.name).await
+    }
+
+    /// Acknowledges that every record up to and including `block_number` was
+    /// processed
+    pub async fn ack(&self, block_number: u64) -> Result<()> {
+        self.store.commit(&self.name, block_number).await
+    }
+
+    /// Moves the start of `request` to the block after the checkpoint, if the
+    /// job has one
+    pub async fn resume<R>(&self, mut request: R) -> Result<R>
+    where
+        R: BlockRange,
+    {
+        if let Some(block_number) = self.checkpoint().await? {
+            request.set_from_block(Bound::Exact(block_number as i64 + 1));
+        }
+
+        Ok(request)
+    }
+}
+
+/// An item of a stream returned by [`Client::resume`](crate::Client::resume)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Checkpointed<V> {
+    /// A record of the stream
+    Record(V),
+    /// Every record of this block has been delivered, acknowledge it with
+    /// [`Job::ack`] once they are processed
+    BlockEnd(u64),
+}
+
+struct Blocks {
+    records: ResponseStream<Value>,
+    current: Option<u64>,
+    pending: Option<Value>,
+    done: bool,
+}
+
+/// Interleaves [`Checkpointed::BlockEnd`] markers into a stream of JSON records
+/// ordered by block
+pub(crate) fn checkpointed<V>(records: ResponseStream<Value>) -> ResponseStream<Checkpointed<V>>
+where
+    V: DeserializeOwned + Send + 'static,
+{
+    let state = Blocks {
+        records,
+        current: None,
+        pending: None,
+        done: false,
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        let record = match state.pending.take() {
+            Some(record) => record,
+            None if state.done => return None,
+            None => match state.records.next().await {
+                Some(Ok(record)) => record,
+                Some(Err(err)) => return Some((Err(err), state)),
+                None => {
+                    state.done = true;
+                    let block_number = state.current.take()?;
+                    return Some((Ok(Checkpointed::BlockEnd(block_number)), state));
+                }
+            },
+        };
+
+        if let Some(position) = Position::of(&record) {
+            match state.current {
+                Some(current) if current < position.block_number => {
+                    state.current = Some(position.block_number);
+                    state.pending = Some(record);
+                    return Some((Ok(Checkpointed::BlockEnd(current)), state));
+                }
+                Some(_) => {}
+                None => state.current = Some(position.block_number),
+            }
+        }
+
+        let record = serde_json::from_value::<V>(record)
+            .map(Checkpointed::Record)
+            .map_err(Into::into);
+        Some((record, state))
+    })
+    .boxed()
+}
+
+/// Keeps checkpoints of all jobs in a single JSON file
+///
+/// The file is replaced atomically on every commit, so it is never left
+/// half-written by a crash.
+pub struct FileCheckpointStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileCheckpointStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    async fn read(&self) -> Result<BTreeMap<String, u64>> {
+        match fs::read(&self.path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self, job: &str) -> Result<Option<u64>> {
+        let _guard = self.lock.lock().await;
+        Ok(self.read().await?.get(job).copied())
+    }
+
+    async fn commit(&self, job: &str, block_number: u64) -> Result<()> {
+        let _guard = self.lock.lock().await;
+
+        let mut checkpoints = self.read().await?;
+        checkpoints.insert(job.to_string(), block_number);
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut file = fs::File::create(&tmp).await?;
+        file.write_all(&serde_json::to_vec_pretty(&checkpoints)?).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, &self.path).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteCheckpointStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::{
+        path::Path,
+        sync::{Mutex, PoisonError},
+    };
+
+    use async_trait::async_trait;
+    use rusqlite::{Connection, OptionalExtension};
+
+    use super::CheckpointStore;
+    use crate::core::error::Result;
+
+    const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS pangea_checkpoints (
+        job TEXT PRIMARY KEY NOT NULL,
+        block_number INTEGER NOT NULL
+    )";
+
+    /// Keeps checkpoints in the `pangea_checkpoints` table of an SQLite database
+    pub struct SqliteCheckpointStore {
+        conn: Mutex<Connection>,
+    }
+
+    impl SqliteCheckpointStore {
+        /// Opens or creates the database at `path`
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            Self::from_connection(Connection::open(path)?)
+        }
+
+        pub fn from_connection(conn: Connection) -> Result<Self> {
+            Self::create_table(&conn)?;
+            Ok(Self {
+                conn: Mutex::new(conn),
+            })
+        }
+
+        /// Creates the checkpoint table, if it does not exist yet
+        pub fn create_table(conn: &Connection) -> Result<()> {
+            conn.execute(CREATE_TABLE, [])?;
+            Ok(())
+        }
+
+        /// Records the checkpoint of `job` as part of a larger transaction,
+        /// e.g. together with the records that were processed
+        pub fn commit_in(conn: &Connection, job: &str, block_number: u64) -> Result<()> {
+            conn.execute(
+                "INSERT INTO pangea_checkpoints (job, block_number) VALUES (?1, ?2)
+                 ON CONFLICT (job) DO UPDATE SET block_number = excluded.block_number",
+                (job, block_number as i64),
+            )?;
+            Ok(())
+        }
+
+        /// Reads the checkpoint of `job` on an existing connection
+        pub fn load_in(conn: &Connection, job: &str) -> Result<Option<u64>> {
+            let block_number = conn
+                .query_row(
+                    "SELECT block_number FROM pangea_checkpoints WHERE job = ?1",
+                    [job],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?;
+            Ok(block_number.map(|n| n as u64))
+        }
+    }
+
+    #[async_trait]
+    impl CheckpointStore for SqliteCheckpointStore {
+        async fn load(&self, job: &str) -> Result<Option<u64>> {
+            let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+            Self::load_in(&conn, job)
+        }
+
+        async fn commit(&self, job: &str, block_number: u64) -> Result<()> {
+            let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+            Self::commit_in(&conn, job, block_number)
+        }
+    }
+}
@@ -1,21 +1,48 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    sync::{atomic::AtomicBool, Arc, PoisonError, RwLock},
+};
+
 use async_trait::async_trait;
+use ethers_core::{
+    abi::{Abi, Detokenize},
+    types::H256,
+};
 use futures::StreamExt;
+use serde::de::DeserializeOwned;
 
 use super::{
-    error::ResponseError,
+    abi::{DecodedLog, EventDecoder},
+    checkpoint::{checkpointed, Checkpointed, Job},
+    enrich::{Context, Enriched, Needed},
+    error::{Error, ResponseError, Result},
+    multichain::{merge, tagged, ChainEvent, Heads},
+    plan::{union, AnyOf},
     provider::{
         BtcProvider, ChainProvider, CurveProvider, Erc20Provider, FuelProvider, Provider,
-        StreamResponse, UniswapV2Provider, UniswapV3Provider,
+        RequestProvider, StreamResponse, UniswapV2Provider, UniswapV3Provider,
     },
     requests::{
         blocks, btc, curve, erc20, fuel, logs, mira, transfers, txs, uniswap_v2, uniswap_v3,
+        Batch, BlockRange,
     },
-    types::{format::Format, status::Status},
+    stream::{detach, json_lines, json_records, merge_ordered, stitch, Position},
+    sway::{DecodedFuelLog, SwayAbi},
+    timestamps::{BlockTime, BlockTimes},
+    types::{format::Format, query::Bound, status::Status, ChainId},
 };
 use crate::{Operation, WsProvider};
 
+/// Default largest number of entries sent in one `__in` filter, see
+/// [`Client::with_batch_size`]
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
 pub struct Client<T> {
     pub inner: T,
+    block_times: BlockTimes,
+    events: RwLock<EventDecoder>,
+    batch_size: usize,
 }
 
 impl<T> Client<T>
@@ -23,7 +50,12 @@ where
     T: Provider,
 {
     pub fn new(inner: T) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            block_times: BlockTimes::default(),
+            events: RwLock::default(),
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
     }
 
     pub async fn get_status(&self) -> StreamResponse<Status> {
@@ -40,6 +72,217 @@ where
     }
 }
 
+impl<T> Client<T> {
+    /// Sets the largest number of entries sent in one `__in` filter
+    ///
+    /// Larger sets are split over several requests, see [`Batch`]. The records
+    /// of bounded requests are merged in block order, those of requests
+    /// following the chain live are delivered as they arrive, a quiet batch
+    /// would hold back the others otherwise. Only [`Format::JsonStream`]
+    /// responses can be merged, requests in other formats that need several
+    /// batches fail with [`Error::UnbatchableFormat`]. Zero disables splitting.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Registers the events of `abi` to decode logs with, see
+    /// [`Client::decode_logs`]
+    pub fn register_abi(&self, abi: &Abi) {
+        self.events
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .add_abi(abi);
+    }
+
+    /// Registers human-readable event signatures to decode logs with, see
+    /// [`Client::decode_logs`]
+    pub fn register_events(&self, signatures: &[&str]) -> Result<()> {
+        self.events
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .add_signatures(signatures)
+    }
+
+    /// Catches up on `request` from its `from_block` to the chain head, then
+    /// keeps following the chain live, as one ordered stream of records
+    ///
+    /// The live subscription is opened before the backfill, so there is no gap
+    /// between the two. Records delivered by both are dropped from the live
+    /// side by their block number, transaction and log index. The `to_block`
+    /// of `request` is ignored.
+    pub async fn follow<R, V>(&self, request: R) -> StreamResponse<V>
+    where
+        Self: RequestProvider<R>,
+        R: BlockRange + Clone + Send,
+        V: DeserializeOwned + Send + 'static,
+    {
+        let records = self
+            .follow_json(request, Arc::default())
+            .await?
+            .map(|record| record.and_then(|record| Ok(serde_json::from_value::<V>(record)?)))
+            .boxed();
+
+        Ok(records)
+    }
+
+    /// Streams `request` from the block after the checkpoint of `job`,
+    /// marking the end of every block so the consumer can acknowledge it
+    ///
+    /// Requests ending in [`Bound::Subscribe`] are resumed with
+    /// [`Client::follow`], so they continue live after catching up.
+    pub async fn resume<R, V>(&self, job: &Job, request: R) -> StreamResponse<Checkpointed<V>>
+    where
+        Self: RequestProvider<R>,
+        R: BlockRange + Clone + Send,
+        V: DeserializeOwned + Send + 'static,
+    {
+        let request = job.resume(request).await?;
+        let records = self.ordered(request).await?;
+
+        Ok(checkpointed(records))
+    }
+
+    /// Streams the records of every request of `query`, each record once
+    ///
+    /// Every request is sent on its own, see [`plan`](crate::plan). Requests
+    /// ending in [`Bound::Subscribe`] are streamed with [`Client::follow`].
+    pub async fn get_any<R, V>(&self, query: impl Into<AnyOf<R>>) -> StreamResponse<V>
+    where
+        Self: RequestProvider<R>,
+        R: BlockRange + Clone + Send,
+        V: DeserializeOwned + Send + 'static,
+    {
+        let branches = query.into().into_branches();
+        let mut streams = Vec::with_capacity(branches.len());
+        for request in branches {
+            streams.push(detach(self.ordered(request).await?));
+        }
+
+        let records = union(streams)
+            .map(|record| record.and_then(|record| Ok(serde_json::from_value::<V>(record)?)))
+            .boxed();
+
+        Ok(records)
+    }
+
+    /// Streams `request` as JSON records in block order, following the chain
+    /// live if it ends in [`Bound::Subscribe`]
+    async fn ordered<R>(&self, request: R) -> StreamResponse<serde_json::Value>
+    where
+        Self: RequestProvider<R>,
+        R: BlockRange + Clone + Send,
+    {
+        match request.block_range() {
+            (_, Bound::Subscribe) => self.follow(request).await,
+            _ => {
+                let raw_data_stream = self
+                    .get_by_format(request, Format::JsonStream, false)
+                    .await?;
+                Ok(json_records(raw_data_stream))
+            }
+        }
+    }
+
+    /// Like [`Client::follow`], as JSON records, setting `caught_up` once the
+    /// backfill has been delivered
+    async fn follow_json<R>(
+        &self,
+        request: R,
+        caught_up: Arc<AtomicBool>,
+    ) -> StreamResponse<serde_json::Value>
+    where
+        Self: RequestProvider<R>,
+        R: BlockRange + Clone + Send,
+    {
+        let mut live = request.clone();
+        live.set_from_block(Bound::Latest);
+        live.set_to_block(Bound::Subscribe);
+
+        let mut backfill = request;
+        backfill.set_to_block(Bound::Latest);
+
+        let live = self.get_by_format(live, Format::JsonStream, false).await?;
+        let live = detach(json_lines(live));
+        let backfill = self
+            .get_by_format(backfill, Format::JsonStream, false)
+            .await?;
+
+        Ok(stitch(json_lines(backfill), live, caught_up))
+    }
+}
+
+impl<T> Client<T>
+where
+    T: Send + Sync,
+{
+    /// Resolves the client-side bounds of `request` that need no blocks, for
+    /// providers without a blocks endpoint
+    async fn resolve_without_blocks<R>(&self, request: &mut R) -> Result<()>
+    where
+        R: BlockRange,
+    {
+        let (from_block, to_block) = request.block_range();
+        for bound in [from_block, to_block] {
+            if matches!(bound, Bound::Timestamp(_) | Bound::FromLatestDuration(_)) {
+                return Err(Error::UnresolvedBound(bound));
+            }
+        }
+
+        self.block_times
+            .resolve_request(request, |_, bound| async move {
+                Err(Error::UnresolvedBound(bound))
+            })
+            .await
+    }
+
+    /// Sends `request` with `send`, split over several requests if one of its
+    /// `__in` sets is larger than the batch size
+    async fn batched<R, F, Fut>(
+        &self,
+        request: R,
+        format: Format,
+        send: F,
+    ) -> StreamResponse<Vec<u8>>
+    where
+        R: Batch + BlockRange + Clone + Send,
+        F: Fn(R) -> Fut + Send + Sync,
+        Fut: Future<Output = StreamResponse<Vec<u8>>> + Send,
+    {
+        let is_live = matches!(request.block_range(), (_, Bound::Subscribe));
+        let mut batches = request.split(self.batch_size)?;
+        if batches.len() == 1 {
+            return send(batches.remove(0)).await;
+        }
+
+        // only JSON lines can be interleaved back into one response
+        if !matches!(format, Format::JsonStream) {
+            return Err(Error::UnbatchableFormat(batches.len()));
+        }
+
+        let mut streams = Vec::with_capacity(batches.len());
+        for batch in batches {
+            streams.push(detach(send(batch).await?));
+        }
+
+        // waiting for every live batch to move past a block would stall on
+        // the quietest one
+        if is_live {
+            let lines = futures::stream::select_all(streams.into_iter().map(json_lines))
+                .map(|line| {
+                    line.map(|mut line| {
+                        line.push(b'\n');
+                        line
+                    })
+                })
+                .boxed();
+            return Ok(lines);
+        }
+
+        Ok(merge_ordered(streams))
+    }
+}
+
 impl Client<WsProvider> {
     pub async fn raw_request(
         &self,
@@ -52,6 +295,388 @@ impl Client<WsProvider> {
     }
 }
 
+impl<T> Client<T>
+where
+    T: ChainProvider + Send + Sync,
+{
+    /// Splits `request` into one request per chain and merges their records,
+    /// keeping the block order of every chain and marking its progress with
+    /// watermarks
+    ///
+    /// Watermarks follow the records of a chain and its block heads, so a
+    /// chain without matching records still advances. A bounded request marks
+    /// every chain up to the head it had when the request was sent once its
+    /// records have ended. Like [`Client::resume`], requests ending in
+    /// [`Bound::Subscribe`] are streamed with [`Client::follow`], and once
+    /// caught up a chain is marked up to the block before every new head while
+    /// no record of it is waiting.
+    pub async fn fan_out<R, V>(&self, request: R) -> StreamResponse<ChainEvent<V>>
+    where
+        Self: RequestProvider<R>,
+        R: BlockRange + Clone + Send,
+        V: DeserializeOwned + Send + 'static,
+    {
+        let mut streams = Vec::with_capacity(request.chains().len());
+        for chain in request.chains().clone() {
+            let mut single = request.clone();
+            single.set_chains(HashSet::from([chain]));
+
+            let stream = match single.block_range() {
+                (_, Bound::Subscribe) => {
+                    // heads are subscribed to first, so none is missed
+                    let heads = detach(self.heads(chain, Bound::Subscribe).await?);
+                    let caught_up = Arc::<AtomicBool>::default();
+                    let records = self.follow_json(single, caught_up.clone()).await?;
+                    tagged(chain, records, Heads { heads, caught_up })
+                }
+                _ => {
+                    let head = self.heads(chain, Bound::Latest).await?.next().await;
+                    let heads = futures::stream::iter(head).boxed();
+                    let records = self.ordered(single).await?;
+                    let caught_up = Arc::default();
+                    tagged(chain, records, Heads { heads, caught_up })
+                }
+            };
+            streams.push(stream);
+        }
+
+        Ok(merge(streams))
+    }
+
+    /// Streams the logs of `request` joined with the timestamp and base fee of
+    /// their block and the sender, recipient and value of their transaction
+    ///
+    /// The logs are enriched in windows of up to `window` logs, see
+    /// [`enrich`](crate::enrich). The logs are read ahead of the consumer
+    /// while the blocks and transactions of a window are fetched.
+    pub async fn enrich_logs<V>(
+        self: &Arc<Self>,
+        request: logs::GetLogsRequest,
+        window: usize,
+    ) -> StreamResponse<Enriched<V>>
+    where
+        T: 'static,
+        V: DeserializeOwned + Send + 'static,
+    {
+        let client = self.clone();
+        let chains = request.chains.clone();
+        // read on while a window is enriched, the responses of its blocks and
+        // transactions may share the connection of the logs
+        let logs = detach(self.ordered(request).await?)
+            .ready_chunks(window.max(1))
+            .then(move |logs| {
+                let client = client.clone();
+                let chains = chains.clone();
+                async move { client.enrich_window(&chains, logs).await }
+            })
+            .flat_map(futures::stream::iter)
+            .boxed();
+
+        Ok(logs)
+    }
+
+    /// Enriches the logs of a window, up to the first error
+    async fn enrich_window<V>(
+        &self,
+        chains: &HashSet<ChainId>,
+        logs: Vec<Result<serde_json::Value>>,
+    ) -> Vec<Result<Enriched<V>>>
+    where
+        V: DeserializeOwned,
+    {
+        let mut records = Vec::with_capacity(logs.len());
+        let mut error = None;
+        for log in logs {
+            match log {
+                Ok(log) => records.push(log),
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            }
+        }
+
+        let context = match self.fetch_context(chains, &records).await {
+            Ok(context) => context,
+            Err(err) => return vec![Err(err)],
+        };
+        records
+            .into_iter()
+            .map(|log| context.enrich(log))
+            .chain(error.map(Err))
+            .collect()
+    }
+
+    /// Fetches the blocks and transactions `logs` were emitted in, logs
+    /// without a chain belong to any of `chains`
+    async fn fetch_context(
+        &self,
+        chains: &HashSet<ChainId>,
+        logs: &[serde_json::Value],
+    ) -> Result<Context> {
+        let mut context = Context::default();
+        for (chain, needed) in Needed::of(logs) {
+            let chains = chain.map_or_else(|| chains.clone(), |chain| HashSet::from([chain]));
+
+            for (from, to) in needed.blocks {
+                let request = blocks::GetBlocksRequest {
+                    chains: chains.clone(),
+                    from_block: Bound::Exact(from as i64),
+                    to_block: Bound::Exact(to as i64),
+                    ..Default::default()
+                };
+                let mut blocks = self.ordered(request).await?;
+                while let Some(block) = blocks.next().await {
+                    context.add_block(&block?);
+                }
+            }
+
+            let (from, to) = needed.transactions_range;
+            let request = txs::GetTxsRequest {
+                chains,
+                from_block: Bound::Exact(from as i64),
+                to_block: Bound::Exact(to as i64),
+                hash__in: needed.transaction_hashes.unwrap_or_default(),
+                ..Default::default()
+            };
+            let mut txs = self.ordered(request).await?;
+            while let Some(tx) = txs.next().await {
+                context.add_transaction(&tx?);
+            }
+        }
+
+        Ok(context)
+    }
+
+    /// Streams the logs of `request` decoded with the registered events
+    ///
+    /// If `topic0__in` is empty it is set to the registered events. Logs of
+    /// events that are not registered are skipped.
+    pub async fn decode_logs(
+        &self,
+        mut request: logs::GetLogsRequest,
+    ) -> StreamResponse<DecodedLog> {
+        let decoder = self
+            .events
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if request.topic0__in.is_empty() {
+            request.topic0__in = decoder.topics0();
+        }
+
+        let records = self
+            .ordered(request)
+            .await?
+            .filter_map(move |record| {
+                let log = record.and_then(|record| decoder.decode(record)).transpose();
+                futures::future::ready(log)
+            })
+            .boxed();
+
+        Ok(records)
+    }
+
+    /// Like [`Client::decode_logs`], with the parameters of every log converted
+    /// into `V`
+    pub async fn decode_logs_as<V>(
+        &self,
+        request: logs::GetLogsRequest,
+    ) -> StreamResponse<DecodedLog<V>>
+    where
+        V: Detokenize + Send + 'static,
+    {
+        let records = self
+            .decode_logs(request)
+            .await?
+            .map(|log| log.and_then(DecodedLog::detokenize))
+            .boxed();
+
+        Ok(records)
+    }
+
+    /// Fetches the transaction with hash `hash` on `chain`, `None` if it does
+    /// not exist
+    pub async fn get_tx_by_hash<V>(&self, chain: ChainId, hash: H256) -> Result<Option<V>>
+    where
+        V: DeserializeOwned + Send + 'static,
+    {
+        self.get_tx_by_hash_from(chain, hash, Bound::Exact(0)).await
+    }
+
+    /// Like [`Client::get_tx_by_hash`], looking only at blocks from
+    /// `from_block` on, e.g. a [`Bound::Timestamp`] of when the transaction
+    /// was sent
+    pub async fn get_tx_by_hash_from<V>(
+        &self,
+        chain: ChainId,
+        hash: H256,
+        from_block: Bound,
+    ) -> Result<Option<V>>
+    where
+        V: DeserializeOwned + Send + 'static,
+    {
+        let request = txs::GetTxsRequest {
+            chains: HashSet::from([chain]),
+            from_block,
+            to_block: Bound::Latest,
+            hash__in: HashSet::from([hash]),
+            ..Default::default()
+        };
+        let stream = self
+            .get_txs_by_format(request, Format::JsonStream, false)
+            .await?;
+
+        json_records::<V>(stream).next().await.transpose()
+    }
+
+    /// Streams the numbers of the blocks of `chain` from its head on, up to
+    /// `to_block`
+    async fn heads(&self, chain: ChainId, to_block: Bound) -> StreamResponse<u64> {
+        let request = blocks::GetBlocksRequest {
+            chains: HashSet::from([chain]),
+            from_block: Bound::Latest,
+            to_block,
+            ..Default::default()
+        };
+        let stream = self
+            .inner
+            .get_blocks_by_format(request, Format::JsonStream, false)
+            .await?;
+
+        let heads = json_records::<serde_json::Value>(stream)
+            .filter_map(|block| async move {
+                match block {
+                    Ok(block) => Position::of(&block).map(|head| Ok(head.block_number)),
+                    Err(err) => Some(Err(err)),
+                }
+            })
+            .boxed();
+
+        Ok(heads)
+    }
+
+    async fn block_time(&self, chain: ChainId, bound: Bound) -> Result<Option<BlockTime>> {
+        let request = blocks::GetBlocksRequest {
+            chains: HashSet::from([chain]),
+            from_block: bound,
+            to_block: next_block(bound),
+            ..Default::default()
+        };
+        let stream = self
+            .inner
+            .get_blocks_by_format(request, Format::JsonStream, false)
+            .await?;
+
+        json_records::<BlockTime>(stream).next().await.transpose()
+    }
+}
+
+impl<T> Client<T>
+where
+    T: FuelProvider + Send + Sync,
+{
+    /// Streams the `LogData` receipts of `request` decoded locally with the
+    /// JSON ABI of a Sway contract
+    ///
+    /// If `rb__in` is empty it is set to the log ids of `abi`. Receipts with an
+    /// unknown log id are skipped. Log ids are only unique within a contract,
+    /// so `id__in` should select the contract `abi` belongs to.
+    pub async fn decode_fuel_logs(
+        &self,
+        mut request: fuel::GetFuelLogsRequest,
+        abi: SwayAbi,
+    ) -> StreamResponse<DecodedFuelLog> {
+        if request.rb__in.is_empty() {
+            request.rb__in = abi.log_ids().collect();
+        }
+
+        let records = self
+            .ordered(request)
+            .await?
+            .filter_map(move |record| {
+                let log = record.and_then(|record| abi.decode_log(record)).transpose();
+                futures::future::ready(log)
+            })
+            .boxed();
+
+        Ok(records)
+    }
+
+    async fn fuel_block_time(&self, chain: ChainId, bound: Bound) -> Result<Option<BlockTime>> {
+        let request = fuel::GetFuelBlocksRequest {
+            chains: HashSet::from([chain]),
+            from_block: bound,
+            to_block: next_block(bound),
+            ..Default::default()
+        };
+        let stream = self
+            .inner
+            .get_fuel_blocks_by_format(request, Format::JsonStream, false)
+            .await?;
+
+        json_records::<BlockTime>(stream).next().await.transpose()
+    }
+}
+
+/// The exclusive upper bound selecting only the block at `bound`
+fn next_block(bound: Bound) -> Bound {
+    match bound {
+        Bound::Exact(n) => Bound::Exact(n + 1),
+        bound => bound,
+    }
+}
+
+/// Resolves the client-side bounds of a request into block heights, see
+/// [`Bound::is_client_side`]
+///
+/// Implemented by clients whose provider serves the blocks of the chain. Chain
+/// and Fuel requests are resolved when sent, the Uniswap, Curve and ERC20
+/// endpoints only resolve [`Bound::Finalized`] and [`Bound::Safe`] themselves,
+/// as their providers need not serve blocks. Their requests with a
+/// [`Bound::Timestamp`] or [`Bound::FromLatestDuration`] have to be resolved
+/// with this first.
+///
+/// ```no_run
+/// use pangea_client::{
+///     provider::UniswapV2Provider, query::Bound, requests::uniswap_v2::GetPricesRequest,
+///     ChainId, ClientBuilder, Format, ResolveBounds, WsProvider,
+/// };
+///
+/// # async fn run() -> pangea_client::Result<()> {
+/// let client = ClientBuilder::default().build::<WsProvider>().await?;
+/// let mut request = GetPricesRequest {
+///     chains: [ChainId::ETH].into(),
+///     from_block: Bound::Timestamp(1_700_000_000),
+///     ..Default::default()
+/// };
+/// client.resolve_bounds(&mut request).await?;
+/// let prices = client.get_prices_by_format(request, Format::JsonStream, false).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait ResolveBounds {
+    async fn resolve_bounds<R>(&self, request: &mut R) -> Result<()>
+    where
+        R: BlockRange + Send;
+}
+
+#[async_trait]
+impl<T> ResolveBounds for Client<T>
+where
+    T: ChainProvider + Send + Sync,
+{
+    async fn resolve_bounds<R>(&self, request: &mut R) -> Result<()>
+    where
+        R: BlockRange + Send,
+    {
+        self.block_times
+            .resolve_request(request, |chain, bound| self.block_time(chain, bound))
+            .await
+    }
+}
+
 #[async_trait]
 impl<T> ChainProvider for Client<T>
 where
@@ -59,42 +684,66 @@ where
 {
     async fn get_blocks_by_format(
         &self,
-        request: blocks::GetBlocksRequest,
+        mut request: blocks::GetBlocksRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_blocks_by_format(request, format, deltas)
-            .await
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| self.block_time(chain, bound))
+            .await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_blocks_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_logs_by_format(
         &self,
-        request: logs::GetLogsRequest,
+        mut request: logs::GetLogsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner.get_logs_by_format(request, format, deltas).await
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| self.block_time(chain, bound))
+            .await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_logs_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_txs_by_format(
         &self,
-        request: txs::GetTxsRequest,
+        mut request: txs::GetTxsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner.get_txs_by_format(request, format, deltas).await
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| self.block_time(chain, bound))
+            .await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_txs_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_transfers_by_format(
         &self,
-        request: transfers::GetTransfersRequest,
+        mut request: transfers::GetTransfersRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_transfers_by_format(request, format, deltas)
-            .await
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| self.block_time(chain, bound))
+            .await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_transfers_by_format(request, format, deltas)
+        })
+        .await
     }
 }
 
@@ -105,24 +754,30 @@ where
 {
     async fn get_pairs_by_format(
         &self,
-        request: uniswap_v2::GetPairsRequest,
+        mut request: uniswap_v2::GetPairsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_pairs_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_pairs_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_prices_by_format(
         &self,
-        request: uniswap_v2::GetPricesRequest,
+        mut request: uniswap_v2::GetPricesRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_prices_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_prices_by_format(request, format, deltas)
+        })
+        .await
     }
 }
 
@@ -133,44 +788,55 @@ where
 {
     async fn get_fees_by_format(
         &self,
-        request: uniswap_v3::GetFeesRequest,
+        mut request: uniswap_v3::GetFeesRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
+        self.resolve_without_blocks(&mut request).await?;
+
         self.inner.get_fees_by_format(request, format, deltas).await
     }
 
     async fn get_pools_by_format(
         &self,
-        request: uniswap_v3::GetPoolsRequest,
+        mut request: uniswap_v3::GetPoolsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_pools_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_pools_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_prices_by_format(
         &self,
-        request: uniswap_v3::GetPricesRequest,
+        mut request: uniswap_v3::GetPricesRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_prices_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_prices_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_positions_by_format(
         &self,
-        request: uniswap_v3::GetPositionsRequest,
+        mut request: uniswap_v3::GetPositionsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_positions_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_positions_by_format(request, format, deltas)
+        })
+        .await
     }
 }
 
@@ -181,35 +847,44 @@ where
 {
     async fn get_tokens_by_format(
         &self,
-        request: curve::GetCrvTokenRequest,
+        mut request: curve::GetCrvTokenRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_tokens_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_tokens_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_pools_by_format(
         &self,
-        request: curve::GetCrvPoolRequest,
+        mut request: curve::GetCrvPoolRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_pools_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_pools_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_prices_by_format(
         &self,
-        request: curve::GetCrvPriceRequest,
+        mut request: curve::GetCrvPriceRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_prices_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_prices_by_format(request, format, deltas)
+        })
+        .await
     }
 }
 
@@ -220,35 +895,45 @@ where
 {
     async fn get_erc20_by_format(
         &self,
-        request: erc20::GetErc20Request,
+        mut request: erc20::GetErc20Request,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_erc20_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_erc20_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_erc20_approval_by_format(
         &self,
-        request: erc20::GetErc20ApprovalsRequest,
+        mut request: erc20::GetErc20ApprovalsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_erc20_approval_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner.get_erc20_approval_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_erc20_transfers_by_format(
         &self,
-        request: erc20::GetErc20TransferssRequest,
+        mut request: erc20::GetErc20TransferssRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
-        self.inner
-            .get_erc20_transfers_by_format(request, format, deltas)
-            .await
+        self.resolve_without_blocks(&mut request).await?;
+
+        self.batched(request, format, |request| {
+            self.inner
+                .get_erc20_transfers_by_format(request, format, deltas)
+        })
+        .await
     }
 }
 
@@ -259,11 +944,16 @@ where
 {
     async fn get_fuel_blocks_by_format(
         &self,
-        request: fuel::GetFuelBlocksRequest,
+        mut request: fuel::GetFuelBlocksRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
         self.inner
             .get_fuel_blocks_by_format(request, format, deltas)
@@ -272,171 +962,249 @@ where
 
     async fn get_fuel_logs_by_format(
         &self,
-        request: fuel::GetFuelLogsRequest,
+        mut request: fuel::GetFuelLogsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_logs_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_logs_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_logs_decoded_by_format(
         &self,
-        request: fuel::GetFuelLogsRequest,
+        mut request: fuel::GetFuelLogsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_logs_decoded_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_logs_decoded_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_txs_by_format(
         &self,
-        request: fuel::GetFuelTxsRequest,
+        mut request: fuel::GetFuelTxsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_txs_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_txs_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_receipts_by_format(
         &self,
-        request: fuel::GetFuelReceiptsRequest,
+        mut request: fuel::GetFuelReceiptsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_receipts_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_receipts_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_messages_by_format(
         &self,
-        request: fuel::GetFuelMessagesRequest,
+        mut request: fuel::GetFuelMessagesRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_messages_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_messages_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_unspent_utxos_by_format(
         &self,
-        request: fuel::GetUtxoRequest,
+        mut request: fuel::GetUtxoRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_unspent_utxos_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_unspent_utxos_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_spark_markets_by_format(
         &self,
-        request: fuel::GetSparkMarketRequest,
+        mut request: fuel::GetSparkMarketRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_spark_markets_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_spark_markets_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_spark_orders_by_format(
         &self,
-        request: fuel::GetSparkOrderRequest,
+        mut request: fuel::GetSparkOrderRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_spark_orders_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_spark_orders_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_src20_by_format(
         &self,
-        request: fuel::GetSrc20,
+        mut request: fuel::GetSrc20,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_src20_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_src20_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_src7_by_format(
         &self,
-        request: fuel::GetSrc7,
+        mut request: fuel::GetSrc7,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_src7_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_src7_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_mira_v1_pools_by_format(
         &self,
-        request: mira::GetMiraPoolsRequest,
+        mut request: mira::GetMiraPoolsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_mira_v1_pools_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_mira_v1_pools_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_mira_v1_liquidity_by_format(
         &self,
-        request: mira::GetMiraLiquidityRequest,
+        mut request: mira::GetMiraLiquidityRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_mira_v1_liquidity_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_mira_v1_liquidity_by_format(request, format, deltas)
+        })
+        .await
     }
 
     async fn get_fuel_mira_v1_swaps_by_format(
         &self,
-        request: mira::GetMiraSwapsRequest,
+        mut request: mira::GetMiraSwapsRequest,
         format: Format,
         deltas: bool,
     ) -> StreamResponse<Vec<u8>> {
         self.check_chain(&request.chains)?;
+        self.block_times
+            .resolve_request(&mut request, |chain, bound| {
+                self.fuel_block_time(chain, bound)
+            })
+            .await?;
 
-        self.inner
-            .get_fuel_mira_v1_swaps_by_format(request, format, deltas)
-            .await
+        self.batched(request, format, |request| {
+            self.inner.get_fuel_mira_v1_swaps_by_format(request, format, deltas)
+        })
+        .await
     }
 }
 
@@ -0,0 +1,222 @@
+//! Joins of log streams with the blocks and transactions they belong to
+//!
+//! [`Client::enrich_logs`](crate::Client::enrich_logs) streams the logs of a
+//! request and attaches to every log the timestamp and base fee of its block
+//! and the sender, recipient and value of its transaction.
+//!
+//! The logs are enriched in windows of consecutive logs. For every window only
+//! the blocks the logs were emitted in and the transactions with their hashes
+//! are requested, so the extra requests grow with the number of logs, not with
+//! the number of blocks and transactions in the range. They are read whole
+//! before the logs of the window are delivered.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use futures::StreamExt;
+//! use pangea_client::{requests::logs::GetLogsRequest, ClientBuilder, WsProvider};
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = Arc::new(ClientBuilder::default().build::<WsProvider>().await?);
+//!
+//! let mut stream = client
+//!     .enrich_logs::<serde_json::Value>(GetLogsRequest::default(), 1000)
+//!     .await?;
+//! while let Some(log) = stream.next().await {
+//!     let log = log?;
+//!     println!("{:?} {:?}", log.block, log.transaction);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use ethers_core::types::{Address, H256, U256};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::{
+    error::Result,
+    stream::{chain_of, json_u256, json_u64, Position},
+    types::{amount::Amount, ChainId},
+};
+
+/// The block a log was emitted in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockContext {
+    pub timestamp: u64,
+    /// Not set on chains and blocks before EIP-1559
+    pub base_fee_per_gas: Option<U256>,
+}
+
+impl BlockContext {
+    fn of(record: &Value) -> Option<Self> {
+        Some(Self {
+            timestamp: record.get("timestamp").and_then(json_u64)?,
+            base_fee_per_gas: record.get("base_fee_per_gas").and_then(json_u256),
+        })
+    }
+}
+
+/// The transaction a log was emitted by
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxContext {
+    pub from: Address,
+    /// Not set for contract creations
+    pub to: Option<Address>,
+    /// The value transferred in the native token, with 18 decimals
+    pub value: Amount,
+}
+
+impl TxContext {
+    fn of(record: &Value) -> Option<Self> {
+        let address = |name: &str| {
+            record
+                .get(name)
+                .and_then(|address| serde_json::from_value(address.clone()).ok())
+        };
+
+        Some(Self {
+            from: address("from")?,
+            to: address("to"),
+            value: Amount::new(
+                record.get("value").and_then(json_u256).unwrap_or_default(),
+                18,
+            )
+            .ok()?,
+        })
+    }
+}
+
+/// An item of a stream returned by
+/// [`Client::enrich_logs`](crate::Client::enrich_logs)
+///
+/// The context is `None` if its record was not found in the range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enriched<V> {
+    pub log: V,
+    pub block: Option<BlockContext>,
+    pub transaction: Option<TxContext>,
+}
+
+/// The blocks and transactions of a window of logs, keyed by chain and
+/// position
+#[derive(Default)]
+pub(crate) struct Context {
+    blocks: HashMap<(Option<ChainId>, u64), BlockContext>,
+    txs: HashMap<(Option<ChainId>, Position), TxContext>,
+}
+
+impl Context {
+    pub fn add_block(&mut self, record: &Value) {
+        if let (Some(position), Some(block)) = (Position::of(record), BlockContext::of(record))
+        {
+            self.blocks
+                .insert((chain_of(record), position.block_number), block);
+        }
+    }
+
+    pub fn add_transaction(&mut self, record: &Value) {
+        if let (Some(position), Some(transaction)) =
+            (Position::of(record), TxContext::of(record))
+        {
+            self.txs.insert((chain_of(record), position), transaction);
+        }
+    }
+
+    /// Attaches the block and transaction of `log`
+    pub fn enrich<V>(&self, log: Value) -> Result<Enriched<V>>
+    where
+        V: DeserializeOwned,
+    {
+        let (block, transaction) = match Position::of(&log) {
+            Some(position) => {
+                let chain = chain_of(&log);
+                let transaction = Position {
+                    log_index: 0,
+                    ..position
+                };
+
+                (
+                    self.blocks.get(&(chain, position.block_number)).cloned(),
+                    self.txs.get(&(chain, transaction)).cloned(),
+                )
+            }
+            None => (None, None),
+        };
+
+        Ok(Enriched {
+            log: serde_json::from_value(log)?,
+            block,
+            transaction,
+        })
+    }
+}
+
+/// What has to be fetched to enrich a window of logs of one chain
+#[derive(Debug, Default)]
+pub(crate) struct Needed {
+    /// Ranges of blocks, the upper bound exclusive
+    pub blocks: Vec<(u64, u64)>,
+    /// The hashes of the transactions, `None` if a log does not carry it and
+    /// every transaction of `transactions_range` is needed
+    pub transaction_hashes: Option<HashSet<H256>>,
+    pub transactions_range: (u64, u64),
+}
+
+impl Needed {
+    /// Blocks at most this far apart are fetched in one range, instead of one
+    /// request per block
+    const MAX_GAP: u64 = 32;
+
+    /// Groups the logs of a window by the chain they belong to
+    pub fn of(logs: &[Value]) -> HashMap<Option<ChainId>, Self> {
+        let mut chains = HashMap::<_, (BTreeSet<u64>, Option<HashSet<H256>>)>::new();
+        for log in logs {
+            let Some(position) = Position::of(log) else {
+                continue;
+            };
+            let (blocks, hashes) = chains
+                .entry(chain_of(log))
+                .or_insert_with(|| (BTreeSet::new(), Some(HashSet::new())));
+            blocks.insert(position.block_number);
+
+            let hash = log
+                .get("transaction_hash")
+                .and_then(|hash| serde_json::from_value::<H256>(hash.clone()).ok());
+            match (hashes.as_mut(), hash) {
+                (Some(hashes), Some(hash)) => {
+                    hashes.insert(hash);
+                }
+                _ => *hashes = None,
+            }
+        }
+
+        chains
+            .into_iter()
+            .map(|(chain, (blocks, transaction_hashes))| {
+                let first = blocks.first().copied().unwrap_or_default();
+                let last = blocks.last().copied().unwrap_or_default();
+                let needed = Self {
+                    blocks: ranges(&blocks),
+                    transaction_hashes,
+                    transactions_range: (first, last + 1),
+                };
+                (chain, needed)
+            })
+            .collect()
+    }
+}
+
+/// Merges block numbers at most [`Needed::MAX_GAP`] apart into ranges
+fn ranges(blocks: &BTreeSet<u64>) -> Vec<(u64, u64)> {
+    let mut ranges = Vec::<(u64, u64)>::new();
+    for &block in blocks {
+        match ranges.last_mut() {
+            Some((_, end)) if block < *end + Needed::MAX_GAP => *end = block + 1,
+            _ => ranges.push((block, block + 1)),
+        }
+    }
+    ranges
+}
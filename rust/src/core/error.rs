@@ -1,6 +1,6 @@
 use std::{borrow::Cow, collections::HashSet};
 
-use crate::ChainId;
+use crate::{query::Bound, ChainId};
 
 /// A Result alias, that uses [`Error`] as the default error
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -59,12 +59,44 @@ pub enum Error {
     /// An error encountered during cbor parsing
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    /// An error encountered while decoding Arrow data
+    #[error(transparent)]
+    Arrow(#[from] arrow::error::ArrowError),
+    /// An error encountered while writing Parquet files
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+    /// A request could not be written as a query string
+    #[error(transparent)]
+    QueryStringEncode(#[from] serde_urlencoded::ser::Error),
+    /// A query string did not describe a valid request
+    #[error(transparent)]
+    QueryStringDecode(#[from] serde_urlencoded::de::Error),
     /// An error encountered during websocket handling
     #[error(transparent)]
     Tungstenite(#[from] tungstenite::Error),
     /// An error encountered during url parsing
     #[error(transparent)]
     Url(#[from] url::ParseError),
+    /// An error encountered during ABI decoding
+    #[error(transparent)]
+    Abi(#[from] ethers_core::abi::Error),
+    /// An error encountered while parsing a human-readable ABI
+    #[error(transparent)]
+    AbiParse(#[from] ethers_core::abi::ParseError),
+    /// Decoded ABI parameters did not match the requested type
+    #[error(transparent)]
+    InvalidOutputType(#[from] ethers_core::abi::InvalidOutputType),
+    /// The JSON ABI of a Sway program could not be loaded
+    #[error("invalid Sway ABI: {0}")]
+    InvalidSwayAbi(String),
+    /// A Sway log did not match its type in the ABI
+    #[error("failed to decode Sway log: {0}")]
+    SwayDecode(String),
+    /// An error encountered while accessing an SQLite database
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
 
     #[error("an unexpected error occurred: {0}")]
     Custom(Cow<'static, str>),
@@ -86,6 +118,69 @@ pub enum Error {
 
     #[error("invalid chain id: {0:?}")]
     InvalidChainId(HashSet<ChainId>),
+
+    #[error("unknown chain: {0}")]
+    UnknownChain(String),
+
+    #[error("chain code {0} is already used by {1:?}")]
+    ChainAlreadyKnown(String, ChainId),
+
+    #[error("client-side bounds require exactly one chain: {0:?}")]
+    ClientSideBoundChains(HashSet<ChainId>),
+
+    #[error("invalid block range bound: {0}")]
+    InvalidBound(String),
+
+    #[error("bound `{0}` needs the blocks of the chain, resolve it with `ResolveBounds` first")]
+    UnresolvedBound(Bound),
+
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("no block found on {0:?} at {1:?}")]
+    BlockNotFound(ChainId, Bound),
+
+    #[error("a log has at most 4 topics, got topic{0}")]
+    InvalidTopic(usize),
+
+    #[error("invalid sink schema: {0}")]
+    SinkSchema(String),
+
+    #[error("archive file {0} does not match its checksum")]
+    ArchiveChecksum(String),
+
+    #[error("the response has no Arrow schema")]
+    MissingSchema,
+
+    #[error("invalid pool event: {0}")]
+    InvalidPoolEvent(String),
+
+    #[error("unknown pool: {0}")]
+    UnknownPool(String),
+
+    #[error("token {0} is not traded by pool {1}")]
+    TokenNotInPool(String, String),
+
+    #[error("insufficient liquidity in pool {0}")]
+    InsufficientLiquidity(String),
+
+    #[error("tick {0} is out of range")]
+    InvalidTick(i32),
+
+    #[error("sqrt price {0} is out of range")]
+    InvalidSqrtPrice(String),
+
+    #[error("arithmetic error: {0}")]
+    MathError(&'static str),
+
+    #[error("incomplete pool state: {0}")]
+    IncompletePool(String),
+
+    #[error("{0} has {1} values, more than the batch size of {2}, and can not be split")]
+    UnsplittableFilter(&'static str, usize, usize),
+
+    #[error("the request needs {0} batches, which only JSON streams can be merged from")]
+    UnbatchableFormat(usize),
 }
 
 /// An error that is returned by the server if something goes wrong
@@ -1,7 +1,21 @@
+pub mod abi;
 pub mod builder;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod checkpoint;
 pub mod client;
+pub mod enrich;
 pub mod error;
+pub mod multichain;
+pub mod plan;
 pub mod provider;
 pub mod requests;
+pub mod sink;
+pub mod state;
+#[cfg(feature = "datafusion")]
+pub mod sql;
+pub mod stream;
+pub mod sway;
+mod timestamps;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,189 @@
+//! Streams spanning several chains
+//!
+//! A request with more than one entry in `chains` is split by
+//! [`Client::fan_out`](crate::Client::fan_out) into one request per chain. The
+//! records of every chain keep their block order and are tagged with their
+//! [`ChainId`], while a [`ChainEvent::Watermark`] tells when a chain has
+//! advanced past a block. Watermarks also follow the block heads of every
+//! chain, so a chain without matching records still advances. Records of
+//! different chains are interleaved in the order they arrive.
+//!
+//! ```no_run
+//! use std::collections::HashSet;
+//!
+//! use futures::StreamExt;
+//! use pangea_client::{
+//!     multichain::ChainEvent, requests::logs::GetLogsRequest, ChainId, ClientBuilder,
+//!     WsProvider,
+//! };
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = ClientBuilder::default().build::<WsProvider>().await?;
+//! let request = GetLogsRequest {
+//!     chains: HashSet::from([ChainId::ETH, ChainId::ARB]),
+//!     ..Default::default()
+//! };
+//!
+//! let mut stream = client.fan_out::<_, serde_json::Value>(request).await?;
+//! while let Some(event) = stream.next().await {
+//!     match event? {
+//!         ChainEvent::Record { chain, record } => println!("{chain:?}: {record}"),
+//!         ChainEvent::Watermark {
+//!             chain,
+//!             block_number,
+//!         } => println!("{chain:?} is past block {block_number}"),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use futures::{
+    future::{self, Either},
+    FutureExt, StreamExt,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::{
+    checkpoint::{checkpointed, Checkpointed},
+    provider::ResponseStream,
+    types::ChainId,
+};
+
+/// An item of a stream returned by [`Client::fan_out`](crate::Client::fan_out)
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChainEvent<V> {
+    /// A record of `chain`
+    Record { chain: ChainId, record: V },
+    /// Every record of `chain` up to and including `block_number` has been
+    /// delivered
+    Watermark { chain: ChainId, block_number: u64 },
+}
+
+impl<V> ChainEvent<V> {
+    /// The chain the event belongs to
+    pub fn chain(&self) -> ChainId {
+        match self {
+            Self::Record { chain, .. } | Self::Watermark { chain, .. } => *chain,
+        }
+    }
+}
+
+/// The block heads of a single chain
+pub(crate) struct Heads {
+    /// Block numbers of the head of the chain, in order
+    pub heads: ResponseStream<u64>,
+    /// Set once the records of the chain are caught up with its heads
+    pub caught_up: Arc<AtomicBool>,
+}
+
+struct Progress<V> {
+    events: ResponseStream<Checkpointed<V>>,
+    heads: ResponseStream<u64>,
+    caught_up: Arc<AtomicBool>,
+    /// The newest head seen
+    head: Option<u64>,
+    /// The last block marked
+    marked: Option<u64>,
+    done: bool,
+}
+
+impl<V> Progress<V> {
+    /// A watermark at `block_number`, if the chain was not marked that far yet
+    fn mark(&mut self, chain: ChainId, block_number: u64) -> Option<ChainEvent<V>> {
+        if self.marked.is_some_and(|marked| marked >= block_number) {
+            return None;
+        }
+        self.marked = Some(block_number);
+        Some(ChainEvent::Watermark {
+            chain,
+            block_number,
+        })
+    }
+}
+
+/// Tags the block ordered JSON records of a single chain and interleaves
+/// watermarks into them
+///
+/// A watermark follows the last record of every block. Once the records are
+/// caught up, a new head marks the block before it if no record is waiting,
+/// as the server sends the records of a block before moving past it. When the
+/// records end, the chain is marked up to the newest head.
+pub(crate) fn tagged<V>(
+    chain: ChainId,
+    records: ResponseStream<Value>,
+    heads: Heads,
+) -> ResponseStream<ChainEvent<V>>
+where
+    V: DeserializeOwned + Send + 'static,
+{
+    let state = Progress {
+        events: checkpointed(records),
+        heads: heads.heads,
+        caught_up: heads.caught_up,
+        head: None,
+        marked: None,
+        done: false,
+    };
+
+    futures::stream::unfold(state, move |mut state| async move {
+        loop {
+            if state.done {
+                return None;
+            }
+
+            // records are polled first, so a head only counts while none waits
+            let next = match future::select(state.events.next(), state.heads.next()).await {
+                Either::Left((event, _)) => Either::Left(event),
+                Either::Right((head, _)) => Either::Right(head),
+            };
+            match next {
+                Either::Left(Some(Ok(Checkpointed::Record(record)))) => {
+                    return Some((Ok(ChainEvent::Record { chain, record }), state));
+                }
+                Either::Left(Some(Ok(Checkpointed::BlockEnd(block_number)))) => {
+                    if let Some(event) = state.mark(chain, block_number) {
+                        return Some((Ok(event), state));
+                    }
+                }
+                Either::Left(Some(Err(err))) | Either::Right(Some(Err(err))) => {
+                    return Some((Err(err), state));
+                }
+                Either::Left(None) => {
+                    state.done = true;
+                    while let Some(Some(Ok(head))) = state.heads.next().now_or_never() {
+                        state.head = state.head.max(Some(head));
+                    }
+                    let event = state.head.and_then(|head| state.mark(chain, head));
+                    return event.map(|event| (Ok(event), state));
+                }
+                Either::Right(Some(Ok(head))) => {
+                    state.head = state.head.max(Some(head));
+                    if state.caught_up.load(Ordering::Acquire) && head > 0 {
+                        if let Some(event) = state.mark(chain, head - 1) {
+                            return Some((Ok(event), state));
+                        }
+                    }
+                }
+                Either::Right(None) => state.heads = futures::stream::pending().boxed(),
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Merges the tagged streams of several chains, polling them fairly
+pub(crate) fn merge<V>(
+    streams: Vec<ResponseStream<ChainEvent<V>>>,
+) -> ResponseStream<ChainEvent<V>>
+where
+    V: Send + 'static,
+{
+    futures::stream::select_all(streams).boxed()
+}
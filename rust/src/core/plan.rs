@@ -0,0 +1,202 @@
+//! Queries the server can not express as a single request
+//!
+//! The filters of a request are combined with AND. [`AnyOf`] holds several
+//! requests whose results are combined with OR: every request is sent on its
+//! own by [`Client::get_any`](crate::Client::get_any) and their records are
+//! merged, dropping the records matched by more than one of them.
+//!
+//! ```no_run
+//! use std::collections::HashSet;
+//!
+//! use ethers_core::types::H256;
+//! use futures::StreamExt;
+//! use pangea_client::{requests::logs::GetLogsRequest, ClientBuilder, WsProvider};
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = ClientBuilder::default().build::<WsProvider>().await?;
+//! let me = H256::from_low_u64_be(0xbeef);
+//!
+//! // logs where topic1 OR topic2 is `me`
+//! let query = GetLogsRequest::default().any_topic([1, 2], HashSet::from([me]))?;
+//!
+//! let mut stream = client
+//!     .get_any::<GetLogsRequest, serde_json::Value>(query)
+//!     .await?;
+//! while let Some(record) = stream.next().await {
+//!     println!("{}", record?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use futures::StreamExt;
+use serde_json::Value;
+
+use super::{
+    provider::ResponseStream,
+    stream::{chain_of, Position},
+    types::ChainId,
+};
+
+/// Requests whose results are combined with OR
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnyOf<R> {
+    branches: Vec<R>,
+}
+
+impl<R> AnyOf<R> {
+    pub fn new(request: R) -> Self {
+        Self {
+            branches: vec![request],
+        }
+    }
+
+    /// Adds the records of `request` to the query
+    pub fn or(mut self, request: R) -> Self {
+        self.branches.push(request);
+        self
+    }
+
+    pub fn branches(&self) -> &[R] {
+        &self.branches
+    }
+
+    pub fn into_branches(self) -> Vec<R> {
+        self.branches
+    }
+}
+
+impl<R> FromIterator<R> for AnyOf<R> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        Self {
+            branches: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<R> From<R> for AnyOf<R> {
+    fn from(request: R) -> Self {
+        Self::new(request)
+    }
+}
+
+/// How many times every branch delivered a record
+type Copies = HashMap<String, Vec<usize>>;
+
+struct Union {
+    streams: futures::stream::SelectAll<ResponseStream<(usize, Option<Value>)>>,
+    /// Last position delivered by every running branch, per chain
+    last: Vec<Option<HashMap<Option<ChainId>, Position>>>,
+    /// The records delivered at every position, per chain
+    seen: HashMap<Option<ChainId>, BTreeMap<Position, Copies>>,
+    /// Records without a position
+    seen_unpositioned: Copies,
+}
+
+/// Counts one more copy of `record` from `branch`, returns whether the branch
+/// delivered it more often than any other branch
+fn is_new(copies: &mut Copies, branches: usize, branch: usize, record: &Value) -> bool {
+    let counts = copies
+        .entry(record.to_string())
+        .or_insert_with(|| vec![0; branches]);
+    counts[branch] += 1;
+
+    counts
+        .iter()
+        .enumerate()
+        .all(|(other, count)| other == branch || *count < counts[branch])
+}
+
+impl Union {
+    /// Forgets the positions on `chain` that every running branch has passed,
+    /// they can not be delivered again
+    fn prune(&mut self, chain: Option<ChainId>) {
+        let Some(low) = self
+            .last
+            .iter()
+            .flatten()
+            .map(|last| last.get(&chain).copied())
+            .min()
+            .flatten()
+        else {
+            return;
+        };
+
+        if let Some(seen) = self.seen.get_mut(&chain) {
+            while seen
+                .first_key_value()
+                .is_some_and(|(position, _)| *position < low)
+            {
+                seen.pop_first();
+            }
+        }
+    }
+}
+
+/// Merges the block ordered JSON records of several branches of an OR query,
+/// delivering every record once
+///
+/// Records are identified by their chain, [`Position`] and content, so distinct
+/// records sharing a position are all delivered. A record is dropped if
+/// another branch delivered it as many times already, copies of a record in
+/// the same branch are all delivered. Records of different branches are
+/// interleaved in the order they arrive.
+pub(crate) fn union(branches: Vec<ResponseStream<Value>>) -> ResponseStream<Value> {
+    let last = vec![Some(HashMap::new()); branches.len()];
+    let streams = futures::stream::select_all(branches.into_iter().enumerate().map(
+        |(branch, stream)| {
+            stream
+                .map(move |record| record.map(|record| (branch, Some(record))))
+                .chain(futures::stream::once(async move { Ok((branch, None)) }))
+                .boxed()
+        },
+    ));
+
+    let state = Union {
+        streams,
+        last,
+        seen: HashMap::new(),
+        seen_unpositioned: HashMap::new(),
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            let (branch, record) = match state.streams.next().await? {
+                Ok(item) => item,
+                Err(err) => return Some((Err(err), state)),
+            };
+
+            let Some(record) = record else {
+                state.last[branch] = None;
+                continue;
+            };
+            let count = state.last.len();
+
+            let is_new = match Position::of(&record) {
+                Some(position) => {
+                    let chain = chain_of(&record);
+                    if let Some(last) = &mut state.last[branch] {
+                        last.insert(chain, position);
+                    }
+                    let copies = state
+                        .seen
+                        .entry(chain)
+                        .or_default()
+                        .entry(position)
+                        .or_default();
+                    let is_new = is_new(copies, count, branch, &record);
+                    state.prune(chain);
+                    is_new
+                }
+                None => is_new(&mut state.seen_unpositioned, count, branch, &record),
+            };
+
+            if is_new {
+                return Some((Ok(record), state));
+            }
+        }
+    })
+    .boxed()
+}
@@ -294,3 +294,70 @@ pub trait BtcProvider {
         deltas: bool,
     ) -> StreamResponse<Vec<u8>>;
 }
+
+/// Sends a request to the endpoint that serves it, which lets helpers like
+/// [`Client::follow`](crate::Client::follow) be generic over the request type
+#[async_trait]
+pub trait RequestProvider<R> {
+    async fn get_by_format(
+        &self,
+        request: R,
+        format: Format,
+        deltas: bool,
+    ) -> StreamResponse<Vec<u8>>;
+}
+
+macro_rules! impl_request_provider {
+    ($($request:ty => $provider:ident::$method:ident),* $(,)?) => {
+        $(
+            #[async_trait]
+            impl<P> RequestProvider<$request> for P
+            where
+                P: $provider + Send + Sync,
+            {
+                async fn get_by_format(
+                    &self,
+                    request: $request,
+                    format: Format,
+                    deltas: bool,
+                ) -> StreamResponse<Vec<u8>> {
+                    $provider::$method(self, request, format, deltas).await
+                }
+            }
+        )*
+    };
+}
+
+impl_request_provider!(
+    GetBlocksRequest => ChainProvider::get_blocks_by_format,
+    GetLogsRequest => ChainProvider::get_logs_by_format,
+    GetTxsRequest => ChainProvider::get_txs_by_format,
+    GetTransfersRequest => ChainProvider::get_transfers_by_format,
+    GetPairsRequest => UniswapV2Provider::get_pairs_by_format,
+    requests::uniswap_v2::GetPricesRequest => UniswapV2Provider::get_prices_by_format,
+    requests::uniswap_v3::GetFeesRequest => UniswapV3Provider::get_fees_by_format,
+    GetPoolsRequest => UniswapV3Provider::get_pools_by_format,
+    requests::uniswap_v3::GetPositionsRequest => UniswapV3Provider::get_positions_by_format,
+    requests::uniswap_v3::GetPricesRequest => UniswapV3Provider::get_prices_by_format,
+    GetCrvTokenRequest => CurveProvider::get_tokens_by_format,
+    GetCrvPoolRequest => CurveProvider::get_pools_by_format,
+    GetCrvPriceRequest => CurveProvider::get_prices_by_format,
+    GetErc20Request => Erc20Provider::get_erc20_by_format,
+    GetErc20ApprovalsRequest => Erc20Provider::get_erc20_approval_by_format,
+    GetErc20TransferssRequest => Erc20Provider::get_erc20_transfers_by_format,
+    GetFuelBlocksRequest => FuelProvider::get_fuel_blocks_by_format,
+    GetFuelLogsRequest => FuelProvider::get_fuel_logs_by_format,
+    GetFuelTxsRequest => FuelProvider::get_fuel_txs_by_format,
+    GetFuelReceiptsRequest => FuelProvider::get_fuel_receipts_by_format,
+    requests::fuel::GetFuelMessagesRequest => FuelProvider::get_fuel_messages_by_format,
+    GetUtxoRequest => FuelProvider::get_fuel_unspent_utxos_by_format,
+    GetSparkMarketRequest => FuelProvider::get_fuel_spark_markets_by_format,
+    GetSparkOrderRequest => FuelProvider::get_fuel_spark_orders_by_format,
+    GetSrc20 => FuelProvider::get_fuel_src20_by_format,
+    GetSrc7 => FuelProvider::get_fuel_src7_by_format,
+    GetMiraPoolsRequest => FuelProvider::get_fuel_mira_v1_pools_by_format,
+    GetMiraLiquidityRequest => FuelProvider::get_fuel_mira_v1_liquidity_by_format,
+    GetMiraSwapsRequest => FuelProvider::get_fuel_mira_v1_swaps_by_format,
+    GetBtcBlocksRequest => BtcProvider::get_btc_blocks_by_format,
+    GetBtcTxsRequest => BtcProvider::get_btc_txs_by_format,
+);
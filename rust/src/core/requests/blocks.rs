@@ -1,19 +1,21 @@
 use std::collections::HashSet;
 
+use ethers_core::types::H256;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     core::types::{default_chains, ChainId},
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{deserialize_comma_separated, serialize_comma_separated},
 };
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetBlocksRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -31,6 +33,14 @@ pub struct GetBlocksRequest {
     // Exclusive upper bound if is Some for block timestamp
     #[serde(default)]
     pub to_timestamp: Option<i64>,
+
+    #[serde(default)]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
+        skip_serializing_if = "HashSet::is_empty"
+    )]
+    pub hash__in: HashSet<H256>,
 }
 
 impl Default for GetBlocksRequest {
@@ -41,6 +51,7 @@ impl Default for GetBlocksRequest {
             to_block: Bound::default(),
             from_timestamp: None,
             to_timestamp: None,
+            hash__in: HashSet::new(),
         }
     }
 }
@@ -4,17 +4,18 @@ use ethers_core::types::{Address, U256};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    core::types::{default_chains, ChainId},
+    core::types::{amount::Amount, default_chains, ChainId},
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{deserialize_comma_separated, serialize_comma_separated},
 };
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetCrvTokenRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -29,6 +30,7 @@ pub struct GetCrvTokenRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub address__in: HashSet<Address>,
@@ -36,6 +38,7 @@ pub struct GetCrvTokenRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub symbol__in: HashSet<String>,
@@ -43,6 +46,7 @@ pub struct GetCrvTokenRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub name__in: HashSet<String>,
@@ -50,6 +54,7 @@ pub struct GetCrvTokenRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_address__in: HashSet<Address>,
@@ -77,12 +82,13 @@ impl Default for GetCrvTokenRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetCrvPoolRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -97,6 +103,7 @@ pub struct GetCrvPoolRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_address__in: HashSet<Address>,
@@ -104,6 +111,7 @@ pub struct GetCrvPoolRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token__in: HashSet<Address>,
@@ -111,6 +119,7 @@ pub struct GetCrvPoolRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub owner__in: HashSet<Address>,
@@ -118,6 +127,7 @@ pub struct GetCrvPoolRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub base_pool__in: HashSet<Address>,
@@ -125,6 +135,7 @@ pub struct GetCrvPoolRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub coins__in: HashSet<Address>,
@@ -132,6 +143,7 @@ pub struct GetCrvPoolRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub base_coins__in: HashSet<Address>,
@@ -209,12 +221,13 @@ impl Default for GetCrvPoolRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetCrvPriceRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -229,6 +242,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_address__in: HashSet<Address>,
@@ -236,6 +250,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub buyer__in: HashSet<Address>,
@@ -243,6 +258,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens_address__in: HashSet<Address>,
@@ -250,6 +266,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens_symbol__in: HashSet<String>,
@@ -257,6 +274,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sold_address__in: HashSet<Address>,
@@ -264,6 +282,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sold_symbol__in: HashSet<String>,
@@ -277,6 +296,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub bought_address__in: HashSet<Address>,
@@ -284,6 +304,7 @@ pub struct GetCrvPriceRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub bought_symbol__in: HashSet<String>,
@@ -295,22 +316,22 @@ pub struct GetCrvPriceRequest {
     pub bought_decimals__lte: Option<u8>,
 
     #[serde(default)]
-    pub price__gte: Option<f64>,
+    pub price__gte: Option<Amount>,
 
     #[serde(default)]
-    pub price__lte: Option<f64>,
+    pub price__lte: Option<Amount>,
 
     #[serde(default)]
-    pub tokens_sold__gte: Option<f64>,
+    pub tokens_sold__gte: Option<Amount>,
 
     #[serde(default)]
-    pub tokens_sold__lte: Option<f64>,
+    pub tokens_sold__lte: Option<Amount>,
 
     #[serde(default)]
-    pub tokens_bought__gte: Option<f64>,
+    pub tokens_bought__gte: Option<Amount>,
 
     #[serde(default)]
-    pub tokens_bought__lte: Option<f64>,
+    pub tokens_bought__lte: Option<Amount>,
 }
 
 impl Default for GetCrvPriceRequest {
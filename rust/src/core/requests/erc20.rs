@@ -1,20 +1,21 @@
 use std::collections::HashSet;
 
-use ethers_core::types::Address;
+use ethers_core::types::{Address, H256};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    core::types::{default_chains, ChainId},
+    core::types::{amount::Amount, default_chains, ChainId},
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{deserialize_comma_separated, serialize_comma_separated},
 };
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetErc20Request {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -29,6 +30,7 @@ pub struct GetErc20Request {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub address__in: HashSet<Address>,
@@ -36,6 +38,7 @@ pub struct GetErc20Request {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub symbol__in: HashSet<String>,
@@ -43,6 +46,7 @@ pub struct GetErc20Request {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub name__in: HashSet<String>,
@@ -69,12 +73,13 @@ impl Default for GetErc20Request {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetErc20ApprovalsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -89,6 +94,7 @@ pub struct GetErc20ApprovalsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub address__in: HashSet<Address>,
@@ -96,6 +102,7 @@ pub struct GetErc20ApprovalsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub symbol__in: HashSet<String>,
@@ -103,6 +110,7 @@ pub struct GetErc20ApprovalsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub name__in: HashSet<String>,
@@ -116,6 +124,7 @@ pub struct GetErc20ApprovalsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub owner__in: HashSet<Address>,
@@ -123,15 +132,16 @@ pub struct GetErc20ApprovalsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub spender__in: HashSet<Address>,
 
     #[serde(default)]
-    pub value__lte: Option<f64>,
+    pub value__lte: Option<Amount>,
 
     #[serde(default)]
-    pub value__gte: Option<f64>,
+    pub value__gte: Option<Amount>,
 }
 
 impl Default for GetErc20ApprovalsRequest {
@@ -153,12 +163,13 @@ impl Default for GetErc20ApprovalsRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetErc20TransferssRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -173,6 +184,15 @@ pub struct GetErc20TransferssRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
+        skip_serializing_if = "HashSet::is_empty"
+    )]
+    pub transaction_hash__in: HashSet<H256>,
+
+    #[serde(default)]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub address__in: HashSet<Address>,
@@ -180,6 +200,15 @@ pub struct GetErc20TransferssRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
+        skip_serializing_if = "HashSet::is_empty"
+    )]
+    pub address__not_in: HashSet<Address>,
+
+    #[serde(default)]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub symbol__in: HashSet<String>,
@@ -187,6 +216,7 @@ pub struct GetErc20TransferssRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub name__in: HashSet<String>,
@@ -200,6 +230,7 @@ pub struct GetErc20TransferssRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub from__in: HashSet<Address>,
@@ -207,15 +238,16 @@ pub struct GetErc20TransferssRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub to__in: HashSet<Address>,
 
     #[serde(default)]
-    pub value__lte: Option<f64>,
+    pub value__lte: Option<Amount>,
 
     #[serde(default)]
-    pub value__gte: Option<f64>,
+    pub value__gte: Option<Amount>,
 }
 
 impl Default for GetErc20TransferssRequest {
@@ -224,7 +256,9 @@ impl Default for GetErc20TransferssRequest {
             chains: default_chains(),
             from_block: Bound::default(),
             to_block: Bound::default(),
+            transaction_hash__in: HashSet::new(),
             address__in: HashSet::new(),
+            address__not_in: HashSet::new(),
             value__lte: None,
             value__gte: None,
             symbol__in: HashSet::new(),
@@ -11,17 +11,20 @@ use crate::{
         ChainId,
     },
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{
+        deserialize_comma_separated, serialize_comma_separated, serialize_hex_comma_separated,
+    },
 };
 
-use fuel_core_types::fuel_types::Address;
+use fuel_core_types::fuel_types::{Address, AssetId, Bytes32, ContractId};
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetFuelBlocksRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -54,12 +57,13 @@ impl Default for GetFuelBlocksRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetFuelLogsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -73,14 +77,16 @@ pub struct GetFuelLogsRequest {
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub id__in: HashSet<Address>,
+    pub id__in: HashSet<ContractId>,
 
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub ra__in: HashSet<u64>,
@@ -88,6 +94,7 @@ pub struct GetFuelLogsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub rb__in: HashSet<u64>,
@@ -106,12 +113,13 @@ impl Default for GetFuelLogsRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetFuelTxsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -126,30 +134,34 @@ pub struct GetFuelTxsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub transaction_type__in: HashSet<TransactionType>,
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub metadata_contract_id__in: HashSet<Address>,
+    pub metadata_contract_id__in: HashSet<ContractId>,
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub input_contract_contract_id__in: HashSet<Address>,
+    pub input_contract_contract_id__in: HashSet<ContractId>,
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub mint_asset_id__in: HashSet<Address>,
+    pub mint_asset_id__in: HashSet<AssetId>,
 
     #[serde(default)]
     pub mint_amount__lte: Option<u64>,
@@ -174,12 +186,13 @@ impl Default for GetFuelTxsRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetFuelReceiptsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -194,6 +207,7 @@ pub struct GetFuelReceiptsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub receipt_type__in: HashSet<ReceiptType>,
@@ -210,12 +224,13 @@ impl Default for GetFuelReceiptsRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetFuelMessagesRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -235,14 +250,16 @@ pub struct GetFuelMessagesRequest {
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sender__in: HashSet<Address>,
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub recipient__in: HashSet<Address>,
@@ -256,6 +273,7 @@ pub struct GetFuelMessagesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub message_type__in: HashSet<MessageType>,
@@ -278,41 +296,52 @@ impl Default for GetFuelMessagesRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetSparkMarketRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
 
     // Inclusive lower bound if is Some for block number
+    #[serde(default)]
     pub from_block: Bound,
     // Inclusive upper bound if is Some for block number
+    #[serde(default)]
     pub to_block: Bound,
 
+    #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub event_type__in: HashSet<MarketEventType>,
+    #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub base_asset__in: HashSet<Address>,
+    pub base_asset__in: HashSet<AssetId>,
+    #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub quote_asset__in: HashSet<Address>,
+    pub quote_asset__in: HashSet<AssetId>,
+    #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub market_id__in: HashSet<Address>,
+    pub market_id__in: HashSet<ContractId>,
 }
 
 impl Default for GetSparkMarketRequest {
@@ -329,12 +358,13 @@ impl Default for GetSparkMarketRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetSparkOrderRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -348,48 +378,58 @@ pub struct GetSparkOrderRequest {
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub order_id__in: HashSet<Address>,
+    pub order_id__in: HashSet<Bytes32>,
 
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub order_type__in: HashSet<OrderType>,
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub event_type__in: HashSet<OrderEventType>,
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub limit_type__in: HashSet<LimitType>,
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub user__in: HashSet<Address>,
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub asset__in: HashSet<Address>,
+    pub asset__in: HashSet<AssetId>,
+    #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub market_id__in: HashSet<Address>,
+    pub market_id__in: HashSet<ContractId>,
+    #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub address__in: HashSet<Address>,
@@ -413,12 +453,13 @@ impl Default for GetSparkOrderRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetUtxoRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -435,7 +476,8 @@ pub struct GetUtxoRequest {
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub address__in: HashSet<Address>,
@@ -453,12 +495,13 @@ impl Default for GetUtxoRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetSrc20 {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -472,21 +515,24 @@ pub struct GetSrc20 {
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub contract_id__in: HashSet<Address>,
+    pub contract_id__in: HashSet<ContractId>,
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub asset_id__in: HashSet<Address>,
+    pub asset_id__in: HashSet<AssetId>,
 
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub symbol__in: HashSet<String>,
@@ -494,6 +540,7 @@ pub struct GetSrc20 {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub name__in: HashSet<String>,
@@ -521,12 +568,13 @@ impl Default for GetSrc20 {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetSrc7 {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -540,21 +588,24 @@ pub struct GetSrc7 {
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
-    pub asset__in: HashSet<Address>,
+    pub asset__in: HashSet<AssetId>,
 
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub key__in: HashSet<String>,
 
     #[serde(default)]
     #[serde(
-        serialize_with = "serialize_comma_separated",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sender__in: HashSet<Address>,
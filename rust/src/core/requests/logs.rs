@@ -4,17 +4,22 @@ use ethers_core::types::{Address, H256};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    core::types::{default_chains, ChainId},
+    core::{
+        plan::AnyOf,
+        types::{default_chains, ChainId},
+    },
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{deserialize_comma_separated, serialize_comma_separated},
+    Error, Result,
 };
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetLogsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -29,6 +34,15 @@ pub struct GetLogsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
+        skip_serializing_if = "HashSet::is_empty"
+    )]
+    pub transaction_hash__in: HashSet<H256>,
+
+    #[serde(default)]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub address__in: HashSet<Address>,
@@ -36,24 +50,36 @@ pub struct GetLogsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
+        skip_serializing_if = "HashSet::is_empty"
+    )]
+    pub address__not_in: HashSet<Address>,
+
+    #[serde(default)]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub topic0__in: HashSet<H256>,
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub topic1__in: HashSet<H256>,
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub topic2__in: HashSet<H256>,
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub topic3__in: HashSet<H256>,
@@ -65,7 +91,9 @@ impl Default for GetLogsRequest {
             chains: default_chains(),
             from_block: Bound::default(),
             to_block: Bound::default(),
+            transaction_hash__in: HashSet::new(),
             address__in: HashSet::new(),
+            address__not_in: HashSet::new(),
             topic0__in: HashSet::new(),
             topic1__in: HashSet::new(),
             topic2__in: HashSet::new(),
@@ -73,3 +101,30 @@ impl Default for GetLogsRequest {
         }
     }
 }
+
+impl GetLogsRequest {
+    /// The logs of this request where any of the `topics` is in `values`,
+    /// e.g. `any_topic([1, 2], ...)` for topic1 OR topic2
+    ///
+    /// Fails if a topic index is larger than 3.
+    pub fn any_topic(
+        self,
+        topics: impl IntoIterator<Item = usize>,
+        values: HashSet<H256>,
+    ) -> Result<AnyOf<Self>> {
+        topics
+            .into_iter()
+            .map(|topic| {
+                let mut request = self.clone();
+                match topic {
+                    0 => request.topic0__in = values.clone(),
+                    1 => request.topic1__in = values.clone(),
+                    2 => request.topic2__in = values.clone(),
+                    3 => request.topic3__in = values.clone(),
+                    _ => return Err(Error::InvalidTopic(topic)),
+                }
+                Ok(request)
+            })
+            .collect()
+    }
+}
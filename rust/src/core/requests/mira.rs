@@ -2,19 +2,24 @@ use std::collections::HashSet;
 
 use serde::{Deserialize, Serialize};
 
-use ethers_core::types::H256;
+use fuel_core_types::fuel_types::{AssetId, Bytes32};
 
 use crate::{
     core::types::{default_chains, ChainId},
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{
+        deserialize_comma_separated, serialize_comma_separated, serialize_hex_comma_separated,
+    },
 };
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetMiraPoolsRequest {
     #[serde(default = "default_chains")]
-    #[serde(serialize_with = "serialize_comma_separated")]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
     pub chains: HashSet<ChainId>,
 
     // Inclusive lower bound if is Some for block number
@@ -25,27 +30,58 @@ pub struct GetMiraPoolsRequest {
     pub to_block: Bound,
 
     #[serde(default)]
-    #[serde(serialize_with = "serialize_comma_separated")]
-    pub pool_address__in: HashSet<H256>,
+    #[serde(
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub pool_address__in: HashSet<Bytes32>,
 
     #[serde(default)]
-    #[serde(alias = "asset0__in", serialize_with = "serialize_comma_separated")]
-    pub asset0_address__in: HashSet<H256>,
+    #[serde(
+        alias = "asset0__in",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub asset0_address__in: HashSet<AssetId>,
 
     #[serde(default)]
-    #[serde(alias = "asset1__in", serialize_with = "serialize_comma_separated")]
-    pub asset1_address__in: HashSet<H256>,
+    #[serde(
+        alias = "asset1__in",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub asset1_address__in: HashSet<AssetId>,
 
     #[serde(default)]
-    #[serde(serialize_with = "serialize_comma_separated")]
-    pub assets__in: HashSet<H256>,
+    #[serde(
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub assets__in: HashSet<AssetId>,
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+impl Default for GetMiraPoolsRequest {
+    fn default() -> Self {
+        Self {
+            chains: default_chains(),
+            from_block: Bound::default(),
+            to_block: Bound::default(),
+            pool_address__in: HashSet::new(),
+            asset0_address__in: HashSet::new(),
+            asset1_address__in: HashSet::new(),
+            assets__in: HashSet::new(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetMiraLiquidityRequest {
     #[serde(default = "default_chains")]
-    #[serde(serialize_with = "serialize_comma_separated")]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
     pub chains: HashSet<ChainId>,
 
     // Inclusive lower bound if is Some for block number
@@ -56,27 +92,58 @@ pub struct GetMiraLiquidityRequest {
     pub to_block: Bound,
 
     #[serde(default)]
-    #[serde(serialize_with = "serialize_comma_separated")]
-    pub pool_address__in: HashSet<H256>,
+    #[serde(
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub pool_address__in: HashSet<Bytes32>,
 
     #[serde(default)]
-    #[serde(alias = "asset0__in", serialize_with = "serialize_comma_separated")]
-    pub asset0_address__in: HashSet<H256>,
+    #[serde(
+        alias = "asset0__in",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub asset0_address__in: HashSet<AssetId>,
 
     #[serde(default)]
-    #[serde(alias = "asset1__in", serialize_with = "serialize_comma_separated")]
-    pub asset1_address__in: HashSet<H256>,
+    #[serde(
+        alias = "asset1__in",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub asset1_address__in: HashSet<AssetId>,
 
     #[serde(default)]
-    #[serde(serialize_with = "serialize_comma_separated")]
-    pub assets__in: HashSet<H256>,
+    #[serde(
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub assets__in: HashSet<AssetId>,
+}
+
+impl Default for GetMiraLiquidityRequest {
+    fn default() -> Self {
+        Self {
+            chains: default_chains(),
+            from_block: Bound::default(),
+            to_block: Bound::default(),
+            pool_address__in: HashSet::new(),
+            asset0_address__in: HashSet::new(),
+            asset1_address__in: HashSet::new(),
+            assets__in: HashSet::new(),
+        }
+    }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetMiraSwapsRequest {
     #[serde(default = "default_chains")]
-    #[serde(serialize_with = "serialize_comma_separated")]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
     pub chains: HashSet<ChainId>,
 
     // Inclusive lower bound if is Some for block number
@@ -87,18 +154,46 @@ pub struct GetMiraSwapsRequest {
     pub to_block: Bound,
 
     #[serde(default)]
-    #[serde(serialize_with = "serialize_comma_separated")]
-    pub pool_address__in: HashSet<H256>,
+    #[serde(
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub pool_address__in: HashSet<Bytes32>,
 
     #[serde(default)]
-    #[serde(alias = "asset0__in", serialize_with = "serialize_comma_separated")]
-    pub asset0_address__in: HashSet<H256>,
+    #[serde(
+        alias = "asset0__in",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub asset0_address__in: HashSet<AssetId>,
 
     #[serde(default)]
-    #[serde(alias = "asset1__in", serialize_with = "serialize_comma_separated")]
-    pub asset1_address__in: HashSet<H256>,
+    #[serde(
+        alias = "asset1__in",
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub asset1_address__in: HashSet<AssetId>,
 
     #[serde(default)]
-    #[serde(serialize_with = "serialize_comma_separated")]
-    pub assets__in: HashSet<H256>,
+    #[serde(
+        serialize_with = "serialize_hex_comma_separated",
+        deserialize_with = "deserialize_comma_separated"
+    )]
+    pub assets__in: HashSet<AssetId>,
+}
+
+impl Default for GetMiraSwapsRequest {
+    fn default() -> Self {
+        Self {
+            chains: default_chains(),
+            from_block: Bound::default(),
+            to_block: Bound::default(),
+            pool_address__in: HashSet::new(),
+            asset0_address__in: HashSet::new(),
+            asset1_address__in: HashSet::new(),
+            assets__in: HashSet::new(),
+        }
+    }
 }
@@ -1,3 +1,9 @@
+use std::{collections::HashSet, hash::Hash};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{query::Bound, ChainId, Error, Result};
+
 pub mod blocks;
 pub mod btc;
 pub mod curve;
@@ -9,3 +15,298 @@ pub mod transfers;
 pub mod txs;
 pub mod uniswap_v2;
 pub mod uniswap_v3;
+
+/// Accessors shared by every request that covers a block range on a set of chains
+pub trait BlockRange {
+    fn chains(&self) -> &HashSet<ChainId>;
+    fn set_chains(&mut self, chains: HashSet<ChainId>);
+    /// The `(from_block, to_block)` bounds of the request
+    fn block_range(&self) -> (Bound, Bound);
+    fn set_from_block(&mut self, bound: Bound);
+    fn set_to_block(&mut self, bound: Bound);
+}
+
+macro_rules! impl_block_range {
+    ($($request:ty),* $(,)?) => {
+        $(
+            impl BlockRange for $request {
+                fn chains(&self) -> &HashSet<ChainId> {
+                    &self.chains
+                }
+
+                fn set_chains(&mut self, chains: HashSet<ChainId>) {
+                    self.chains = chains;
+                }
+
+                fn block_range(&self) -> (Bound, Bound) {
+                    (self.from_block, self.to_block)
+                }
+
+                fn set_from_block(&mut self, bound: Bound) {
+                    self.from_block = bound;
+                }
+
+                fn set_to_block(&mut self, bound: Bound) {
+                    self.to_block = bound;
+                }
+            }
+        )*
+    };
+}
+
+impl_block_range!(
+    blocks::GetBlocksRequest,
+    btc::GetBtcBlocksRequest,
+    btc::GetBtcTxsRequest,
+    curve::GetCrvTokenRequest,
+    curve::GetCrvPoolRequest,
+    curve::GetCrvPriceRequest,
+    erc20::GetErc20Request,
+    erc20::GetErc20ApprovalsRequest,
+    erc20::GetErc20TransferssRequest,
+    fuel::GetFuelBlocksRequest,
+    fuel::GetFuelLogsRequest,
+    fuel::GetFuelTxsRequest,
+    fuel::GetFuelReceiptsRequest,
+    fuel::GetFuelMessagesRequest,
+    fuel::GetSparkMarketRequest,
+    fuel::GetSparkOrderRequest,
+    fuel::GetUtxoRequest,
+    fuel::GetSrc20,
+    fuel::GetSrc7,
+    logs::GetLogsRequest,
+    mira::GetMiraPoolsRequest,
+    mira::GetMiraLiquidityRequest,
+    mira::GetMiraSwapsRequest,
+    transfers::GetTransfersRequest,
+    txs::GetTxsRequest,
+    uniswap_v2::GetPairsRequest,
+    uniswap_v2::GetPricesRequest,
+    uniswap_v3::GetFeesRequest,
+    uniswap_v3::GetPoolsRequest,
+    uniswap_v3::GetPositionsRequest,
+    uniswap_v3::GetPricesRequest,
+);
+
+/// Conversions of a request from and to the forms sent by the providers
+///
+/// [`HttpProvider`](crate::HttpProvider) sends the query string and
+/// [`WsProvider`](crate::WsProvider) the JSON object, both round-trip to the
+/// same request. Sets are written as comma separated values, so their items
+/// can not contain commas.
+pub trait Encoding: Serialize + DeserializeOwned {
+    fn to_query_string(&self) -> Result<String> {
+        Ok(serde_urlencoded::to_string(self)?)
+    }
+
+    fn from_query_string(query: &str) -> Result<Self> {
+        Ok(serde_urlencoded::from_str(query.trim_start_matches('?'))?)
+    }
+
+    fn to_json(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn from_json(value: serde_json::Value) -> Result<Self> {
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl<R> Encoding for R where R: BlockRange + Serialize + DeserializeOwned {}
+
+/// Requests whose `__in` filters can be split over several smaller requests
+///
+/// Every set is sent as one comma separated value, so a large set can exceed
+/// the length limits of URLs and WebSocket messages.
+pub trait Batch: Sized {
+    /// Splits every `__in` set with more than `size` entries into chunks of at
+    /// most `size`, returning one request per combination of chunks
+    ///
+    /// The union of the records of the returned requests is the records of
+    /// `self`, and no record matches two of them. A `size` of zero disables
+    /// splitting.
+    ///
+    /// Sets a record can match with several values, like `tokens__in`, and
+    /// `__not_in` sets can not be split that way, and fail with
+    /// [`Error::UnsplittableFilter`] when larger than `size`.
+    fn split(self, size: usize) -> Result<Vec<Self>>;
+}
+
+fn split_set<R, T>(mut request: R, size: usize, set: fn(&mut R) -> &mut HashSet<T>) -> Vec<R>
+where
+    R: Clone,
+    T: Clone + Eq + Hash,
+{
+    if size == 0 || set(&mut request).len() <= size {
+        return vec![request];
+    }
+
+    let values = std::mem::take(set(&mut request)).into_iter().collect::<Vec<_>>();
+    values
+        .chunks(size)
+        .map(|chunk| {
+            let mut batch = request.clone();
+            *set(&mut batch) = chunk.iter().cloned().collect();
+            batch
+        })
+        .collect()
+}
+
+fn check_set<T>(name: &'static str, set: &HashSet<T>, size: usize) -> Result<()> {
+    if size != 0 && set.len() > size {
+        return Err(Error::UnsplittableFilter(name, set.len(), size));
+    }
+    Ok(())
+}
+
+macro_rules! impl_batch {
+    ($($request:ty { split: [$($field:ident),* $(,)?], whole: [$($whole:ident),* $(,)?] $(,)? }),* $(,)?) => {
+        $(
+            impl Batch for $request {
+                fn split(self, size: usize) -> Result<Vec<Self>> {
+                    $(check_set(stringify!($whole), &self.$whole, size)?;)*
+                    let requests = vec![self];
+                    $(
+                        let requests = requests
+                            .into_iter()
+                            .flat_map(|request| split_set(request, size, |r| &mut r.$field))
+                            .collect::<Vec<_>>();
+                    )*
+                    Ok(requests)
+                }
+            }
+        )*
+    };
+}
+
+impl_batch!(
+    blocks::GetBlocksRequest { split: [hash__in], whole: [] },
+    curve::GetCrvTokenRequest {
+        split: [address__in, symbol__in, name__in],
+        whole: [pool_address__in],
+    },
+    curve::GetCrvPoolRequest {
+        split: [pool_address__in, token__in, owner__in, base_pool__in],
+        whole: [coins__in, base_coins__in],
+    },
+    curve::GetCrvPriceRequest {
+        split: [
+            pool_address__in,
+            buyer__in,
+            sold_address__in,
+            sold_symbol__in,
+            bought_address__in,
+            bought_symbol__in,
+        ],
+        whole: [tokens_address__in, tokens_symbol__in],
+    },
+    erc20::GetErc20Request { split: [address__in, symbol__in, name__in], whole: [] },
+    erc20::GetErc20ApprovalsRequest {
+        split: [address__in, symbol__in, name__in, owner__in, spender__in],
+        whole: [],
+    },
+    erc20::GetErc20TransferssRequest {
+        split: [address__in, transaction_hash__in, symbol__in, name__in, from__in, to__in],
+        whole: [address__not_in],
+    },
+    fuel::GetFuelLogsRequest { split: [id__in, ra__in, rb__in], whole: [] },
+    fuel::GetFuelTxsRequest {
+        split: [transaction_type__in, metadata_contract_id__in, mint_asset_id__in],
+        whole: [input_contract_contract_id__in],
+    },
+    fuel::GetFuelReceiptsRequest { split: [receipt_type__in], whole: [] },
+    fuel::GetFuelMessagesRequest {
+        split: [sender__in, recipient__in, message_type__in],
+        whole: [],
+    },
+    fuel::GetSparkMarketRequest {
+        split: [event_type__in, base_asset__in, quote_asset__in, market_id__in],
+        whole: [],
+    },
+    fuel::GetSparkOrderRequest {
+        split: [
+            order_id__in,
+            order_type__in,
+            event_type__in,
+            limit_type__in,
+            user__in,
+            asset__in,
+            market_id__in,
+        ],
+        whole: [address__in],
+    },
+    fuel::GetUtxoRequest { split: [address__in], whole: [] },
+    fuel::GetSrc20 { split: [contract_id__in, asset_id__in, symbol__in, name__in], whole: [] },
+    fuel::GetSrc7 { split: [asset__in, key__in, sender__in], whole: [] },
+    logs::GetLogsRequest {
+        split: [
+            address__in,
+            transaction_hash__in,
+            topic0__in,
+            topic1__in,
+            topic2__in,
+            topic3__in,
+        ],
+        whole: [address__not_in],
+    },
+    mira::GetMiraPoolsRequest {
+        split: [pool_address__in, asset0_address__in, asset1_address__in],
+        whole: [assets__in],
+    },
+    mira::GetMiraLiquidityRequest {
+        split: [pool_address__in, asset0_address__in, asset1_address__in],
+        whole: [assets__in],
+    },
+    mira::GetMiraSwapsRequest {
+        split: [pool_address__in, asset0_address__in, asset1_address__in],
+        whole: [assets__in],
+    },
+    transfers::GetTransfersRequest {
+        split: [address__in, transaction_hash__in, from__in, to__in],
+        whole: [address__not_in],
+    },
+    txs::GetTxsRequest { split: [hash__in, from__in, to__in], whole: [] },
+    uniswap_v2::GetPairsRequest {
+        split: [pair_address__in, factory_address__in, token0__in, token1__in],
+        whole: [tokens__in],
+    },
+    uniswap_v2::GetPricesRequest {
+        split: [
+            pair_address__in,
+            pair_factory_address__in,
+            event__in,
+            sender__in,
+            receiver__in,
+            token0_address__in,
+            token0_symbol__in,
+            token1_address__in,
+            token1_symbol__in,
+        ],
+        whole: [pair_address__not_in, tokens_address__in, tokens_symbol__in],
+    },
+    uniswap_v3::GetFeesRequest {
+        split: [pool_address__in, sender__in, recipient__in],
+        whole: [],
+    },
+    uniswap_v3::GetPoolsRequest {
+        split: [pool_address__in, factory_address__in, token0__in, token1__in],
+        whole: [tokens__in],
+    },
+    uniswap_v3::GetPositionsRequest {
+        split: [pool_address__in, sender__in, recipient__in],
+        whole: [],
+    },
+    uniswap_v3::GetPricesRequest {
+        split: [
+            pool_address__in,
+            pool_factory_address__in,
+            sender__in,
+            receiver__in,
+            token0_address__in,
+            token0_symbol__in,
+            token1_address__in,
+            token1_symbol__in,
+        ],
+        whole: [pool_address__not_in, tokens_address__in, tokens_symbol__in],
+    },
+);
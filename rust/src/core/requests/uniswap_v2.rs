@@ -5,17 +5,18 @@ use serde::{Deserialize, Serialize};
 use ethers_core::types::{Address, U128};
 
 use crate::{
-    core::types::{default_chains, uniswap_v2::ReserveEvent, ChainId},
+    core::types::{amount::Amount, default_chains, uniswap_v2::ReserveEvent, ChainId},
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{deserialize_comma_separated, serialize_comma_separated},
 };
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetPairsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -30,6 +31,7 @@ pub struct GetPairsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pair_address__in: HashSet<Address>,
@@ -37,6 +39,7 @@ pub struct GetPairsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub factory_address__in: HashSet<Address>,
@@ -44,6 +47,7 @@ pub struct GetPairsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token0__in: HashSet<Address>,
@@ -51,6 +55,7 @@ pub struct GetPairsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token1__in: HashSet<Address>,
@@ -58,6 +63,7 @@ pub struct GetPairsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens__in: HashSet<Address>,
@@ -78,12 +84,13 @@ impl Default for GetPairsRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetPricesRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -98,6 +105,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pair_address__in: HashSet<Address>,
@@ -105,6 +113,15 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
+        skip_serializing_if = "HashSet::is_empty"
+    )]
+    pub pair_address__not_in: HashSet<Address>,
+
+    #[serde(default)]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pair_factory_address__in: HashSet<Address>,
@@ -112,6 +129,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub event__in: HashSet<ReserveEvent>,
@@ -129,14 +147,15 @@ pub struct GetPricesRequest {
     pub reserve1__lte: Option<U128>,
 
     #[serde(default)]
-    pub price__gte: Option<f64>,
+    pub price__gte: Option<Amount>,
 
     #[serde(default)]
-    pub price__lte: Option<f64>,
+    pub price__lte: Option<Amount>,
 
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sender__in: HashSet<Address>,
@@ -144,37 +163,39 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub receiver__in: HashSet<Address>,
 
     #[serde(default)]
-    pub amount0__gte: Option<f64>,
+    pub amount0__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount0__lte: Option<f64>,
+    pub amount0__lte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__gte: Option<f64>,
+    pub amount1__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__lte: Option<f64>,
+    pub amount1__lte: Option<Amount>,
 
     #[serde(default)]
-    pub lp_amount__gte: Option<f64>,
+    pub lp_amount__gte: Option<Amount>,
 
     #[serde(default)]
-    pub lp_amount__lte: Option<f64>,
+    pub lp_amount__lte: Option<Amount>,
 
     #[serde(default)]
-    pub protocol_fee__gte: Option<f64>,
+    pub protocol_fee__gte: Option<Amount>,
 
     #[serde(default)]
-    pub protocol_fee__lte: Option<f64>,
+    pub protocol_fee__lte: Option<Amount>,
 
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token0_address__in: HashSet<Address>,
@@ -182,6 +203,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token0_symbol__in: HashSet<String>,
@@ -189,6 +211,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token1_address__in: HashSet<Address>,
@@ -196,6 +219,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token1_symbol__in: HashSet<String>,
@@ -203,6 +227,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens_address__in: HashSet<Address>,
@@ -210,6 +235,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens_symbol__in: HashSet<String>,
@@ -222,6 +248,7 @@ impl Default for GetPricesRequest {
             from_block: Bound::default(),
             to_block: Bound::default(),
             pair_address__in: HashSet::new(),
+            pair_address__not_in: HashSet::new(),
             pair_factory_address__in: HashSet::new(),
             event__in: HashSet::new(),
             reserve0__gte: None,
@@ -4,16 +4,17 @@ use std::collections::HashSet;
 use ethers_core::types::Address;
 
 use crate::{
-    core::types::{default_chains, ChainId},
+    core::types::{amount::Amount, default_chains, ChainId},
     query::Bound,
-    utils::serialize_comma_separated,
+    utils::{deserialize_comma_separated, serialize_comma_separated},
 };
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetFeesRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -28,6 +29,7 @@ pub struct GetFeesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_address__in: HashSet<Address>,
@@ -35,6 +37,7 @@ pub struct GetFeesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sender__in: HashSet<Address>,
@@ -42,21 +45,22 @@ pub struct GetFeesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub recipient__in: HashSet<Address>,
 
     #[serde(default)]
-    pub amount0__gte: Option<f64>,
+    pub amount0__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount0__lte: Option<f64>,
+    pub amount0__lte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__gte: Option<f64>,
+    pub amount1__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__lte: Option<f64>,
+    pub amount1__lte: Option<Amount>,
 
     #[serde(default)]
     pub tick_lower__gte: Option<i32>,
@@ -100,12 +104,13 @@ impl Default for GetFeesRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetPoolsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -120,6 +125,7 @@ pub struct GetPoolsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_address__in: HashSet<Address>,
@@ -127,6 +133,7 @@ pub struct GetPoolsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub factory_address__in: HashSet<Address>,
@@ -134,6 +141,7 @@ pub struct GetPoolsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token0__in: HashSet<Address>,
@@ -141,6 +149,7 @@ pub struct GetPoolsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token1__in: HashSet<Address>,
@@ -148,6 +157,7 @@ pub struct GetPoolsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens__in: HashSet<Address>,
@@ -181,12 +191,13 @@ impl Default for GetPoolsRequest {
         }
     }
 }
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetPositionsRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -201,6 +212,7 @@ pub struct GetPositionsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_address__in: HashSet<Address>,
@@ -208,6 +220,7 @@ pub struct GetPositionsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sender__in: HashSet<Address>,
@@ -215,21 +228,22 @@ pub struct GetPositionsRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub recipient__in: HashSet<Address>,
 
     #[serde(default)]
-    pub amount0__gte: Option<f64>,
+    pub amount0__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount0__lte: Option<f64>,
+    pub amount0__lte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__gte: Option<f64>,
+    pub amount1__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__lte: Option<f64>,
+    pub amount1__lte: Option<Amount>,
 
     #[serde(default)]
     pub tick_lower__gte: Option<i32>,
@@ -273,12 +287,13 @@ impl Default for GetPositionsRequest {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[allow(non_snake_case)]
 pub struct GetPricesRequest {
     #[serde(default = "default_chains")]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub chains: HashSet<ChainId>,
@@ -293,6 +308,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_address__in: HashSet<Address>,
@@ -300,31 +316,41 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
+        skip_serializing_if = "HashSet::is_empty"
+    )]
+    pub pool_address__not_in: HashSet<Address>,
+
+    #[serde(default)]
+    #[serde(
+        serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub pool_factory_address__in: HashSet<Address>,
 
     #[serde(default)]
-    pub virtual0__gte: Option<f64>,
+    pub virtual0__gte: Option<Amount>,
 
     #[serde(default)]
-    pub virtual0__lte: Option<f64>,
+    pub virtual0__lte: Option<Amount>,
 
     #[serde(default)]
-    pub virtual1__gte: Option<f64>,
+    pub virtual1__gte: Option<Amount>,
 
     #[serde(default)]
-    pub virtual1__lte: Option<f64>,
+    pub virtual1__lte: Option<Amount>,
 
     #[serde(default)]
-    pub price__gte: Option<f64>,
+    pub price__gte: Option<Amount>,
 
     #[serde(default)]
-    pub price__lte: Option<f64>,
+    pub price__lte: Option<Amount>,
 
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub sender__in: HashSet<Address>,
@@ -332,27 +358,28 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub receiver__in: HashSet<Address>,
 
     #[serde(default)]
-    pub amount0__gte: Option<f64>,
+    pub amount0__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount0__lte: Option<f64>,
+    pub amount0__lte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__gte: Option<f64>,
+    pub amount1__gte: Option<Amount>,
 
     #[serde(default)]
-    pub amount1__lte: Option<f64>,
+    pub amount1__lte: Option<Amount>,
 
     #[serde(default)]
-    pub liquidity__gte: Option<f64>,
+    pub liquidity__gte: Option<Amount>,
 
     #[serde(default)]
-    pub liquidity__lte: Option<f64>,
+    pub liquidity__lte: Option<Amount>,
 
     #[serde(default)]
     pub tick__gte: Option<i32>,
@@ -363,6 +390,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token0_address__in: HashSet<Address>,
@@ -370,6 +398,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token0_symbol__in: HashSet<String>,
@@ -377,6 +406,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token1_address__in: HashSet<Address>,
@@ -384,6 +414,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub token1_symbol__in: HashSet<String>,
@@ -391,6 +422,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens_address__in: HashSet<Address>,
@@ -398,6 +430,7 @@ pub struct GetPricesRequest {
     #[serde(default)]
     #[serde(
         serialize_with = "serialize_comma_separated",
+        deserialize_with = "deserialize_comma_separated",
         skip_serializing_if = "HashSet::is_empty"
     )]
     pub tokens_symbol__in: HashSet<String>,
@@ -424,6 +457,7 @@ impl Default for GetPricesRequest {
             tokens_address__in: HashSet::new(),
             tokens_symbol__in: HashSet::new(),
             pool_address__in: HashSet::new(),
+            pool_address__not_in: HashSet::new(),
             pool_factory_address__in: HashSet::new(),
             virtual0__gte: None,
             virtual0__lte: None,
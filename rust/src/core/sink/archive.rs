@@ -0,0 +1,492 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Cursor, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use ethers_core::utils::hex;
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{part_path, Rotation};
+use crate::core::{
+    error::{Error, ResponseError, Result},
+    provider::ResponseStream,
+    stream::{json_lines, json_u64},
+    types::ChainId,
+};
+
+/// How the files of an archive are compressed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "ndjson.gz",
+            Self::Zstd => "ndjson.zst",
+        }
+    }
+}
+
+/// A file of an archive as recorded in its [`Manifest`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    /// Relative to the archive directory
+    pub name: String,
+    pub compression: Compression,
+    /// The request the records of the file are the response to
+    pub request: Option<Value>,
+    /// First and last block of the records, `None` if they have no block
+    pub range: Option<(u64, u64)>,
+    pub records: usize,
+    pub bytes: u64,
+    /// Hex encoded SHA-256 of the compressed file
+    pub sha256: String,
+}
+
+/// The `manifest.json` listing the files of an archive directory in the order
+/// they were written
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<ArchiveFile>,
+}
+
+impl Manifest {
+    pub const FILE_NAME: &'static str = "manifest.json";
+
+    /// Reads the manifest of `dir`, empty if there is none yet
+    pub fn load(dir: &Path) -> Result<Self> {
+        match fs::read(dir.join(Self::FILE_NAME)) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        let tmp = dir.join(format!(".{}.tmp", Self::FILE_NAME));
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, dir.join(Self::FILE_NAME))?;
+        Ok(())
+    }
+}
+
+/// Hashes and counts the bytes written to a file
+struct Hashed {
+    file: File,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl Write for Hashed {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.file.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        self.bytes += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+enum Encoder {
+    Gzip(GzEncoder<Hashed>),
+    Zstd(zstd::Encoder<'static, Hashed>),
+}
+
+impl Encoder {
+    fn new(compression: Compression, file: File) -> Result<Self> {
+        let hashed = Hashed {
+            file,
+            hasher: Sha256::new(),
+            bytes: 0,
+        };
+
+        Ok(match compression {
+            Compression::Gzip => Self::Gzip(GzEncoder::new(hashed, Default::default())),
+            Compression::Zstd => Self::Zstd(zstd::Encoder::new(hashed, 0)?),
+        })
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Gzip(encoder) => encoder.write_all(buf),
+            Self::Zstd(encoder) => encoder.write_all(buf),
+        }
+    }
+
+    fn bytes(&self) -> u64 {
+        match self {
+            Self::Gzip(encoder) => encoder.get_ref().bytes,
+            Self::Zstd(encoder) => encoder.get_ref().bytes,
+        }
+    }
+
+    fn finish(self) -> io::Result<Hashed> {
+        let hashed = match self {
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        hashed.file.sync_all()?;
+        Ok(hashed)
+    }
+}
+
+struct Part {
+    encoder: Encoder,
+    tmp: PathBuf,
+    records: usize,
+    range: Option<(u64, u64)>,
+    opened: Instant,
+}
+
+/// Archives raw [`Format::JsonStream`](crate::Format::JsonStream) responses to
+/// compressed NDJSON files
+///
+/// Files are written to `<dir>/<chain>/<endpoint>/` and named like the files
+/// of the [`ParquetSink`](super::ParquetSink). Every closed file is added to
+/// the [`Manifest`] of the directory with the request, its block range and its
+/// checksum, so an archive can be extended by later runs and replayed with an
+/// [`ArchiveReader`].
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use pangea_client::{
+///     provider::ChainProvider, requests::logs::GetLogsRequest, sink::ArchiveSink, ChainId,
+///     ClientBuilder, Format, WsProvider,
+/// };
+///
+/// # async fn run() -> pangea_client::Result<()> {
+/// let client = ClientBuilder::default().build::<WsProvider>().await?;
+/// let request = GetLogsRequest::default();
+///
+/// let sink = ArchiveSink::new("audit", ChainId::ETH, "logs").with_request(&request)?;
+/// let response = client.get_logs_by_format(request, Format::JsonStream, false).await?;
+/// let mut stream = sink.tee(response);
+/// while let Some(chunk) = stream.next().await {
+///     // the response is passed on unchanged
+///     let chunk = chunk?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ArchiveSink {
+    dir: PathBuf,
+    rotation: Rotation,
+    compression: Compression,
+    request: Option<Value>,
+    part: Option<Part>,
+    files: Vec<PathBuf>,
+}
+
+impl ArchiveSink {
+    pub fn new(dir: impl Into<PathBuf>, chain: ChainId, endpoint: &str) -> Self {
+        Self {
+            dir: dir.into().join(chain.chain_code()).join(endpoint),
+            rotation: Rotation::default(),
+            compression: Compression::default(),
+            request: None,
+            part: None,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Records `request` in the manifest entry of every file
+    pub fn with_request<R>(mut self, request: &R) -> Result<Self>
+    where
+        R: Serialize,
+    {
+        self.request = Some(serde_json::to_value(request)?);
+        Ok(self)
+    }
+
+    /// Archives every line of a raw response and closes the last file,
+    /// returning the paths of all written files
+    pub async fn write_stream(
+        mut self,
+        stream: ResponseStream<Vec<u8>>,
+    ) -> Result<Vec<PathBuf>> {
+        let mut lines = json_lines(stream);
+        while let Some(line) = lines.next().await {
+            self.write_line(&line?)?;
+        }
+
+        self.finish()
+    }
+
+    /// Passes a raw response on unchanged while archiving its lines
+    ///
+    /// The last file is closed when the response ends, and the current one
+    /// once it is older than the [`Rotation::max_age`] even if the response is
+    /// quiet. Failures to archive are returned as errors of the stream.
+    pub fn tee(self, stream: ResponseStream<Vec<u8>>) -> ResponseStream<Vec<u8>> {
+        let state = (Some(self), stream, Vec::new());
+
+        futures::stream::unfold(state, |(sink, mut stream, mut buffer)| async move {
+            let mut sink = sink?;
+            let next = loop {
+                let Some(deadline) = sink.deadline() else {
+                    break stream.next().await;
+                };
+                tokio::select! {
+                    next = stream.next() => break next,
+                    _ = tokio::time::sleep_until(deadline.into()) => {
+                        if let Err(err) = sink.rotate() {
+                            return Some((Err(err), (Some(sink), stream, buffer)));
+                        }
+                    }
+                }
+            };
+
+            match next {
+                Some(Ok(chunk)) => {
+                    buffer.extend_from_slice(&chunk);
+                    let mut archived = Ok(());
+                    while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                        let line = buffer.drain(..=pos).collect::<Vec<_>>();
+                        archived = archived.and_then(|_| sink.write_line(&line));
+                    }
+                    let item = archived.map(|_| chunk);
+                    Some((item, (Some(sink), stream, buffer)))
+                }
+                Some(Err(err)) => Some((Err(err), (Some(sink), stream, buffer))),
+                None => {
+                    let finished = sink.write_line(&buffer).and_then(|_| sink.finish());
+                    finished
+                        .err()
+                        .map(|err| (Err(err), (None, stream, Vec::new())))
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// Archives one JSON line, error responses of the server are skipped
+    pub fn write_line(&mut self, line: &[u8]) -> Result<()> {
+        let line = line.trim_ascii();
+        if line.is_empty() || serde_json::from_slice::<ResponseError>(line).is_ok() {
+            return Ok(());
+        }
+
+        let block = serde_json::from_slice::<Value>(line)?;
+        let block = block
+            .get("block_number")
+            .or_else(|| block.get("height"))
+            .and_then(json_u64);
+
+        if let (Some(max), Some(block), Some(part)) =
+            (self.rotation.max_blocks, block, &self.part)
+        {
+            if part
+                .range
+                .is_some_and(|(from, _)| block >= from.saturating_add(max.max(1)))
+            {
+                self.rotate()?;
+            }
+        }
+
+        let part = match &mut self.part {
+            Some(part) => part,
+            part @ None => {
+                part.insert(Part::create(&self.dir, self.files.len(), self.compression)?)
+            }
+        };
+
+        part.encoder.write_all(line)?;
+        part.encoder.write_all(b"\n")?;
+        part.records += 1;
+        if let Some(block) = block {
+            part.range = Some(match part.range {
+                Some((from, to)) => (from.min(block), to.max(block)),
+                None => (block, block),
+            });
+        }
+
+        let is_full = self
+            .rotation
+            .max_rows
+            .is_some_and(|max| part.records >= max)
+            || self
+                .rotation
+                .max_bytes
+                .is_some_and(|max| part.encoder.bytes() >= max as u64)
+            || self
+                .rotation
+                .max_age
+                .is_some_and(|max| part.opened.elapsed() >= max);
+        if is_full {
+            self.rotate()?;
+        }
+
+        Ok(())
+    }
+
+    /// When the current file reaches its [`Rotation::max_age`]
+    fn deadline(&self) -> Option<Instant> {
+        Some(self.part.as_ref()?.opened + self.rotation.max_age?)
+    }
+
+    /// Closes the current file, returning the paths of all written files
+    pub fn finish(mut self) -> Result<Vec<PathBuf>> {
+        self.rotate()?;
+        Ok(self.files)
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let Some(part) = self.part.take() else {
+            return Ok(());
+        };
+
+        let hashed = part.encoder.finish()?;
+        if part.records == 0 {
+            fs::remove_file(&part.tmp)?;
+            return Ok(());
+        }
+
+        // files of earlier runs are in the manifest
+        let mut manifest = Manifest::load(&self.dir)?;
+        let written = manifest
+            .files
+            .iter()
+            .map(|file| self.dir.join(&file.name))
+            .collect::<Vec<_>>();
+        let path = part_path(
+            &self.dir,
+            part.range,
+            &written,
+            self.compression.extension(),
+        );
+        fs::rename(&part.tmp, &path)?;
+
+        manifest.files.push(ArchiveFile {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            compression: self.compression,
+            request: self.request.clone(),
+            range: part.range,
+            records: part.records,
+            bytes: hashed.bytes,
+            sha256: hex::encode(hashed.hasher.finalize()),
+        });
+        manifest.save(&self.dir)?;
+        self.files.push(path);
+
+        Ok(())
+    }
+}
+
+impl Part {
+    fn create(dir: &Path, index: usize, compression: Compression) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!(".part-{index:06}.{}.tmp", compression.extension()));
+
+        Ok(Self {
+            encoder: Encoder::new(compression, File::create(&tmp)?)?,
+            tmp,
+            records: 0,
+            range: None,
+            opened: Instant::now(),
+        })
+    }
+}
+
+/// Replays an archive directory written by an [`ArchiveSink`]
+///
+/// The files are read in the order of the manifest and checked against their
+/// checksum before any of their lines is delivered.
+///
+/// ```no_run
+/// use pangea_client::{core::stream::json_records, sink::ArchiveReader};
+///
+/// # fn run() -> pangea_client::Result<()> {
+/// let reader = ArchiveReader::open("audit/ETH/logs")?;
+/// let logs = json_records::<serde_json::Value>(reader.stream());
+/// # Ok(())
+/// # }
+/// ```
+pub struct ArchiveReader {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl ArchiveReader {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let bytes = fs::read(dir.join(Manifest::FILE_NAME))?;
+        let manifest = serde_json::from_slice(&bytes)?;
+
+        Ok(Self { dir, manifest })
+    }
+
+    pub fn files(&self) -> &[ArchiveFile] {
+        &self.manifest.files
+    }
+
+    /// The archived response, one line per item like a
+    /// [`Format::JsonStream`](crate::Format::JsonStream) response
+    pub fn stream(self) -> ResponseStream<Vec<u8>> {
+        let files = VecDeque::from(self.manifest.files);
+        let state = (self.dir, files, None::<Box<dyn BufRead + Send>>);
+
+        futures::stream::unfold(state, |(dir, mut files, mut reader)| async move {
+            loop {
+                let current = match &mut reader {
+                    Some(current) => current,
+                    None => match files.pop_front() {
+                        Some(file) => match open_file(&dir, &file).await {
+                            Ok(opened) => reader.insert(opened),
+                            Err(err) => return Some((Err(err), (dir, VecDeque::new(), None))),
+                        },
+                        None => return None,
+                    },
+                };
+
+                let mut line = Vec::new();
+                match current.read_until(b'\n', &mut line) {
+                    Ok(0) => reader = None,
+                    Ok(_) => return Some((Ok(line), (dir, files, reader))),
+                    Err(err) => return Some((Err(err.into()), (dir, VecDeque::new(), None))),
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Reads and verifies a file, returning a reader of its decompressed lines
+async fn open_file(dir: &Path, file: &ArchiveFile) -> Result<Box<dyn BufRead + Send>> {
+    let bytes = tokio::fs::read(dir.join(&file.name)).await?;
+    if hex::encode(Sha256::digest(&bytes)) != file.sha256 {
+        return Err(Error::ArchiveChecksum(file.name.clone()));
+    }
+
+    Ok(match file.compression {
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(Cursor::new(bytes)))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::new(Cursor::new(bytes))?)),
+    })
+}
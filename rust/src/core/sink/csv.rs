@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use arrow::{
+    array::RecordBatch,
+    util::display::{ArrayFormatter, FormatOptions},
+};
+use csv_async::{AsyncWriter, AsyncWriterBuilder};
+use ethers_core::{
+    types::{Address, U256},
+    utils::to_checksum,
+};
+use futures::{io::AsyncWrite, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::{error::Result, provider::ResponseStream};
+
+/// How the values of a column are written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CellFormat {
+    /// `0x` strings of 20 bytes are written as addresses, of 32 bytes as they
+    /// are, and any other `0x` string as a decimal number
+    #[default]
+    Auto,
+    /// Written as received
+    Raw,
+    /// `0x` hex numbers are written in decimal
+    Decimal,
+    /// Addresses are written with their EIP-55 checksum
+    Checksum,
+    /// Addresses are written in lowercase
+    Lowercase,
+}
+
+/// Writes records as CSV, one row per record
+///
+/// Typed records are serialized to JSON objects first, nested values are
+/// written as JSON. Unless columns are set, they are the fields of the first
+/// record sorted by name, since JSON objects do not keep the order of their
+/// fields, or the fields of the schema in order for Arrow batches.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use pangea_client::{
+///     requests::transfers::GetTransfersRequest, sink::CsvSink, ClientBuilder, WsProvider,
+/// };
+///
+/// # async fn run() -> pangea_client::Result<()> {
+/// let client = ClientBuilder::default().build::<WsProvider>().await?;
+/// let transfers = client
+///     .follow::<_, serde_json::Value>(GetTransfersRequest::default())
+///     .await?
+///     .take(100)
+///     .boxed();
+///
+/// let csv = CsvSink::new(Vec::new())
+///     .columns(["block_number", "from", "to", "value"])
+///     .write_stream(transfers)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CsvSink<W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    builder: AsyncWriterBuilder,
+    writer: Option<AsyncWriter<W>>,
+    inner: Option<W>,
+    columns: Option<Vec<String>>,
+    has_headers: bool,
+    checksum: bool,
+    formats: HashMap<String, CellFormat>,
+}
+
+impl<W> CsvSink<W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    pub fn new(writer: W) -> Self {
+        let mut builder = AsyncWriterBuilder::new();
+        builder.has_headers(false);
+
+        Self {
+            builder,
+            writer: None,
+            inner: Some(writer),
+            columns: None,
+            has_headers: true,
+            checksum: true,
+            formats: HashMap::new(),
+        }
+    }
+
+    /// Sets the columns and their order, missing fields are left empty
+    ///
+    /// Set them to write the fields of records in any order other than by name
+    pub fn columns<I>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Whether a header row is written first, default is true
+    pub fn headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.builder.delimiter(delimiter);
+        self
+    }
+
+    /// Whether [`CellFormat::Auto`] columns write addresses with their
+    /// checksum, default is true
+    pub fn checksum_addresses(mut self, checksum: bool) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    pub fn format(mut self, column: impl Into<String>, format: CellFormat) -> Self {
+        self.formats.insert(column.into(), format);
+        self
+    }
+
+    /// Writes every record of `stream` and flushes, returning the writer
+    pub async fn write_stream<V>(mut self, mut stream: ResponseStream<V>) -> Result<W>
+    where
+        V: Serialize,
+    {
+        while let Some(record) = stream.next().await {
+            self.write(&record?).await?;
+        }
+
+        self.finish().await
+    }
+
+    /// Writes every batch of `stream`, see
+    /// [`arrow_batches`](crate::core::stream::arrow_batches), and flushes,
+    /// returning the writer
+    pub async fn write_batches(mut self, mut stream: ResponseStream<RecordBatch>) -> Result<W> {
+        while let Some(batch) = stream.next().await {
+            self.write_batch(&batch?).await?;
+        }
+
+        self.finish().await
+    }
+
+    pub async fn write<V>(&mut self, record: &V) -> Result<()>
+    where
+        V: Serialize,
+    {
+        let record = serde_json::to_value(record)?;
+        let Value::Object(mut fields) = record else {
+            return self
+                .write_row(vec!["value".to_string()], vec![Some(record)])
+                .await;
+        };
+
+        let columns = match &self.columns {
+            Some(columns) => columns.clone(),
+            None => fields.keys().cloned().collect(),
+        };
+        let cells = columns.iter().map(|column| fields.remove(column)).collect();
+        self.write_row(columns, cells).await
+    }
+
+    pub async fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let columns = match &self.columns {
+            Some(columns) => columns.clone(),
+            None => batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .collect(),
+        };
+
+        let options = FormatOptions::default();
+        let formatters = columns
+            .iter()
+            .map(|column| {
+                batch
+                    .column_by_name(column)
+                    .map(|array| ArrayFormatter::try_new(array.as_ref(), &options))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for row in 0..batch.num_rows() {
+            let cells = formatters
+                .iter()
+                .map(|formatter| {
+                    let formatter = formatter.as_ref()?;
+                    Some(Value::String(formatter.value(row).to_string()))
+                })
+                .collect();
+            self.write_row(columns.clone(), cells).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the written rows and returns the writer
+    pub async fn finish(mut self) -> Result<W> {
+        match self.writer.take() {
+            Some(mut writer) => {
+                writer.flush().await?;
+                writer.into_inner().await.map_err(Into::into)
+            }
+            None => Ok(self.inner.take().expect("the writer is only taken once")),
+        }
+    }
+
+    async fn write_row(
+        &mut self,
+        columns: Vec<String>,
+        cells: Vec<Option<Value>>,
+    ) -> Result<()> {
+        if self.writer.is_none() {
+            let inner = self.inner.take().expect("the writer is only taken once");
+            let mut writer = self.builder.create_writer(inner);
+            // later records are written with the columns of the first one
+            self.columns.get_or_insert_with(|| columns.clone());
+            if self.has_headers {
+                writer.write_record(&columns).await?;
+            }
+            self.writer = Some(writer);
+        }
+
+        let row = columns
+            .iter()
+            .zip(cells)
+            .map(|(column, cell)| {
+                let format = self.formats.get(column).copied().unwrap_or_default();
+                cell.map(|value| self.format_cell(value, format))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(writer) = &mut self.writer {
+            writer.write_record(&row).await?;
+        }
+
+        Ok(())
+    }
+
+    fn format_cell(&self, value: Value, format: CellFormat) -> String {
+        let text = match value {
+            Value::Null => return String::new(),
+            Value::String(text) => text,
+            value => return value.to_string(),
+        };
+        let Some(hex) = text.strip_prefix("0x") else {
+            return text;
+        };
+
+        let format = match format {
+            CellFormat::Auto => match hex.len() {
+                40 if self.checksum => CellFormat::Checksum,
+                40 => CellFormat::Lowercase,
+                64 => CellFormat::Raw,
+                _ => CellFormat::Decimal,
+            },
+            format => format,
+        };
+
+        match format {
+            CellFormat::Decimal => U256::from_str_radix(hex, 16)
+                .map(|n| n.to_string())
+                .unwrap_or(text),
+            CellFormat::Checksum => text
+                .parse::<Address>()
+                .map(|address| to_checksum(&address, None))
+                .unwrap_or(text),
+            CellFormat::Lowercase => text.to_lowercase(),
+            CellFormat::Auto | CellFormat::Raw => text,
+        }
+    }
+}
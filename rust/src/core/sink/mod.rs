@@ -0,0 +1,89 @@
+//! Writers that persist the responses of the client to disk
+//!
+//! File sinks split their output over several files according to a [`Rotation`].
+//! Files are written under a temporary name and renamed once complete, so a
+//! file with its final name is never partially written.
+
+#[cfg(any(feature = "parquet", feature = "archive"))]
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(feature = "archive")]
+mod archive;
+mod csv;
+#[cfg(feature = "parquet")]
+mod parquet;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "archive")]
+pub use self::archive::{ArchiveFile, ArchiveReader, ArchiveSink, Compression, Manifest};
+pub use self::csv::{CellFormat, CsvSink};
+#[cfg(feature = "parquet")]
+pub use self::parquet::ParquetSink;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteSink;
+
+/// When a sink closes its current file and starts a new one
+///
+/// A file is closed as soon as any of the limits is reached. Without limits
+/// everything is written to one file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rotation {
+    pub max_rows: Option<usize>,
+    /// Approximate, checked after every write
+    pub max_bytes: Option<usize>,
+    /// Largest number of blocks covered by one file
+    pub max_blocks: Option<u64>,
+    /// Longest time a file is kept open, checked after every write and while
+    /// `ArchiveSink::tee` waits for the response
+    pub max_age: Option<Duration>,
+}
+
+impl Rotation {
+    pub fn rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    pub fn bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn blocks(mut self, max_blocks: u64) -> Self {
+        self.max_blocks = Some(max_blocks);
+        self
+    }
+
+    pub fn age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+}
+
+/// The path of the next part covering the blocks `from..=to`, zero padded so
+/// the files sort by block
+///
+/// Parts that cover the same blocks, because a block did not fit in one
+/// file, are told apart by a counter. Parts without blocks are numbered.
+#[cfg(any(feature = "parquet", feature = "archive"))]
+pub(crate) fn part_path(
+    dir: &Path,
+    range: Option<(u64, u64)>,
+    written: &[PathBuf],
+    extension: &str,
+) -> PathBuf {
+    let Some((from, to)) = range else {
+        return dir.join(format!("part-{:06}.{extension}", written.len()));
+    };
+
+    let mut path = dir.join(format!("{from:012}-{to:012}.{extension}"));
+    let mut duplicate = 0;
+    while written.contains(&path) {
+        duplicate += 1;
+        path = dir.join(format!("{from:012}-{to:012}-{duplicate}.{extension}"));
+    }
+
+    path
+}
@@ -0,0 +1,243 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use arrow::{
+    array::{AsArray, RecordBatch},
+    compute::cast,
+    datatypes::{DataType, SchemaRef, UInt64Type},
+};
+use futures::StreamExt;
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+
+use super::{part_path, Rotation};
+use crate::core::{
+    error::Result, provider::ResponseStream, stream::arrow_batches, types::ChainId,
+};
+
+struct Part {
+    writer: ArrowWriter<File>,
+    schema: SchemaRef,
+    tmp: PathBuf,
+    rows: usize,
+    range: Option<(u64, u64)>,
+    opened: Instant,
+}
+
+/// Writes Arrow record batches to Parquet files
+///
+/// Files are written to `<dir>/<chain>/<endpoint>/` and named after the first
+/// and last block they contain, like `000017000000-000017009999.parquet`, or
+/// numbered if the records have no `block_number` or `height` column. Records
+/// are expected in block order.
+///
+/// ```no_run
+/// use pangea_client::{
+///     provider::ChainProvider, requests::logs::GetLogsRequest, sink::ParquetSink, ChainId,
+///     ClientBuilder, Format, WsProvider,
+/// };
+///
+/// # async fn run() -> pangea_client::Result<()> {
+/// let client = ClientBuilder::default().build::<WsProvider>().await?;
+/// let request = GetLogsRequest::default();
+///
+/// let files = ParquetSink::new("data", ChainId::ETH, "logs")
+///     .write_stream(client.get_logs_by_format(request, Format::ArrowStream, false).await?)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ParquetSink {
+    dir: PathBuf,
+    rotation: Rotation,
+    properties: WriterProperties,
+    part: Option<Part>,
+    files: Vec<PathBuf>,
+}
+
+impl ParquetSink {
+    pub fn new(dir: impl Into<PathBuf>, chain: ChainId, endpoint: &str) -> Self {
+        Self {
+            dir: dir.into().join(chain.chain_code()).join(endpoint),
+            rotation: Rotation::default(),
+            properties: WriterProperties::default(),
+            part: None,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Writes every record batch of a raw [`Format::ArrowStream`] response and
+    /// closes the last file, returning the paths of all written files
+    ///
+    /// [`Format::ArrowStream`]: crate::Format::ArrowStream
+    pub async fn write_stream(
+        mut self,
+        stream: ResponseStream<Vec<u8>>,
+    ) -> Result<Vec<PathBuf>> {
+        let mut batches = arrow_batches(stream);
+        while let Some(batch) = batches.next().await {
+            self.write_batch(&batch?)?;
+        }
+
+        self.finish()
+    }
+
+    pub fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let blocks = block_numbers(batch)?;
+        let max_rows = self.rotation.max_rows.map(|max| max.max(1));
+        let max_blocks = self.rotation.max_blocks.map(|max| max.max(1));
+
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            if self
+                .part
+                .as_ref()
+                .is_some_and(|part| part.schema != batch.schema())
+            {
+                self.rotate()?;
+            }
+            let part = match &mut self.part {
+                Some(part) => part,
+                part @ None => part.insert(Part::create(
+                    &self.dir,
+                    self.files.len(),
+                    batch.schema(),
+                    self.properties.clone(),
+                )?),
+            };
+
+            let mut len = batch.num_rows() - offset;
+            if let Some(max) = max_rows {
+                len = len.min(max.saturating_sub(part.rows));
+            }
+            if let (Some(max), Some(blocks)) = (max_blocks, &blocks) {
+                let rest = &blocks[offset..offset + len];
+                let start = part
+                    .range
+                    .map(|(from, _)| from)
+                    .or_else(|| rest.iter().flatten().next().copied());
+                if let Some(start) = start {
+                    let end = start.saturating_add(max);
+                    if let Some(cut) = rest.iter().position(|b| b.is_some_and(|b| b >= end)) {
+                        len = cut;
+                    }
+                }
+            }
+
+            if len == 0 {
+                self.rotate()?;
+                continue;
+            }
+
+            let slice = blocks.as_ref().map(|blocks| &blocks[offset..offset + len]);
+            part.write(&batch.slice(offset, len), slice)?;
+            offset += len;
+
+            let is_full = max_rows.is_some_and(|max| part.rows >= max)
+                || self
+                    .rotation
+                    .max_bytes
+                    .is_some_and(|max| part.size() >= max)
+                || self
+                    .rotation
+                    .max_age
+                    .is_some_and(|max| part.opened.elapsed() >= max);
+            if is_full {
+                self.rotate()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Closes the current file, returning the paths of all written files
+    pub fn finish(mut self) -> Result<Vec<PathBuf>> {
+        self.rotate()?;
+        Ok(self.files)
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let Some(part) = self.part.take() else {
+            return Ok(());
+        };
+
+        if part.rows == 0 {
+            part.writer.close()?;
+            fs::remove_file(&part.tmp)?;
+            return Ok(());
+        }
+
+        let path = part_path(&self.dir, part.range, &self.files, "parquet");
+        part.writer.close()?;
+        fs::rename(&part.tmp, &path)?;
+        self.files.push(path);
+
+        Ok(())
+    }
+}
+
+impl Part {
+    fn create(
+        dir: &Path,
+        index: usize,
+        schema: SchemaRef,
+        properties: WriterProperties,
+    ) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!(".part-{index:06}.parquet.tmp"));
+        let writer =
+            ArrowWriter::try_new(File::create(&tmp)?, schema.clone(), Some(properties))?;
+
+        Ok(Self {
+            writer,
+            schema,
+            tmp,
+            rows: 0,
+            range: None,
+            opened: Instant::now(),
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch, blocks: Option<&[Option<u64>]>) -> Result<()> {
+        self.writer.write(batch)?;
+        self.rows += batch.num_rows();
+
+        for block in blocks.into_iter().flatten().flatten() {
+            self.range = Some(match self.range {
+                Some((from, to)) => (from.min(*block), to.max(*block)),
+                None => (*block, *block),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.writer.bytes_written() + self.writer.in_progress_size()
+    }
+}
+
+/// The block number of every row, `None` if the batch has no block column
+fn block_numbers(batch: &RecordBatch) -> Result<Option<Vec<Option<u64>>>> {
+    let Some(column) = batch
+        .column_by_name("block_number")
+        .or_else(|| batch.column_by_name("height"))
+    else {
+        return Ok(None);
+    };
+
+    let column = cast(column, &DataType::UInt64)?;
+    Ok(Some(column.as_primitive::<UInt64Type>().iter().collect()))
+}
@@ -0,0 +1,397 @@
+use std::path::Path;
+
+use arrow::{
+    array::RecordBatch,
+    datatypes::{DataType, Schema},
+    json::ArrayWriter,
+};
+use futures::StreamExt;
+use rusqlite::{params_from_iter, types::Value as SqlValue, Connection};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::core::{
+    checkpoint::{Checkpointed, SqliteCheckpointStore},
+    error::{Error, Result},
+    provider::ResponseStream,
+};
+
+/// The columns the natural key is picked from, in order, if it is not set
+const NATURAL_KEY: [&str; 4] = ["chain", "block_number", "transaction_index", "log_index"];
+
+/// Upserts records into a table of an SQLite database
+///
+/// The table is created from the fields of the first record, or from the
+/// Arrow schema, and gains a column for every new field later on. Records are
+/// upserted by their natural key, by default the fields of `chain`,
+/// `block_number`, `transaction_index` and `log_index` they have, so writing a
+/// record twice keeps one row. Key columns are `NOT NULL`, a record without
+/// one of them is stored with 0 in it. A record whose `removed` field is
+/// `true` is deleted instead.
+///
+/// [`SqliteSink::write_stream`] writes every block in one transaction together
+/// with the checkpoint of the job, so the table and the checkpoint never
+/// disagree. Resume the job from the same database with a
+/// [`SqliteCheckpointStore`].
+pub struct SqliteSink {
+    conn: Connection,
+    table: String,
+    job: Option<String>,
+    key: Option<Vec<String>>,
+    retraction_field: String,
+    columns: Vec<String>,
+}
+
+impl SqliteSink {
+    /// Opens or creates the database at `path`
+    pub fn open(path: impl AsRef<Path>, table: impl Into<String>) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?, table)
+    }
+
+    pub fn from_connection(conn: Connection, table: impl Into<String>) -> Result<Self> {
+        SqliteCheckpointStore::create_table(&conn)?;
+        let mut sink = Self {
+            conn,
+            table: table.into(),
+            job: None,
+            key: None,
+            retraction_field: "removed".to_string(),
+            columns: Vec::new(),
+        };
+        sink.load_table()?;
+
+        Ok(sink)
+    }
+
+    /// Stores the checkpoint of `job` with every block written by
+    /// [`SqliteSink::write_stream`]
+    pub fn job(mut self, job: impl Into<String>) -> Self {
+        self.job = Some(job.into());
+        self
+    }
+
+    /// Sets the columns records are upserted by
+    pub fn key<I>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.key = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the boolean field that marks a record as retracted, default is
+    /// `removed`
+    pub fn retraction_field(mut self, field: impl Into<String>) -> Self {
+        self.retraction_field = field.into();
+        self
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// The last block written for the job
+    pub fn checkpoint(&self) -> Result<Option<u64>> {
+        match &self.job {
+            Some(job) => SqliteCheckpointStore::load_in(&self.conn, job),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes the records of a [`Client::resume`](crate::Client::resume)
+    /// stream, committing every block with its checkpoint
+    pub async fn write_stream<V>(
+        &mut self,
+        mut stream: ResponseStream<Checkpointed<V>>,
+    ) -> Result<()>
+    where
+        V: Serialize,
+    {
+        let mut block = Vec::new();
+        while let Some(event) = stream.next().await {
+            match event? {
+                Checkpointed::Record(record) => block.push(serde_json::to_value(record)?),
+                Checkpointed::BlockEnd(block_number) => {
+                    self.write_block(std::mem::take(&mut block), Some(block_number))?;
+                }
+            }
+        }
+
+        // the last block is incomplete without its end, keep its checkpoint
+        self.write_block(block, None)
+    }
+
+    /// Writes `records` in one transaction, recording `block_number` as the
+    /// checkpoint of the job
+    pub fn write_block(
+        &mut self,
+        records: Vec<Value>,
+        block_number: Option<u64>,
+    ) -> Result<()> {
+        let records = records
+            .into_iter()
+            .map(|record| match record {
+                Value::Object(fields) => Ok(fields),
+                record => Err(Error::SinkSchema(format!("not an object: {record}"))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for record in &records {
+            self.add_columns(record.iter().map(|(name, value)| (name, sql_type(value))))?;
+        }
+
+        let key = if records.is_empty() {
+            Vec::new()
+        } else {
+            self.resolved_key()?
+        };
+        let tx = self.conn.transaction()?;
+        for record in &records {
+            let is_retraction = record
+                .get(&self.retraction_field)
+                .and_then(Value::as_bool)
+                .unwrap_or_default();
+
+            if is_retraction {
+                let sql = format!(
+                    "DELETE FROM {} WHERE {}",
+                    quote(&self.table),
+                    key.iter()
+                        .map(|column| format!("{} IS ?", quote(column)))
+                        .collect::<Vec<_>>()
+                        .join(" AND "),
+                );
+                let values = key.iter().map(|column| column_value(record, column, &key));
+                tx.execute(&sql, params_from_iter(values))?;
+                continue;
+            }
+
+            let mut columns = record
+                .keys()
+                .filter(|column| **column != self.retraction_field)
+                .collect::<Vec<_>>();
+            for column in &key {
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+            let updates = columns
+                .iter()
+                .filter(|column| !key.iter().any(|k| k == **column))
+                .map(|column| format!("{0} = excluded.{0}", quote(column)))
+                .collect::<Vec<_>>();
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO {}",
+                quote(&self.table),
+                columns
+                    .iter()
+                    .map(|c| quote(c))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                vec!["?"; columns.len()].join(", "),
+                key.iter().map(|c| quote(c)).collect::<Vec<_>>().join(", "),
+                if updates.is_empty() {
+                    "NOTHING".to_string()
+                } else {
+                    format!("UPDATE SET {}", updates.join(", "))
+                },
+            );
+            let values = columns
+                .iter()
+                .map(|column| column_value(record, column, &key));
+            tx.execute(&sql, params_from_iter(values))?;
+        }
+
+        if let (Some(job), Some(block_number)) = (&self.job, block_number) {
+            SqliteCheckpointStore::commit_in(&tx, job, block_number)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Writes the rows of an Arrow batch in one transaction, creating the
+    /// table from its schema
+    pub fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.add_schema(&batch.schema())?;
+
+        let mut writer = ArrayWriter::new(Vec::new());
+        writer.write_batches(&[batch])?;
+        writer.finish()?;
+        let rows = serde_json::from_slice::<Vec<Value>>(&writer.into_inner())?;
+
+        self.write_block(rows, None)
+    }
+
+    /// Creates the table or adds the missing columns of an Arrow schema
+    pub fn add_schema(&mut self, schema: &Schema) -> Result<()> {
+        self.add_columns(
+            schema
+                .fields()
+                .iter()
+                .map(|field| (field.name(), arrow_sql_type(field.data_type()))),
+        )
+    }
+
+    fn add_columns<'a>(
+        &mut self,
+        columns: impl IntoIterator<Item = (&'a String, &'static str)>,
+    ) -> Result<()> {
+        let mut missing = columns
+            .into_iter()
+            .filter(|(name, _)| {
+                **name != self.retraction_field && !self.columns.contains(*name)
+            })
+            .map(|(name, ty)| (name.clone(), ty))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        if self.columns.is_empty() {
+            let names = missing
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            let key = self.key_of(&names)?;
+            for column in &key {
+                if !names.contains(column) {
+                    missing.push((column.clone(), "INTEGER"));
+                }
+            }
+            let sql = format!(
+                "CREATE TABLE IF NOT EXISTS {} ({}, PRIMARY KEY ({}))",
+                quote(&self.table),
+                missing
+                    .iter()
+                    .map(|(name, ty)| {
+                        let not_null = if key.contains(name) { " NOT NULL" } else { "" };
+                        format!("{} {ty}{not_null}", quote(name))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                key.iter().map(|c| quote(c)).collect::<Vec<_>>().join(", "),
+            );
+            self.conn.execute(&sql, [])?;
+            self.key = Some(key);
+        } else {
+            for (name, ty) in &missing {
+                let sql = format!(
+                    "ALTER TABLE {} ADD COLUMN {} {ty}",
+                    quote(&self.table),
+                    quote(name)
+                );
+                self.conn.execute(&sql, [])?;
+            }
+        }
+
+        self.columns
+            .extend(missing.into_iter().map(|(name, _)| name));
+        Ok(())
+    }
+
+    fn resolved_key(&self) -> Result<Vec<String>> {
+        match &self.key {
+            Some(key) => Ok(key.clone()),
+            None => self.key_of(&self.columns),
+        }
+    }
+
+    fn key_of(&self, columns: &[String]) -> Result<Vec<String>> {
+        if let Some(key) = &self.key {
+            return Ok(key.clone());
+        }
+
+        let key = NATURAL_KEY
+            .iter()
+            .filter(|column| columns.iter().any(|c| c == *column))
+            .map(|column| column.to_string())
+            .collect::<Vec<_>>();
+        if !key.iter().any(|column| column == "block_number") {
+            return Err(Error::SinkSchema(format!(
+                "no natural key in the columns {columns:?}, set one with `SqliteSink::key`"
+            )));
+        }
+
+        Ok(key)
+    }
+
+    /// Reads the columns and the key of the table, if it exists
+    fn load_table(&mut self) -> Result<()> {
+        let mut statement = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", quote(&self.table)))?;
+        let mut columns = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(statement);
+
+        self.columns = columns.iter().map(|(name, _)| name.clone()).collect();
+        columns.retain(|(_, pk)| *pk > 0);
+        columns.sort_by_key(|(_, pk)| *pk);
+        if self.key.is_none() && !columns.is_empty() {
+            self.key = Some(columns.into_iter().map(|(name, _)| name).collect());
+        }
+
+        Ok(())
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn sql_type(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "INTEGER",
+        Value::Number(n) if n.is_i64() => "INTEGER",
+        Value::Number(n) if n.is_f64() => "REAL",
+        _ => "TEXT",
+    }
+}
+
+fn arrow_sql_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => "INTEGER",
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => "REAL",
+        _ => "TEXT",
+    }
+}
+
+/// The value of `column` in a record
+///
+/// A NULL in the key would never conflict with another row, so missing parts
+/// of the key are 0, like the position of a record without a log index.
+fn column_value(record: &Map<String, Value>, column: &String, key: &[String]) -> SqlValue {
+    match sql_value(record.get(column)) {
+        SqlValue::Null if key.contains(column) => SqlValue::Integer(0),
+        value => value,
+    }
+}
+
+/// Numbers that do not fit an SQLite integer are stored as text, objects and
+/// arrays as JSON
+fn sql_value(value: Option<&Value>) -> SqlValue {
+    match value {
+        None | Some(Value::Null) => SqlValue::Null,
+        Some(Value::Bool(b)) => SqlValue::Integer(*b as i64),
+        Some(Value::Number(n)) => match (n.as_i64(), n.as_f64()) {
+            (Some(n), _) => SqlValue::Integer(n),
+            (None, Some(f)) if !n.is_u64() => SqlValue::Real(f),
+            _ => SqlValue::Text(n.to_string()),
+        },
+        Some(Value::String(s)) => SqlValue::Text(s.clone()),
+        Some(value) => SqlValue::Text(value.to_string()),
+    }
+}
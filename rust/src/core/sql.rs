@@ -0,0 +1,530 @@
+//! Pangea endpoints as [DataFusion](https://datafusion.apache.org) tables
+//!
+//! A [`PangeaTable`] streams the Arrow response of a request into the query
+//! engine. Filters of a query on the chain, the block number and the columns
+//! the request has an `__in` or `__not_in` set for are pushed down into the
+//! request, DataFusion still applies every filter to the received rows.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use datafusion::prelude::SessionContext;
+//! use pangea_client::{
+//!     query::Bound, requests::logs::GetLogsRequest, sql::PangeaTable, ClientBuilder, WsProvider,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = Arc::new(ClientBuilder::default().build::<WsProvider>().await?);
+//! let logs = GetLogsRequest {
+//!     from_block: Bound::Exact(0),
+//!     ..Default::default()
+//! };
+//!
+//! let ctx = SessionContext::new();
+//! ctx.register_table("logs", Arc::new(PangeaTable::try_new(client, logs).await?))?;
+//!
+//! let frame = ctx
+//!     .sql(
+//!         "SELECT address, COUNT(*) FROM logs \
+//!          WHERE block_number BETWEEN 17000000 AND 17000999 \
+//!          AND topic0 = '0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef' \
+//!          GROUP BY address",
+//!     )
+//!     .await?;
+//! frame.show().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use arrow::{
+    array::{new_null_array, RecordBatch},
+    compute::cast,
+    datatypes::SchemaRef,
+    ipc::reader::StreamReader,
+};
+use async_trait::async_trait;
+use datafusion::{
+    catalog::{Session, TableProvider},
+    common::ScalarValue,
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::TaskContext,
+    logical_expr::{
+        expr::InList, Between, BinaryExpr, Expr, Operator, TableProviderFilterPushDown,
+        TableType,
+    },
+    physical_expr::LexOrdering,
+    physical_plan::{
+        empty::EmptyExec,
+        stream::RecordBatchStreamAdapter,
+        streaming::{PartitionStream, StreamingTableExec},
+        ExecutionPlan, SendableRecordBatchStream,
+    },
+};
+use futures::StreamExt;
+use serde_json::Value;
+
+use super::{
+    client::Client,
+    error::{Error, ResponseError, Result},
+    provider::RequestProvider,
+    requests::{BlockRange, Encoding},
+    stream::arrow_batches,
+    types::{format::Format, query::Bound, ChainId},
+};
+
+/// The rows of a request, as a DataFusion table
+///
+/// The block range and the filters of the request bound every query of the
+/// table, filters of a query only narrow them.
+pub struct PangeaTable<T, R> {
+    client: Arc<Client<T>>,
+    request: R,
+    schema: SchemaRef,
+}
+
+impl<T, R> PangeaTable<T, R>
+where
+    T: Send + Sync + 'static,
+    Client<T>: RequestProvider<R>,
+    R: BlockRange + Encoding + Clone + Send + Sync + 'static,
+{
+    pub fn new(client: Arc<Client<T>>, request: R, schema: SchemaRef) -> Self {
+        Self {
+            client,
+            request,
+            schema,
+        }
+    }
+
+    /// Creates the table with the schema of the response to `request` for
+    /// the latest block
+    pub async fn try_new(client: Arc<Client<T>>, request: R) -> Result<Self> {
+        let mut probe = request.clone();
+        probe.set_from_block(Bound::FromLatest(1));
+        probe.set_to_block(Bound::Latest);
+
+        let response = client
+            .get_by_format(probe, Format::ArrowStream, false)
+            .await?;
+        let mut response = ResponseError::map_stream(response);
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.next().await {
+            bytes.extend(chunk?);
+            // the schema is the first message of the stream, it can be read
+            // once it has arrived whole
+            if let Ok(reader) = StreamReader::try_new(bytes.as_slice(), None) {
+                return Ok(Self::new(client, request, reader.schema()));
+            }
+        }
+
+        Err(Error::MissingSchema)
+    }
+
+    pub fn request(&self) -> &R {
+        &self.request
+    }
+}
+
+impl<T, R> fmt::Debug for PangeaTable<T, R>
+where
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PangeaTable")
+            .field("request", &self.request)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<T, R> TableProvider for PangeaTable<T, R>
+where
+    T: Send + Sync + 'static,
+    Client<T>: RequestProvider<R>,
+    R: BlockRange + Encoding + Clone + fmt::Debug + Send + Sync + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let Some(request) = narrow(&self.request, filters) else {
+            let schema = match projection {
+                Some(projection) => Arc::new(self.schema.project(projection)?),
+                None => self.schema.clone(),
+            };
+            return Ok(Arc::new(EmptyExec::new(schema)));
+        };
+
+        let is_live = matches!(request.block_range().1, Bound::Subscribe);
+        let partition = Partition {
+            client: self.client.clone(),
+            request,
+            schema: self.schema.clone(),
+        };
+        let exec = StreamingTableExec::try_new(
+            self.schema.clone(),
+            vec![Arc::new(partition)],
+            projection,
+            Vec::<LexOrdering>::new(),
+            is_live,
+            limit,
+        )?;
+
+        Ok(Arc::new(exec))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|filter| match push_down(&self.request, filter) {
+                Pushdown::Unsupported => TableProviderFilterPushDown::Unsupported,
+                _ => TableProviderFilterPushDown::Inexact,
+            })
+            .collect())
+    }
+}
+
+/// Sends the request when the query is executed
+struct Partition<T, R> {
+    client: Arc<Client<T>>,
+    request: R,
+    schema: SchemaRef,
+}
+
+impl<T, R> fmt::Debug for Partition<T, R>
+where
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Partition")
+            .field("request", &self.request)
+            .finish()
+    }
+}
+
+impl<T, R> PartitionStream for Partition<T, R>
+where
+    T: Send + Sync + 'static,
+    Client<T>: RequestProvider<R>,
+    R: Clone + fmt::Debug + Send + Sync + 'static,
+{
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let client = self.client.clone();
+        let request = self.request.clone();
+        let schema = self.schema.clone();
+
+        let batches = futures::stream::once(async move {
+            client
+                .get_by_format(request, Format::ArrowStream, false)
+                .await
+        })
+        .flat_map(|response| match response {
+            Ok(response) => arrow_batches(response),
+            Err(err) => futures::stream::once(async move { Err(err) }).boxed(),
+        })
+        .map(move |batch| {
+            batch
+                .and_then(|batch| conform(&batch, &schema))
+                .map_err(|err| DataFusionError::External(Box::new(err)))
+        });
+
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), batches))
+    }
+}
+
+/// Brings a batch into the schema of the table, columns are matched by name
+fn conform(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    if batch.schema() == *schema {
+        return Ok(batch.clone());
+    }
+
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch.column_by_name(field.name()) {
+            Some(column) if column.data_type() == field.data_type() => Ok(column.clone()),
+            Some(column) => Ok(cast(column, field.data_type())?),
+            None => Ok(new_null_array(field.data_type(), batch.num_rows())),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// The request sent for a query with `filters`, `None` if no row of `request`
+/// can match them
+///
+/// Filters that can not be expressed by the request are left to DataFusion.
+pub fn narrow<R>(request: &R, filters: &[Expr]) -> Option<R>
+where
+    R: BlockRange + Encoding + Clone,
+{
+    let mut request = request.clone();
+    for filter in filters {
+        match push_down(&request, filter) {
+            Pushdown::Narrowed(narrowed) => request = narrowed,
+            Pushdown::Unsupported => {}
+            Pushdown::Empty => return None,
+        }
+    }
+
+    Some(request)
+}
+
+/// The outcome of pushing a filter down into a request
+enum Pushdown<R> {
+    /// The request does not have a filter for it
+    Unsupported,
+    Narrowed(R),
+    /// No row can match both the request and the filter
+    Empty,
+}
+
+fn push_down<R>(request: &R, filter: &Expr) -> Pushdown<R>
+where
+    R: BlockRange + Encoding + Clone,
+{
+    match filter {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let (column, op, value) = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(column), Expr::Literal(value)) => (column, *op, value),
+                (Expr::Literal(value), Expr::Column(column)) => match op.swap() {
+                    Some(op) => (column, op, value),
+                    None => return Pushdown::Unsupported,
+                },
+                _ => return Pushdown::Unsupported,
+            };
+
+            match op {
+                Operator::Eq => filter_in(request, &column.name, &[value], false),
+                Operator::NotEq => filter_in(request, &column.name, &[value], true),
+                Operator::Gt | Operator::GtEq | Operator::Lt | Operator::LtEq => {
+                    filter_block(request, &column.name, op, value)
+                }
+                _ => Pushdown::Unsupported,
+            }
+        }
+        Expr::InList(InList {
+            expr,
+            list,
+            negated,
+        }) => {
+            let Expr::Column(column) = expr.as_ref() else {
+                return Pushdown::Unsupported;
+            };
+            let values = list
+                .iter()
+                .map(|value| match value {
+                    Expr::Literal(value) => Some(value),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+
+            match values {
+                Some(values) => filter_in(request, &column.name, &values, *negated),
+                None => Pushdown::Unsupported,
+            }
+        }
+        Expr::Between(Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        }) => {
+            let (Expr::Column(column), Expr::Literal(low), Expr::Literal(high)) =
+                (expr.as_ref(), low.as_ref(), high.as_ref())
+            else {
+                return Pushdown::Unsupported;
+            };
+
+            match filter_block(request, &column.name, Operator::GtEq, low) {
+                Pushdown::Narrowed(request) => {
+                    filter_block(&request, &column.name, Operator::LtEq, high)
+                }
+                pushdown => pushdown,
+            }
+        }
+        _ => Pushdown::Unsupported,
+    }
+}
+
+/// Narrows the block range of the request, its `to_block` is exclusive
+fn filter_block<R>(request: &R, column: &str, op: Operator, value: &ScalarValue) -> Pushdown<R>
+where
+    R: BlockRange + Clone,
+{
+    if column != "block_number" && column != "height" {
+        return Pushdown::Unsupported;
+    }
+    let Some(block) = literal(value).and_then(|value| value.parse::<i64>().ok()) else {
+        return Pushdown::Unsupported;
+    };
+
+    let mut request = request.clone();
+    let (from, to) = request.block_range();
+    match op {
+        Operator::Gt | Operator::GtEq => {
+            let block = if op == Operator::Gt { block + 1 } else { block };
+            // only an exact start can be narrowed without widening the range
+            if let Bound::Exact(from) = from {
+                request.set_from_block(Bound::Exact(from.max(block)));
+            }
+        }
+        _ => {
+            let block = if op == Operator::LtEq {
+                block + 1
+            } else {
+                block
+            };
+            match to {
+                Bound::Exact(to) => request.set_to_block(Bound::Exact(to.min(block))),
+                Bound::Latest | Bound::Subscribe => request.set_to_block(Bound::Exact(block)),
+                _ => {}
+            }
+        }
+    }
+
+    match request.block_range() {
+        (Bound::Exact(from), Bound::Exact(to)) if from >= to => Pushdown::Empty,
+        _ => Pushdown::Narrowed(request),
+    }
+}
+
+/// Narrows the chains or the `__in` / `__not_in` set of `column`
+fn filter_in<R>(
+    request: &R,
+    column: &str,
+    values: &[&ScalarValue],
+    negated: bool,
+) -> Pushdown<R>
+where
+    R: BlockRange + Encoding + Clone,
+{
+    let Some(values) = values
+        .iter()
+        .map(|value| literal(value))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Pushdown::Unsupported;
+    };
+
+    if column == "chain" {
+        let Ok(values) = values
+            .iter()
+            .map(|value| value.parse::<ChainId>())
+            .collect::<Result<HashSet<_>>>()
+        else {
+            return Pushdown::Unsupported;
+        };
+
+        let mut request = request.clone();
+        let chains: HashSet<ChainId> = match (request.chains().is_empty(), negated) {
+            (true, false) => values.into_iter().collect(),
+            (true, true) => return Pushdown::Unsupported,
+            (false, false) => request
+                .chains()
+                .iter()
+                .filter(|chain| values.contains(chain))
+                .copied()
+                .collect(),
+            (false, true) => request
+                .chains()
+                .iter()
+                .filter(|chain| !values.contains(chain))
+                .copied()
+                .collect(),
+        };
+        if chains.is_empty() {
+            return Pushdown::Empty;
+        }
+
+        request.set_chains(chains);
+        return Pushdown::Narrowed(request);
+    }
+
+    let key = if negated {
+        format!("{column}__not_in")
+    } else {
+        format!("{column}__in")
+    };
+    let Ok(Value::Object(mut fields)) = request.to_json() else {
+        return Pushdown::Unsupported;
+    };
+    let current = fields.get(&key).map(split_set);
+
+    // parsing normalizes the values, fields the request does not have are dropped
+    fields.insert(key.clone(), Value::String(values.join(",")));
+    let Some(values) = R::from_json(Value::Object(fields.clone()))
+        .and_then(|request| request.to_json())
+        .ok()
+        .and_then(|json| json.get(&key).map(split_set))
+    else {
+        return Pushdown::Unsupported;
+    };
+
+    let values = match current {
+        Some(current) if negated => current.union(&values).cloned().collect(),
+        Some(current) => current.intersection(&values).cloned().collect(),
+        None => values,
+    };
+    if values.is_empty() {
+        return Pushdown::Empty;
+    }
+
+    let values = values.into_iter().collect::<Vec<_>>();
+    fields.insert(key, Value::String(values.join(",")));
+    match R::from_json(Value::Object(fields)) {
+        Ok(request) => Pushdown::Narrowed(request),
+        Err(_) => Pushdown::Unsupported,
+    }
+}
+
+fn split_set(value: &Value) -> BTreeSet<String> {
+    value
+        .as_str()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn literal(value: &ScalarValue) -> Option<String> {
+    match value {
+        ScalarValue::Utf8(Some(value))
+        | ScalarValue::LargeUtf8(Some(value))
+        | ScalarValue::Utf8View(Some(value)) => Some(value.clone()),
+        value if value.data_type().is_integer() && !value.is_null() => Some(value.to_string()),
+        _ => None,
+    }
+}
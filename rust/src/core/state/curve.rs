@@ -0,0 +1,844 @@
+use std::collections::HashMap;
+
+use ethers_core::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{field, u256_field, u64_field, PoolState};
+use crate::core::{
+    error::{Error, Result},
+    stream::{chain_of, json_u256, Position},
+    types::{amount::Amount, ChainId},
+};
+
+/// Fees are in units of `10^-10`
+pub const FEE_DENOMINATOR: u64 = 10_000_000_000;
+
+/// The precision `A` is stored with by the current Curve contracts, the first
+/// pools like 3pool store it as is, with a precision of 1
+pub const DEFAULT_A_PRECISION: u64 = 100;
+
+const PRECISION: u64 = 1_000_000_000_000_000_000;
+
+const MAX_ITERATIONS: usize = 255;
+
+/// A Curve stableswap pool
+///
+/// A meta pool trades a coin against the LP token of its base pool, which is
+/// its last coin, and trades the coins of the base pool through it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurvePool {
+    pub chain: ChainId,
+    pub address: Address,
+    pub coins: Vec<Address>,
+    /// The decimals of the coins, unknown until a record carries them
+    pub decimals: Vec<Option<u8>>,
+    /// The balances of the coins in their smallest unit
+    pub balances: Vec<U256>,
+    pub base_pool: Option<Address>,
+    pub base_coins: Vec<Address>,
+    /// The swap fee, over [`FEE_DENOMINATOR`]
+    pub fee: u64,
+    /// The share of the swap fee taken out of the pool, over
+    /// [`FEE_DENOMINATOR`]
+    pub admin_fee: u64,
+    pub initial_a: u64,
+    pub future_a: u64,
+    pub initial_a_time: u64,
+    pub future_a_time: u64,
+    pub a_precision: u64,
+    /// The supply of the LP token, needed to price it for meta pools
+    pub total_supply: Option<U256>,
+    /// Timestamp of the last record applied, `A` is interpolated at it
+    pub timestamp: u64,
+    /// Position of the last trade applied
+    pub last: Option<Position>,
+}
+
+impl CurvePool {
+    pub fn new(chain: ChainId, address: Address, a_precision: u64) -> Self {
+        Self {
+            chain,
+            address,
+            coins: Vec::new(),
+            decimals: Vec::new(),
+            balances: Vec::new(),
+            base_pool: None,
+            base_coins: Vec::new(),
+            fee: 0,
+            admin_fee: 0,
+            initial_a: 0,
+            future_a: 0,
+            initial_a_time: 0,
+            future_a_time: 0,
+            a_precision,
+            total_supply: None,
+            timestamp: 0,
+            last: None,
+        }
+    }
+
+    pub fn is_meta(&self) -> bool {
+        self.base_pool.is_some()
+    }
+
+    /// Sets the balances, for the liquidity added and removed which is not in
+    /// the trades stream
+    pub fn set_balances(&mut self, balances: Vec<U256>) {
+        self.balances = balances;
+    }
+
+    /// The amplification coefficient at `timestamp`, with `a_precision`
+    ///
+    /// `A` moves linearly from `initial_a` to `future_a` during a ramp.
+    pub fn a_at(&self, timestamp: u64) -> U256 {
+        let (a0, a1) = (U256::from(self.initial_a), U256::from(self.future_a));
+        let (t0, t1) = (self.initial_a_time, self.future_a_time);
+        if timestamp >= t1 || t1 <= t0 {
+            return a1;
+        }
+
+        let elapsed = U256::from(timestamp.saturating_sub(t0));
+        let duration = U256::from(t1 - t0);
+        if a1 > a0 {
+            a0 + (a1 - a0) * elapsed / duration
+        } else {
+            a0 - (a0 - a1) * elapsed / duration
+        }
+    }
+
+    /// The amplification coefficient at the last record applied
+    pub fn a(&self) -> U256 {
+        self.a_at(self.timestamp)
+    }
+
+    /// [`Self::a`], failing if no record set the `A` ramp of the pool
+    fn amp(&self) -> Result<U256> {
+        let amp = self.a();
+        if amp.is_zero() {
+            return Err(Error::IncompletePool(format!(
+                "no amplification coefficient for {}",
+                self.name()
+            )));
+        }
+
+        Ok(amp)
+    }
+
+    /// The amount of coin `j` received for `dx` of coin `i`
+    ///
+    /// The base pool is needed to price the LP token of a meta pool.
+    pub fn get_dy(
+        &self,
+        i: usize,
+        j: usize,
+        dx: U256,
+        base: Option<&CurvePool>,
+    ) -> Result<U256> {
+        self.check_coins(i, j, self.coins.len())?;
+        let rates = self.rates(base)?;
+
+        Ok(self.exchange(i, j, dx, &rates)?.0)
+    }
+
+    /// The amount of underlying coin `j` received for `dx` of underlying coin
+    /// `i` of a meta pool, the coins of the meta pool but its LP token
+    /// followed by the coins of the base pool
+    pub fn get_dy_underlying(
+        &self,
+        i: usize,
+        j: usize,
+        dx: U256,
+        base: &CurvePool,
+    ) -> Result<U256> {
+        let max_coin = self.max_coin()?;
+        self.check_coins(i, j, max_coin + base.coins.len())?;
+
+        let rates = self.rates(Some(base))?;
+        let (base_i, base_j) = (i.checked_sub(max_coin), j.checked_sub(max_coin));
+        let dx = match (base_i, base_j) {
+            (Some(base_i), Some(base_j)) => return base.get_dy(base_i, base_j, dx, None),
+            (Some(base_i), None) => self.deposit(base, base_i, dx)?,
+            (None, _) => dx,
+        };
+
+        let (dy, _) = self.exchange(i.min(max_coin), j.min(max_coin), dx, &rates)?;
+        match base_j {
+            Some(base_j) => base.calc_withdraw_one_coin(dy, base_j),
+            None => Ok(dy),
+        }
+    }
+
+    /// The price of the LP token in units of `10^-18` of the D invariant
+    pub fn virtual_price(&self) -> Result<U256> {
+        let supply = self.supply()?;
+        let d = get_d(
+            &self.xp(&self.rates(None)?)?,
+            self.amp()?,
+            self.a_precision(),
+        )?;
+
+        Ok(d * U256::from(PRECISION) / supply)
+    }
+
+    /// The LP tokens minted for depositing `amounts`, or burned for
+    /// withdrawing them, before fees
+    pub fn calc_token_amount(&self, amounts: &[U256], deposit: bool) -> Result<U256> {
+        let rates = self.rates(None)?;
+        let (amp, a_precision) = (self.amp()?, self.a_precision());
+        let d0 = get_d(&self.xp(&rates)?, amp, a_precision)?;
+        if d0.is_zero() {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        let mut balances = self.balances.clone();
+        for (balance, amount) in balances.iter_mut().zip(amounts) {
+            *balance = if deposit {
+                add(*balance, *amount)?
+            } else {
+                balance
+                    .checked_sub(*amount)
+                    .ok_or_else(|| Error::InsufficientLiquidity(self.name()))?
+            };
+        }
+        let d1 = get_d(&xp(&balances, &rates)?, amp, a_precision)?;
+        let diff = if deposit { sub(d1, d0)? } else { sub(d0, d1)? };
+
+        Ok(mul(diff, self.supply()?)? / d0)
+    }
+
+    /// The amount of coin `i` received for burning `token_amount` LP tokens
+    pub fn calc_withdraw_one_coin(&self, token_amount: U256, i: usize) -> Result<U256> {
+        let rates = self.rates(None)?;
+        let (amp, a_precision) = (self.amp()?, self.a_precision());
+        let xp = self.xp(&rates)?;
+        let d0 = get_d(&xp, amp, a_precision)?;
+        let supply = self.supply()?;
+        if token_amount > supply || d0.is_zero() {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        let d1 = sub(d0, mul(token_amount, d0)? / supply)?;
+        let new_y = get_y_d(amp, i, &xp, d1, a_precision)?;
+        let n = U256::from(xp.len());
+        let fee = U256::from(self.fee) * n / (U256::from(4) * (n - 1));
+
+        let mut xp_reduced = xp.clone();
+        for (k, x) in xp_reduced.iter_mut().enumerate() {
+            let dx_expected = if k == i {
+                sub(mul(xp[k], d1)? / d0, new_y)?
+            } else {
+                sub(xp[k], mul(xp[k], d1)? / d0)?
+            };
+            *x = sub(*x, mul(fee, dx_expected)? / U256::from(FEE_DENOMINATOR))?;
+        }
+
+        let dy = sub(
+            xp_reduced[i],
+            get_y_d(amp, i, &xp_reduced, d1, a_precision)?,
+        )?;
+        let precision = rates[i] / U256::from(PRECISION);
+        // withdraw less to account for rounding errors
+        Ok(dy.saturating_sub(U256::one()) / precision)
+    }
+
+    /// Applies a trade of `dx` of coin `i` for `dy` of coin `j`, taking the
+    /// admin fee out of the balance of `j`
+    fn apply_trade(&mut self, i: usize, j: usize, dx: U256, dy: U256) -> Result<()> {
+        let fee = U256::from(self.fee);
+        let denominator = U256::from(FEE_DENOMINATOR);
+        let dy_fee = mul(dy, fee)? / (denominator - fee);
+        let dy_admin_fee = dy_fee * U256::from(self.admin_fee) / denominator;
+
+        self.balances[i] = add(self.balances[i], dx)?;
+        self.balances[j] =
+            self.balances[j]
+                .checked_sub(dy + dy_admin_fee)
+                .ok_or_else(|| {
+                    Error::InvalidPoolEvent(format!(
+                        "balance of coin {j} of {} underflows, set the balances first",
+                        self.name()
+                    ))
+                })?;
+
+        Ok(())
+    }
+
+    /// The amounts out and the admin fee of a swap between two coins of the
+    /// pool
+    fn exchange(&self, i: usize, j: usize, dx: U256, rates: &[U256]) -> Result<(U256, U256)> {
+        let precision = U256::from(PRECISION);
+        let denominator = U256::from(FEE_DENOMINATOR);
+        let xp = self.xp(rates)?;
+        let x = add(xp[i], mul(dx, rates[i])? / precision)?;
+        let y = get_y(i, j, x, &xp, self.amp()?, self.a_precision())?;
+
+        let dy = xp[j]
+            .checked_sub(y + 1)
+            .ok_or_else(|| Error::InsufficientLiquidity(self.name()))?;
+        let dy_fee = dy * U256::from(self.fee) / denominator;
+        let dy_admin_fee = dy_fee * U256::from(self.admin_fee) / denominator;
+
+        Ok((
+            (dy - dy_fee) * precision / rates[j],
+            dy_admin_fee * precision / rates[j],
+        ))
+    }
+
+    /// The LP tokens of the base pool minted for `dx` of its coin `i`, with
+    /// the fee of an imbalanced deposit
+    fn deposit(&self, base: &CurvePool, i: usize, dx: U256) -> Result<U256> {
+        let mut amounts = vec![U256::zero(); base.coins.len()];
+        amounts[i] = dx;
+        let minted = base.calc_token_amount(&amounts, true)?;
+
+        Ok(minted - mul(minted, U256::from(base.fee))? / U256::from(2 * FEE_DENOMINATOR))
+    }
+
+    /// The rates scaling the balances to 18 decimals, the virtual price of
+    /// the base pool for the LP token of a meta pool
+    fn rates(&self, base: Option<&CurvePool>) -> Result<Vec<U256>> {
+        if self.balances.len() != self.coins.len() || self.coins.len() < 2 {
+            return Err(Error::IncompletePool(format!(
+                "no balances for the coins of {}",
+                self.name()
+            )));
+        }
+
+        let last = self.coins.len() - 1;
+        (0..self.coins.len())
+            .map(|k| {
+                if self.is_meta() && k == last {
+                    return base
+                        .filter(|base| Some(base.address) == self.base_pool)
+                        .ok_or_else(|| {
+                            Error::IncompletePool(format!("no base pool for {}", self.name()))
+                        })?
+                        .virtual_price();
+                }
+
+                let decimals = self
+                    .decimals
+                    .get(k)
+                    .copied()
+                    .flatten()
+                    .filter(|decimals| *decimals <= 36)
+                    .ok_or_else(|| {
+                        Error::IncompletePool(format!(
+                            "no decimals for coin {k} of {}",
+                            self.name()
+                        ))
+                    })?;
+                Ok(U256::exp10(36 - decimals as usize))
+            })
+            .collect()
+    }
+
+    fn xp(&self, rates: &[U256]) -> Result<Vec<U256>> {
+        xp(&self.balances, rates)
+    }
+
+    fn supply(&self) -> Result<U256> {
+        self.total_supply
+            .filter(|supply| !supply.is_zero())
+            .ok_or_else(|| {
+                Error::IncompletePool(format!("no LP token supply for {}", self.name()))
+            })
+    }
+
+    fn a_precision(&self) -> U256 {
+        U256::from(self.a_precision.max(1))
+    }
+
+    fn max_coin(&self) -> Result<usize> {
+        if self.is_meta() {
+            Ok(self.coins.len().saturating_sub(1))
+        } else {
+            Err(Error::IncompletePool(format!(
+                "{} is not a meta pool",
+                self.name()
+            )))
+        }
+    }
+
+    fn check_coins(&self, i: usize, j: usize, n: usize) -> Result<()> {
+        for k in [i, j] {
+            if k >= n {
+                return Err(Error::TokenNotInPool(format!("coin {k}"), self.name()));
+            }
+        }
+        if i == j {
+            return Err(Error::MathError("a coin swapped for itself"));
+        }
+
+        Ok(())
+    }
+
+    /// The index of a coin, or of an underlying coin of a meta pool
+    fn index(&self, token: Address, underlying: bool) -> Option<usize> {
+        if !underlying {
+            return self.coins.iter().position(|coin| *coin == token);
+        }
+
+        let max_coin = self.max_coin().ok()?;
+        self.coins[..max_coin]
+            .iter()
+            .chain(&self.base_coins)
+            .position(|coin| *coin == token)
+    }
+
+    fn name(&self) -> String {
+        format!("{:?} on {}", self.address, self.chain)
+    }
+}
+
+fn xp(balances: &[U256], rates: &[U256]) -> Result<Vec<U256>> {
+    balances
+        .iter()
+        .zip(rates)
+        .map(|(balance, rate)| Ok(mul(*balance, *rate)? / U256::from(PRECISION)))
+        .collect()
+}
+
+fn add(a: U256, b: U256) -> Result<U256> {
+    a.checked_add(b).ok_or(Error::MathError("overflow"))
+}
+
+fn sub(a: U256, b: U256) -> Result<U256> {
+    a.checked_sub(b).ok_or(Error::MathError("underflow"))
+}
+
+fn mul(a: U256, b: U256) -> Result<U256> {
+    a.checked_mul(b).ok_or(Error::MathError("overflow"))
+}
+
+/// The D invariant of balances scaled to 18 decimals
+fn get_d(xp: &[U256], amp: U256, a_precision: U256) -> Result<U256> {
+    let s = xp.iter().try_fold(U256::zero(), |s, x| add(s, *x))?;
+    if s.is_zero() {
+        return Ok(U256::zero());
+    }
+    if xp.iter().any(U256::is_zero) {
+        return Err(Error::MathError("D of a pool with an empty balance"));
+    }
+
+    let n = U256::from(xp.len());
+    let ann = mul(amp, n)?;
+    let ann_less_precision = ann
+        .checked_sub(a_precision)
+        .ok_or(Error::MathError("A below 1"))?;
+    let mut d = s;
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_p = d;
+        for x in xp {
+            d_p = mul(d_p, d)? / mul(*x, n)?;
+        }
+        let d_prev = d;
+        d = mul(add(mul(ann, s)? / a_precision, mul(d_p, n)?)?, d)?
+            / add(mul(ann_less_precision, d)? / a_precision, mul(n + 1, d_p)?)?;
+        if converged(d, d_prev) {
+            return Ok(d);
+        }
+    }
+
+    Err(Error::MathError("D does not converge"))
+}
+
+/// The balance of coin `j` keeping D after the balance of coin `i` is `x`
+fn get_y(
+    i: usize,
+    j: usize,
+    x: U256,
+    xp: &[U256],
+    amp: U256,
+    a_precision: U256,
+) -> Result<U256> {
+    let d = get_d(xp, amp, a_precision)?;
+    let n = U256::from(xp.len());
+    let ann = mul(amp, n)?;
+
+    let mut c = d;
+    let mut s = U256::zero();
+    for (k, x_k) in xp.iter().enumerate() {
+        let x_k = match k {
+            k if k == i => x,
+            k if k == j => continue,
+            _ => *x_k,
+        };
+        s = add(s, x_k)?;
+        c = mul(c, d)? / mul(x_k, n)?;
+    }
+
+    solve_y(
+        mul(mul(c, d)?, a_precision)? / mul(ann, n)?,
+        add(s, mul(d, a_precision)? / ann)?,
+        d,
+    )
+}
+
+/// The balance of coin `i` for D to be `d`
+fn get_y_d(amp: U256, i: usize, xp: &[U256], d: U256, a_precision: U256) -> Result<U256> {
+    let n = U256::from(xp.len());
+    let ann = mul(amp, n)?;
+
+    let mut c = d;
+    let mut s = U256::zero();
+    for (_, x_k) in xp.iter().enumerate().filter(|(k, _)| *k != i) {
+        s = add(s, *x_k)?;
+        c = mul(c, d)? / mul(*x_k, n)?;
+    }
+
+    solve_y(
+        mul(mul(c, d)?, a_precision)? / mul(ann, n)?,
+        add(s, mul(d, a_precision)? / ann)?,
+        d,
+    )
+}
+
+/// Solves `y^2 + (b - D) y = c` by Newton's method
+fn solve_y(c: U256, b: U256, d: U256) -> Result<U256> {
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        y = add(mul(y, y)?, c)? / sub(add(mul(y, U256::from(2))?, b)?, d)?;
+        if converged(y, y_prev) {
+            return Ok(y);
+        }
+    }
+
+    Err(Error::MathError("y does not converge"))
+}
+
+fn converged(a: U256, b: U256) -> bool {
+    if a > b {
+        a - b <= U256::one()
+    } else {
+        b - a <= U256::one()
+    }
+}
+
+/// The Curve pools seen in streams of pools and trades records
+///
+/// Pool records set the coins, fees and `A` ramp of a pool, and its balances
+/// and LP token supply if they carry them. Trades records move the balances
+/// of the coins traded. Liquidity added and removed is not in the trades
+/// stream, refresh the balances from records carrying them or with
+/// [`CurvePool::set_balances`].
+///
+/// Trades of underlying coins move the balances of a meta pool by the amount
+/// of LP tokens of its base pool the trade swaps, as computed from the state
+/// of the base pool, and the balance of the base pool by the coin traded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Snapshot", into = "Snapshot")]
+pub struct CurveState {
+    pools: HashMap<(ChainId, Address), CurvePool>,
+    a_precision: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    a_precision: u64,
+    pools: Vec<CurvePool>,
+}
+
+impl From<Snapshot> for CurveState {
+    fn from(snapshot: Snapshot) -> Self {
+        Self {
+            pools: snapshot
+                .pools
+                .into_iter()
+                .map(|pool| ((pool.chain, pool.address), pool))
+                .collect(),
+            a_precision: snapshot.a_precision,
+        }
+    }
+}
+
+impl From<CurveState> for Snapshot {
+    fn from(state: CurveState) -> Self {
+        Self {
+            a_precision: state.a_precision,
+            pools: state.pools.into_values().collect(),
+        }
+    }
+}
+
+impl Default for CurveState {
+    fn default() -> Self {
+        Self {
+            pools: HashMap::new(),
+            a_precision: DEFAULT_A_PRECISION,
+        }
+    }
+}
+
+impl CurveState {
+    /// Sets the precision of `A` of the pools created from now on, a record
+    /// can set it with an `a_precision` field
+    pub fn with_a_precision(mut self, a_precision: u64) -> Self {
+        self.a_precision = a_precision;
+        self
+    }
+
+    pub fn pool(&self, chain: ChainId, address: Address) -> Option<&CurvePool> {
+        self.pools.get(&(chain, address))
+    }
+
+    pub fn pool_mut(&mut self, chain: ChainId, address: Address) -> Option<&mut CurvePool> {
+        self.pools.get_mut(&(chain, address))
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &CurvePool> {
+        self.pools.values()
+    }
+
+    /// The position of the last trade applied on `chain`, resume the trades
+    /// stream after it
+    pub fn last_position(&self, chain: ChainId) -> Option<Position> {
+        self.pools
+            .values()
+            .filter(|pool| pool.chain == chain)
+            .filter_map(|pool| pool.last)
+            .max()
+    }
+
+    /// The amount of `token_out` received for `dx` of `token_in`, coins of
+    /// the pool or underlying coins of a meta pool
+    pub fn get_dy(
+        &self,
+        chain: ChainId,
+        pool: Address,
+        token_in: Address,
+        token_out: Address,
+        dx: U256,
+    ) -> Result<U256> {
+        let pool = self
+            .pool(chain, pool)
+            .ok_or_else(|| Error::UnknownPool(format!("{pool:?} on {chain}")))?;
+        let base = self.base_of(pool);
+
+        if let (Some(i), Some(j)) = (pool.index(token_in, false), pool.index(token_out, false))
+        {
+            return pool.get_dy(i, j, dx, base);
+        }
+        let (i, j) = underlying_indexes(pool, token_in, token_out)?;
+        let base = base.ok_or_else(|| {
+            Error::IncompletePool(format!("no base pool for {}", pool.name()))
+        })?;
+
+        pool.get_dy_underlying(i, j, dx, base)
+    }
+
+    fn base_of(&self, pool: &CurvePool) -> Option<&CurvePool> {
+        self.pool(pool.chain, pool.base_pool?)
+    }
+
+    /// Moves the balances of a meta pool and its base pool for a trade of
+    /// `dx` of underlying coin `i` for `dy` of underlying coin `j`
+    fn apply_underlying_trade(
+        &mut self,
+        key: (ChainId, Address),
+        (i, j): (usize, usize),
+        dx: U256,
+        dy: U256,
+    ) -> Result<()> {
+        let pool = &self.pools[&key];
+        let max_coin = pool.max_coin()?;
+        let (base_i, base_j) = (i.checked_sub(max_coin), j.checked_sub(max_coin));
+        if base_i.is_some() && base_j.is_some() {
+            // swapped by the base pool, which records the trade itself
+            return Ok(());
+        }
+
+        let base_key = (key.0, pool.base_pool.unwrap_or_default());
+        let base = self.pools.get(&base_key).ok_or_else(|| {
+            Error::IncompletePool(format!("no base pool for {}", pool.name()))
+        })?;
+        let rates = pool.rates(Some(base))?;
+        // the LP tokens of the base pool swapped by the meta pool
+        let lp = match base_i {
+            Some(base_i) => pool.deposit(base, base_i, dx)?,
+            None => pool.exchange(i, max_coin, dx, &rates)?.0,
+        };
+
+        let pool = self.pools.get_mut(&key).expect("pool exists");
+        match base_i {
+            Some(_) => pool.apply_trade(max_coin, j, lp, dy)?,
+            None => pool.apply_trade(i, max_coin, dx, lp)?,
+        }
+
+        let base = self.pools.get_mut(&base_key).expect("base pool exists");
+        if let Some(balance) = base_i.and_then(|base_i| base.balances.get_mut(base_i)) {
+            *balance = add(*balance, dx)?;
+            base.total_supply = base
+                .total_supply
+                .map(|supply| add(supply, lp))
+                .transpose()?;
+        }
+        if let Some(balance) = base_j.and_then(|base_j| base.balances.get_mut(base_j)) {
+            *balance = balance.saturating_sub(dy);
+            base.total_supply = base.total_supply.map(|supply| supply.saturating_sub(lp));
+        }
+
+        Ok(())
+    }
+}
+
+/// The underlying indexes of two coins of a meta pool
+fn underlying_indexes(
+    pool: &CurvePool,
+    token_in: Address,
+    token_out: Address,
+) -> Result<(usize, usize)> {
+    let index = |token: Address| {
+        pool.index(token, true)
+            .ok_or_else(|| Error::TokenNotInPool(format!("{token:?}"), pool.name()))
+    };
+
+    Ok((index(token_in)?, index(token_out)?))
+}
+
+impl PoolState for CurveState {
+    fn apply(&mut self, record: &Value) -> Result<bool> {
+        let Some(address) = field::<Address>(record, &["pool_address", "address"]) else {
+            return Err(Error::InvalidPoolEvent(format!(
+                "no pool address: {record}"
+            )));
+        };
+        let chain = chain_of(record).unwrap_or_default();
+        let a_precision = self.a_precision;
+        let pool = self
+            .pools
+            .entry((chain, address))
+            .or_insert_with(|| CurvePool::new(chain, address, a_precision));
+
+        if let Some(timestamp) = u64_field(record, &["timestamp", "block_timestamp"]) {
+            pool.timestamp = pool.timestamp.max(timestamp);
+        }
+
+        let sold = field::<Address>(record, &["sold_address"]);
+        let bought = field::<Address>(record, &["bought_address"]);
+        let (Some(sold), Some(bought)) = (sold, bought) else {
+            apply_pool_record(pool, record);
+            return Ok(true);
+        };
+
+        let position = Position::of(record);
+        if position.is_some() && position <= pool.last {
+            return Ok(false);
+        }
+
+        let (i, j, underlying) = match pool.index(sold, false).zip(pool.index(bought, false)) {
+            Some((i, j)) => (i, j, false),
+            None => {
+                let (i, j) = underlying_indexes(pool, sold, bought)?;
+                (i, j, true)
+            }
+        };
+        let sold_decimals = field::<u8>(record, &["sold_decimals"]);
+        let bought_decimals = field::<u8>(record, &["bought_decimals"]);
+        if !underlying {
+            if let Some(decimals) = sold_decimals {
+                set_decimals(pool, i, decimals);
+            }
+            if let Some(decimals) = bought_decimals {
+                set_decimals(pool, j, decimals);
+            }
+        }
+
+        // the decimals of the coins of the base pool are not kept
+        let coin_decimals = |k: usize| {
+            let is_base_coin = underlying && k + 1 >= pool.coins.len();
+            pool.decimals
+                .get(k)
+                .copied()
+                .flatten()
+                .filter(|_| !is_base_coin)
+        };
+        let (dx, dy) = match (
+            raw_amount(record, "tokens_sold", sold_decimals.or(coin_decimals(i))),
+            raw_amount(
+                record,
+                "tokens_bought",
+                bought_decimals.or(coin_decimals(j)),
+            ),
+        ) {
+            (Some(dx), Some(dy)) => (dx, dy),
+            _ => {
+                return Err(Error::InvalidPoolEvent(format!(
+                    "no raw amounts traded: {record}"
+                )));
+            }
+        };
+
+        if pool.balances.len() != pool.coins.len() {
+            return Err(Error::IncompletePool(format!(
+                "no balances for the coins of {}",
+                pool.name()
+            )));
+        }
+        if underlying {
+            self.apply_underlying_trade((chain, address), (i, j), dx, dy)?;
+        } else {
+            pool.apply_trade(i, j, dx, dy)?;
+        }
+
+        let pool = self.pools.get_mut(&(chain, address)).expect("pool exists");
+        if position.is_some() {
+            pool.last = position;
+        }
+        Ok(true)
+    }
+}
+
+fn apply_pool_record(pool: &mut CurvePool, record: &Value) {
+    if let Some(coins) = field::<Vec<Address>>(record, &["coins"]) {
+        pool.decimals.resize(coins.len(), None);
+        pool.coins = coins;
+    }
+    if let Some(base_coins) = field(record, &["base_coins"]) {
+        pool.base_coins = base_coins;
+    }
+    if let Some(base_pool) = field::<Address>(record, &["base_pool"]) {
+        pool.base_pool = Some(base_pool).filter(|base_pool| !base_pool.is_zero());
+    }
+    if let Some(decimals) = field::<Vec<u8>>(record, &["decimals", "coins_decimals"]) {
+        pool.decimals = decimals.into_iter().map(Some).collect();
+    }
+    if let Some(balances) = record.get("balances").and_then(Value::as_array) {
+        pool.balances = balances.iter().filter_map(json_u256).collect();
+    }
+
+    let set = |value: &mut u64, names: &[&str]| {
+        if let Some(v) = u64_field(record, names) {
+            *value = v;
+        }
+    };
+    set(&mut pool.fee, &["fee"]);
+    set(&mut pool.admin_fee, &["admin_fee"]);
+    set(&mut pool.initial_a, &["initial_a"]);
+    set(&mut pool.future_a, &["future_a"]);
+    set(&mut pool.initial_a_time, &["initial_a_time"]);
+    set(&mut pool.future_a_time, &["future_a_time"]);
+    set(&mut pool.a_precision, &["a_precision"]);
+    if let Some(supply) = u256_field(record, &["total_supply", "lp_supply"]) {
+        pool.total_supply = Some(supply);
+    }
+}
+
+fn set_decimals(pool: &mut CurvePool, k: usize, decimals: u8) {
+    if pool.decimals.len() <= k {
+        pool.decimals.resize(k + 1, None);
+    }
+    pool.decimals[k] = Some(decimals);
+}
+
+/// Reads an amount traded, in units of the coin if its decimals are known and
+/// in its smallest unit otherwise
+fn raw_amount(record: &Value, name: &str, decimals: Option<u8>) -> Option<U256> {
+    let amount = field::<Amount>(record, &[name])?;
+    match decimals {
+        Some(decimals) => amount.rescale(decimals).map(|amount| amount.raw()),
+        None => (amount.decimals() == 0).then(|| amount.raw()),
+    }
+}
@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+
+use ethers_core::types::U256;
+use fuel_core_types::fuel_types::{AssetId, Bytes32};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::PoolState;
+use crate::core::{
+    error::{Error, Result},
+    stream::{chain_of, Position},
+    types::{
+        amount::Amount,
+        mira::{EventType, MiraLiquidity, MiraPool, MiraSwap, Side},
+        ChainId,
+    },
+};
+
+/// The LP fee of Mira v1 volatile pools, in basis points
+pub const DEFAULT_VOLATILE_FEE_BPS: u32 = 30;
+/// The LP fee of Mira v1 stable pools, in basis points
+pub const DEFAULT_STABLE_FEE_BPS: u32 = 5;
+
+const BPS: u32 = 10_000;
+/// Newton iterations of the stable curve before giving up
+const MAX_ITERATIONS: usize = 255;
+
+/// The reserves of a Mira v1 pool
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MiraReserves {
+    pub chain: ChainId,
+    pub pool_address: Bytes32,
+    pub asset0: AssetId,
+    pub asset1: AssetId,
+    pub is_stable: bool,
+    /// Needed to quote stable pools, unknown until the pool record is applied
+    pub decimals0: Option<u8>,
+    pub decimals1: Option<u8>,
+    pub reserve0: u64,
+    pub reserve1: u64,
+    pub fee_bps: u32,
+    /// Position of the last mint or burn applied
+    pub last_liquidity: Option<Position>,
+    /// Position of the last swap applied, tracked apart from the liquidity
+    /// events as the two streams are consumed independently
+    pub last_swap: Option<Position>,
+}
+
+impl MiraReserves {
+    pub fn new(
+        chain: ChainId,
+        pool_address: Bytes32,
+        asset0: AssetId,
+        asset1: AssetId,
+    ) -> Self {
+        Self {
+            chain,
+            pool_address,
+            asset0,
+            asset1,
+            is_stable: false,
+            decimals0: None,
+            decimals1: None,
+            reserve0: 0,
+            reserve1: 0,
+            fee_bps: DEFAULT_VOLATILE_FEE_BPS,
+            last_liquidity: None,
+            last_swap: None,
+        }
+    }
+
+    /// The amount of the other asset received for `amount_in` of `asset_in`
+    pub fn get_amount_out(&self, asset_in: AssetId, amount_in: u64) -> Result<u64> {
+        let (reserve_in, reserve_out, decimals_in, decimals_out) = self.sides(asset_in)?;
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        let amount_in = U256::from(amount_in);
+        let fee = (amount_in * self.fee() + BPS - 1) / BPS;
+        let amount_in = amount_in - fee;
+        let (reserve_in, reserve_out) = (U256::from(reserve_in), U256::from(reserve_out));
+
+        let amount_out = if self.is_stable {
+            let (pow_in, pow_out) = self.pows(decimals_in, decimals_out)?;
+            let x = reserve_in * one() / pow_in;
+            let y = reserve_out * one() / pow_out;
+            let xy = k(x, y);
+            let y_new = get_y(x + amount_in * one() / pow_in, xy, y)?;
+            y.saturating_sub(y_new) * pow_out / one()
+        } else {
+            amount_in * reserve_out / (reserve_in + amount_in)
+        };
+
+        Ok(amount_out.as_u64())
+    }
+
+    /// The amount of the other asset needed to receive `amount_out` of
+    /// `asset_out`
+    pub fn get_amount_in(&self, asset_out: AssetId, amount_out: u64) -> Result<u64> {
+        let (reserve_out, reserve_in, decimals_out, decimals_in) = self.sides(asset_out)?;
+        // a fee of 100% keeps every input, no amount buys anything
+        if amount_out >= reserve_out || reserve_in == 0 || self.fee() == BPS {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        let amount_out = U256::from(amount_out);
+        let (reserve_in, reserve_out) = (U256::from(reserve_in), U256::from(reserve_out));
+
+        let amount_in = if self.is_stable {
+            let (pow_in, pow_out) = self.pows(decimals_in, decimals_out)?;
+            let x = reserve_in * one() / pow_in;
+            let y = reserve_out * one() / pow_out;
+            let xy = k(x, y);
+            // the curve is symmetric, solve it for the reserve in
+            let x_new = get_y(y - amount_out * one() / pow_out, xy, x)?;
+            x_new.saturating_sub(x) * pow_in / one() + U256::one()
+        } else {
+            reserve_in * amount_out / (reserve_out - amount_out) + U256::one()
+        };
+        let fee = U256::from(BPS - self.fee());
+        let amount_in = (amount_in * BPS + fee - 1) / fee;
+
+        u64::try_from(amount_in).map_err(|_| Error::InsufficientLiquidity(self.name()))
+    }
+
+    /// Adds a mint to the reserves or removes a burn from them
+    pub fn apply_liquidity(&mut self, event: &MiraLiquidity) -> Result<()> {
+        let (amount0, amount1) = (self.units(event.amount0)?, self.units(event.amount1)?);
+        let (reserve0, reserve1) = match event.event_type {
+            EventType::Mint => (
+                self.add(self.reserve0, amount0)?,
+                self.add(self.reserve1, amount1)?,
+            ),
+            EventType::Burn => (
+                self.sub(self.reserve0, amount0)?,
+                self.sub(self.reserve1, amount1)?,
+            ),
+            EventType::Swap => {
+                return Err(Error::InvalidPoolEvent(format!(
+                    "swap in the liquidity stream of {}",
+                    self.name()
+                )));
+            }
+        };
+        self.reserve0 = reserve0;
+        self.reserve1 = reserve1;
+
+        Ok(())
+    }
+
+    /// Moves the amounts of a swap in and out of the reserves, the LP fee stays
+    /// in the pool
+    pub fn apply_swap(&mut self, swap: &MiraSwap) -> Result<()> {
+        let (amount0, amount1) = (self.units(swap.amount0)?, self.units(swap.amount1)?);
+        let (reserve0, reserve1) = match swap.side {
+            Side::Sell => (
+                self.add(self.reserve0, amount0)?,
+                self.sub(self.reserve1, amount1)?,
+            ),
+            Side::Buy => (
+                self.sub(self.reserve0, amount0)?,
+                self.add(self.reserve1, amount1)?,
+            ),
+        };
+        self.reserve0 = reserve0;
+        self.reserve1 = reserve1;
+
+        Ok(())
+    }
+
+    /// The reserve and decimals of `asset` and those of the other asset
+    fn sides(&self, asset: AssetId) -> Result<(u64, u64, Option<u8>, Option<u8>)> {
+        if asset == self.asset0 {
+            Ok((self.reserve0, self.reserve1, self.decimals0, self.decimals1))
+        } else if asset == self.asset1 {
+            Ok((self.reserve1, self.reserve0, self.decimals1, self.decimals0))
+        } else {
+            Err(Error::TokenNotInPool(format!("{asset:?}"), self.name()))
+        }
+    }
+
+    /// `10^decimals` of the asset in and out, to scale stable reserves to 18
+    /// decimals
+    fn pows(&self, decimals_in: Option<u8>, decimals_out: Option<u8>) -> Result<(U256, U256)> {
+        match (decimals_in, decimals_out) {
+            (Some(decimals_in), Some(decimals_out)) => Ok((
+                U256::exp10(decimals_in as usize),
+                U256::exp10(decimals_out as usize),
+            )),
+            _ => Err(Error::IncompletePool(format!(
+                "no decimals for stable pool {}",
+                self.name()
+            ))),
+        }
+    }
+
+    /// An amount of a record in base units, reserves are `u64` on Fuel
+    fn units(&self, amount: Amount) -> Result<u64> {
+        amount
+            .rescale(0)
+            .and_then(|amount| u64::try_from(amount.raw()).ok())
+            .ok_or_else(|| {
+                Error::InvalidPoolEvent(format!("amount {amount} in {}", self.name()))
+            })
+    }
+
+    fn add(&self, reserve: u64, amount: u64) -> Result<u64> {
+        reserve
+            .checked_add(amount)
+            .ok_or(Error::MathError("reserve overflow"))
+    }
+
+    fn sub(&self, reserve: u64, amount: u64) -> Result<u64> {
+        reserve.checked_sub(amount).ok_or_else(|| {
+            Error::InvalidPoolEvent(format!(
+                "{amount} out of a reserve of {reserve} in {}",
+                self.name()
+            ))
+        })
+    }
+
+    fn fee(&self) -> u32 {
+        self.fee_bps.min(BPS)
+    }
+
+    fn name(&self) -> String {
+        format!("{:?} on {}", self.pool_address, self.chain)
+    }
+}
+
+fn one() -> U256 {
+    U256::exp10(18)
+}
+
+/// The stable invariant `x^3 y + x y^3` of reserves scaled to 18 decimals
+fn k(x: U256, y: U256) -> U256 {
+    let a = x * y / one();
+    let b = x * x / one() + y * y / one();
+    a * b / one()
+}
+
+fn f(x0: U256, y: U256) -> U256 {
+    x0 * (y * y / one() * y / one()) / one() + (x0 * x0 / one() * x0 / one()) * y / one()
+}
+
+/// The derivative of `f` in `y`
+fn d(x0: U256, y: U256) -> U256 {
+    U256::from(3) * x0 * (y * y / one()) / one() + (x0 * x0 / one() * x0 / one())
+}
+
+/// The reserve `y` keeping the invariant at `xy` for a reserve `x0`, found by
+/// Newton's method from the current `y`
+fn get_y(x0: U256, xy: U256, mut y: U256) -> Result<U256> {
+    for _ in 0..MAX_ITERATIONS {
+        let k = f(x0, y);
+        let d = d(x0, y);
+        if d.is_zero() {
+            return Err(Error::MathError("stable curve derivative is zero"));
+        }
+        let dy = if k < xy {
+            let dy = (xy - k) * one() / d;
+            y += dy;
+            dy
+        } else {
+            let dy = (k - xy) * one() / d;
+            y = y.saturating_sub(dy);
+            dy
+        };
+        if dy <= U256::one() {
+            return Ok(y);
+        }
+    }
+
+    Err(Error::MathError("stable curve did not converge"))
+}
+
+/// The reserves of every Mira v1 pool seen in a stream of pools, liquidity
+/// and swaps records
+///
+/// Pool records set the assets, curve and decimals of a pool. Mints and burns
+/// add and remove liquidity and swaps move their amounts in and out of the
+/// reserves, so the reserves are exact when the streams are replayed from the
+/// creation of the pools. Protocol fees, off on Mira v1, are not tracked.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Snapshot", into = "Snapshot")]
+pub struct MiraPoolState {
+    pools: HashMap<(ChainId, Bytes32), MiraReserves>,
+    volatile_fee_bps: u32,
+    stable_fee_bps: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    volatile_fee_bps: u32,
+    stable_fee_bps: u32,
+    pools: Vec<MiraReserves>,
+}
+
+impl From<Snapshot> for MiraPoolState {
+    fn from(snapshot: Snapshot) -> Self {
+        Self {
+            pools: snapshot
+                .pools
+                .into_iter()
+                .map(|pool| ((pool.chain, pool.pool_address), pool))
+                .collect(),
+            volatile_fee_bps: snapshot.volatile_fee_bps,
+            stable_fee_bps: snapshot.stable_fee_bps,
+        }
+    }
+}
+
+impl From<MiraPoolState> for Snapshot {
+    fn from(state: MiraPoolState) -> Self {
+        Self {
+            volatile_fee_bps: state.volatile_fee_bps,
+            stable_fee_bps: state.stable_fee_bps,
+            pools: state.pools.into_values().collect(),
+        }
+    }
+}
+
+impl Default for MiraPoolState {
+    fn default() -> Self {
+        Self {
+            pools: HashMap::new(),
+            volatile_fee_bps: DEFAULT_VOLATILE_FEE_BPS,
+            stable_fee_bps: DEFAULT_STABLE_FEE_BPS,
+        }
+    }
+}
+
+impl MiraPoolState {
+    /// Sets the LP fees of the pools created from now on
+    pub fn with_fees(mut self, volatile_fee_bps: u32, stable_fee_bps: u32) -> Self {
+        self.volatile_fee_bps = volatile_fee_bps;
+        self.stable_fee_bps = stable_fee_bps;
+        self
+    }
+
+    pub fn pool(&self, chain: ChainId, pool: Bytes32) -> Option<&MiraReserves> {
+        self.pools.get(&(chain, pool))
+    }
+
+    pub fn pool_mut(&mut self, chain: ChainId, pool: Bytes32) -> Option<&mut MiraReserves> {
+        self.pools.get_mut(&(chain, pool))
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &MiraReserves> {
+        self.pools.values()
+    }
+
+    /// The position of the last event of either stream applied on `chain`
+    pub fn last_position(&self, chain: ChainId) -> Option<Position> {
+        self.last_liquidity_position(chain)
+            .max(self.last_swap_position(chain))
+    }
+
+    /// The position of the last mint or burn applied on `chain`, resume the
+    /// liquidity stream after it
+    pub fn last_liquidity_position(&self, chain: ChainId) -> Option<Position> {
+        self.last_of(chain, |pool| pool.last_liquidity)
+    }
+
+    /// The position of the last swap applied on `chain`, resume the swaps
+    /// stream after it
+    pub fn last_swap_position(&self, chain: ChainId) -> Option<Position> {
+        self.last_of(chain, |pool| pool.last_swap)
+    }
+
+    pub fn get_amount_out(
+        &self,
+        chain: ChainId,
+        pool: Bytes32,
+        asset_in: AssetId,
+        amount_in: u64,
+    ) -> Result<u64> {
+        self.existing(chain, pool)?
+            .get_amount_out(asset_in, amount_in)
+    }
+
+    pub fn get_amount_in(
+        &self,
+        chain: ChainId,
+        pool: Bytes32,
+        asset_out: AssetId,
+        amount_out: u64,
+    ) -> Result<u64> {
+        self.existing(chain, pool)?
+            .get_amount_in(asset_out, amount_out)
+    }
+
+    /// Sets the assets, curve and decimals of a pool
+    pub fn apply_pool(&mut self, record: &MiraPool) {
+        let fee_bps = if record.is_stable {
+            self.stable_fee_bps
+        } else {
+            self.volatile_fee_bps
+        };
+        let pool = self.entry(
+            record.chain,
+            record.pool_address,
+            record.asset0_address,
+            record.asset1_address,
+        );
+        pool.is_stable = record.is_stable;
+        pool.fee_bps = fee_bps;
+        if record.decimals0.is_some() {
+            pool.decimals0 = record.decimals0;
+        }
+        if record.decimals1.is_some() {
+            pool.decimals1 = record.decimals1;
+        }
+    }
+
+    /// Applies a mint or burn, returning whether it was after the last mint or
+    /// burn of its pool
+    pub fn apply_liquidity(&mut self, event: &MiraLiquidity) -> Result<bool> {
+        let position = event.position();
+        let pool = self.entry(
+            event.chain,
+            event.pool_address,
+            event.asset0_address,
+            event.asset1_address,
+        );
+        if Some(position) <= pool.last_liquidity {
+            return Ok(false);
+        }
+
+        pool.apply_liquidity(event)?;
+        pool.last_liquidity = Some(position);
+        Ok(true)
+    }
+
+    /// Applies a swap, returning whether it was after the last swap of its
+    /// pool
+    pub fn apply_swap(&mut self, swap: &MiraSwap) -> Result<bool> {
+        let position = swap.position();
+        let pool = self.entry(
+            swap.chain,
+            swap.pool_address,
+            swap.asset0_address,
+            swap.asset1_address,
+        );
+        if Some(position) <= pool.last_swap {
+            return Ok(false);
+        }
+
+        pool.apply_swap(swap)?;
+        pool.last_swap = Some(position);
+        Ok(true)
+    }
+
+    fn entry(
+        &mut self,
+        chain: ChainId,
+        pool: Bytes32,
+        asset0: AssetId,
+        asset1: AssetId,
+    ) -> &mut MiraReserves {
+        self.pools
+            .entry((chain, pool))
+            .or_insert_with(|| MiraReserves::new(chain, pool, asset0, asset1))
+    }
+
+    fn last_of(
+        &self,
+        chain: ChainId,
+        last: impl Fn(&MiraReserves) -> Option<Position>,
+    ) -> Option<Position> {
+        self.pools
+            .values()
+            .filter(|pool| pool.chain == chain)
+            .filter_map(last)
+            .max()
+    }
+
+    fn existing(&self, chain: ChainId, pool: Bytes32) -> Result<&MiraReserves> {
+        self.pool(chain, pool)
+            .ok_or_else(|| Error::UnknownPool(format!("{pool:?} on {chain}")))
+    }
+}
+
+impl PoolState for MiraPoolState {
+    fn apply(&mut self, record: &Value) -> Result<bool> {
+        let invalid =
+            |err: serde_json::Error| Error::InvalidPoolEvent(format!("{err}: {record}"));
+        let mut record = record.clone();
+        // the typed records name the chain, default it like the other states
+        if let (None, Some(fields)) = (chain_of(&record), record.as_object_mut()) {
+            fields.insert("chain".into(), serde_json::to_value(ChainId::default())?);
+        }
+
+        let has = |field: &str| record.get(field).is_some();
+        let is_pool = ["pool_address", "asset0_address", "asset1_address"]
+            .into_iter()
+            .all(has)
+            && !has("amount0")
+            && !has("amount1");
+
+        if has("side") {
+            let swap = serde_json::from_value(record).map_err(invalid)?;
+            self.apply_swap(&swap)
+        } else if has("event_type") {
+            let event = serde_json::from_value(record).map_err(invalid)?;
+            self.apply_liquidity(&event)
+        } else if is_pool {
+            let pool = serde_json::from_value(record).map_err(invalid)?;
+            self.apply_pool(&pool);
+            Ok(true)
+        } else {
+            Err(Error::InvalidPoolEvent(format!(
+                "neither a pool, liquidity nor swap record: {record}"
+            )))
+        }
+    }
+}
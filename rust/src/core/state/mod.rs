@@ -0,0 +1,130 @@
+//! Local state of AMM pools rebuilt from their event streams
+//!
+//! A state consumes the JSON records of the endpoints of a protocol in block
+//! order and keeps the current reserves of every pool, so quotes are answered
+//! without a round trip. States can be written to disk and restored, and the
+//! event streams resumed from the block after the snapshot instead of
+//! replaying the history of every pool.
+//!
+//! ```no_run
+//! use pangea_client::{
+//!     query::Bound,
+//!     requests::uniswap_v2::{GetPairsRequest, GetPricesRequest},
+//!     state::{PoolState, UniV2State},
+//!     ClientBuilder, WsProvider,
+//! };
+//! use serde_json::Value;
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = ClientBuilder::default().build::<WsProvider>().await?;
+//!
+//! let mut state = UniV2State::default();
+//! let pairs = GetPairsRequest {
+//!     from_block: Bound::Exact(0),
+//!     ..Default::default()
+//! };
+//! state.consume(client.get_any::<_, Value>(pairs).await?).await?;
+//! let prices = GetPricesRequest {
+//!     from_block: Bound::Exact(0),
+//!     ..Default::default()
+//! };
+//! state.consume(client.get_any::<_, Value>(prices).await?).await?;
+//!
+//! state.snapshot("univ2.json".as_ref())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fs, path::Path};
+
+use async_trait::async_trait;
+use ethers_core::types::U256;
+use futures::StreamExt;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use super::{
+    error::Result,
+    provider::ResponseStream,
+    stream::{json_u256, json_u64},
+};
+
+pub mod curve;
+pub mod mira;
+pub mod uniswap_v2;
+pub mod uniswap_v3;
+
+pub use self::{
+    curve::{CurvePool, CurveState},
+    mira::{MiraPoolState, MiraReserves},
+    uniswap_v2::{UniV2Pair, UniV2State},
+    uniswap_v3::{UniV3Pool, UniV3State},
+};
+
+/// A state built by applying the records of a protocol one at a time
+#[async_trait]
+pub trait PoolState: Send {
+    /// Applies one record, returning whether it changed the state
+    ///
+    /// Events at or before the last one applied to their pool are skipped, so
+    /// a stream can be replayed over a restored state.
+    fn apply(&mut self, record: &Value) -> Result<bool>;
+
+    /// Applies every record of a block ordered stream
+    async fn consume(&mut self, mut stream: ResponseStream<Value>) -> Result<()> {
+        while let Some(record) = stream.next().await {
+            self.apply(&record?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the state to `path` as JSON, replacing it atomically
+    fn snapshot(&self, path: &Path) -> Result<()>
+    where
+        Self: Serialize,
+    {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
+    fn restore(path: &Path) -> Result<Self>
+    where
+        Self: DeserializeOwned + Sized,
+    {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+/// Reads the first of `names` a record has
+pub(crate) fn field<T>(record: &Value, names: &[&str]) -> Option<T>
+where
+    T: DeserializeOwned,
+{
+    names
+        .iter()
+        .find_map(|name| record.get(name))
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+}
+
+/// Reads the first of `names` a record has as a 256-bit integer, see
+/// [`json_u256`]
+pub(crate) fn u256_field(record: &Value, names: &[&str]) -> Option<U256> {
+    names
+        .iter()
+        .find_map(|name| record.get(name))
+        .and_then(json_u256)
+}
+
+/// Reads the first of `names` a record has as an unsigned integer, see
+/// [`json_u64`]
+pub(crate) fn u64_field(record: &Value, names: &[&str]) -> Option<u64> {
+    names
+        .iter()
+        .find_map(|name| record.get(name))
+        .and_then(json_u64)
+}
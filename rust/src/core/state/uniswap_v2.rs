@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use ethers_core::types::{Address, U256, U512};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{field, u256_field, PoolState};
+use crate::core::{
+    error::{Error, Result},
+    stream::{chain_of, Position},
+    types::{uniswap_v2::ReserveEvent, ChainId},
+};
+
+/// The swap fee of Uniswap V2 pairs, in basis points
+pub const DEFAULT_FEE_BPS: u32 = 30;
+
+const BPS: u32 = 10_000;
+
+/// The reserves of a Uniswap V2 pair
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniV2Pair {
+    pub chain: ChainId,
+    pub address: Address,
+    /// Unknown until the pair record, or an event naming the tokens, is applied
+    pub token0: Option<Address>,
+    pub token1: Option<Address>,
+    pub reserve0: U256,
+    pub reserve1: U256,
+    pub fee_bps: u32,
+    /// Position of the last event applied
+    pub last: Option<Position>,
+}
+
+impl UniV2Pair {
+    pub fn new(chain: ChainId, address: Address, fee_bps: u32) -> Self {
+        Self {
+            chain,
+            address,
+            token0: None,
+            token1: None,
+            reserve0: U256::zero(),
+            reserve1: U256::zero(),
+            fee_bps,
+            last: None,
+        }
+    }
+
+    /// The amount of the other token received for `amount_in` of `token_in`
+    pub fn get_amount_out(&self, token_in: Address, amount_in: U256) -> Result<U256> {
+        let (reserve_in, reserve_out) = self.reserves(token_in)?;
+        if reserve_in.is_zero() || reserve_out.is_zero() {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        let amount_in_with_fee = amount_in.full_mul(U256::from(BPS - self.fee()));
+        let numerator = amount_in_with_fee * U512::from(reserve_out);
+        let denominator = U512::from(reserve_in) * U512::from(BPS) + amount_in_with_fee;
+
+        Ok(U256::try_from(numerator / denominator).expect("less than the reserve out"))
+    }
+
+    /// The amount of the other token needed to receive `amount_out` of
+    /// `token_out`
+    pub fn get_amount_in(&self, token_out: Address, amount_out: U256) -> Result<U256> {
+        let (reserve_out, reserve_in) = self.reserves(token_out)?;
+        // a fee of 100% keeps every input, no amount buys anything
+        if amount_out >= reserve_out || reserve_in.is_zero() || self.fee() == BPS {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        let numerator = reserve_in.full_mul(amount_out) * U512::from(BPS);
+        let denominator = U512::from(reserve_out - amount_out) * U512::from(BPS - self.fee());
+        let amount_in = numerator / denominator + U512::one();
+
+        U256::try_from(amount_in).map_err(|_| Error::InsufficientLiquidity(self.name()))
+    }
+
+    /// The reserve of `token` and the reserve of the other token
+    fn reserves(&self, token: Address) -> Result<(U256, U256)> {
+        if self.token0 == Some(token) {
+            Ok((self.reserve0, self.reserve1))
+        } else if self.token1 == Some(token) {
+            Ok((self.reserve1, self.reserve0))
+        } else {
+            Err(Error::TokenNotInPool(format!("{token:?}"), self.name()))
+        }
+    }
+
+    fn fee(&self) -> u32 {
+        self.fee_bps.min(BPS)
+    }
+
+    fn name(&self) -> String {
+        format!("{:?} on {}", self.address, self.chain)
+    }
+}
+
+/// The reserves of every Uniswap V2 pair seen in a stream of pairs and prices
+/// records
+///
+/// Pair records set the tokens of a pair. A pair emits a `Sync` event with
+/// its new reserves after every mint, burn and swap, so the reserves are taken
+/// from the records that carry them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Snapshot", into = "Snapshot")]
+pub struct UniV2State {
+    pairs: HashMap<(ChainId, Address), UniV2Pair>,
+    fee_bps: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    fee_bps: u32,
+    pairs: Vec<UniV2Pair>,
+}
+
+impl From<Snapshot> for UniV2State {
+    fn from(snapshot: Snapshot) -> Self {
+        Self {
+            pairs: snapshot
+                .pairs
+                .into_iter()
+                .map(|pair| ((pair.chain, pair.address), pair))
+                .collect(),
+            fee_bps: snapshot.fee_bps,
+        }
+    }
+}
+
+impl From<UniV2State> for Snapshot {
+    fn from(state: UniV2State) -> Self {
+        Self {
+            fee_bps: state.fee_bps,
+            pairs: state.pairs.into_values().collect(),
+        }
+    }
+}
+
+impl Default for UniV2State {
+    fn default() -> Self {
+        Self {
+            pairs: HashMap::new(),
+            fee_bps: DEFAULT_FEE_BPS,
+        }
+    }
+}
+
+impl UniV2State {
+    /// Sets the fee of the pairs created from now on, for forks that charge a
+    /// different one
+    pub fn with_fee(mut self, fee_bps: u32) -> Self {
+        self.fee_bps = fee_bps;
+        self
+    }
+
+    pub fn pair(&self, chain: ChainId, address: Address) -> Option<&UniV2Pair> {
+        self.pairs.get(&(chain, address))
+    }
+
+    pub fn pair_mut(&mut self, chain: ChainId, address: Address) -> Option<&mut UniV2Pair> {
+        self.pairs.get_mut(&(chain, address))
+    }
+
+    pub fn pairs(&self) -> impl Iterator<Item = &UniV2Pair> {
+        self.pairs.values()
+    }
+
+    /// The position of the last event applied on `chain`, resume the prices
+    /// stream after it
+    pub fn last_position(&self, chain: ChainId) -> Option<Position> {
+        self.pairs
+            .values()
+            .filter(|pair| pair.chain == chain)
+            .filter_map(|pair| pair.last)
+            .max()
+    }
+
+    pub fn get_amount_out(
+        &self,
+        chain: ChainId,
+        pair: Address,
+        token_in: Address,
+        amount_in: U256,
+    ) -> Result<U256> {
+        self.existing(chain, pair)?
+            .get_amount_out(token_in, amount_in)
+    }
+
+    pub fn get_amount_in(
+        &self,
+        chain: ChainId,
+        pair: Address,
+        token_out: Address,
+        amount_out: U256,
+    ) -> Result<U256> {
+        self.existing(chain, pair)?
+            .get_amount_in(token_out, amount_out)
+    }
+
+    fn existing(&self, chain: ChainId, pair: Address) -> Result<&UniV2Pair> {
+        self.pair(chain, pair)
+            .ok_or_else(|| Error::UnknownPool(format!("{pair:?} on {chain}")))
+    }
+}
+
+impl PoolState for UniV2State {
+    fn apply(&mut self, record: &Value) -> Result<bool> {
+        let Some(address) = field::<Address>(record, &["pair_address", "address"]) else {
+            return Err(Error::InvalidPoolEvent(format!(
+                "no pair address: {record}"
+            )));
+        };
+        let chain = chain_of(record).unwrap_or_default();
+        let fee_bps = self.fee_bps;
+        let pair = self
+            .pairs
+            .entry((chain, address))
+            .or_insert_with(|| UniV2Pair::new(chain, address, fee_bps));
+
+        if let Some(token0) = field(record, &["token0", "token0_address"]) {
+            pair.token0 = Some(token0);
+        }
+        if let Some(token1) = field(record, &["token1", "token1_address"]) {
+            pair.token1 = Some(token1);
+        }
+
+        // a pair record
+        let Some(event) = record.get("event") else {
+            return Ok(true);
+        };
+
+        let position = Position::of(record);
+        if position.is_some() && position <= pair.last {
+            return Ok(false);
+        }
+
+        let event = match event {
+            Value::Number(n) => n
+                .as_i64()
+                .and_then(|n| ReserveEvent::try_from(n as i32).ok()),
+            event => serde_json::from_value::<ReserveEvent>(event.clone()).ok(),
+        };
+        let reserves = (
+            u256_field(record, &["reserve0"]),
+            u256_field(record, &["reserve1"]),
+        );
+        match (event, reserves) {
+            (None, _) => {
+                return Err(Error::InvalidPoolEvent(format!("unknown event: {record}")));
+            }
+            (_, (Some(reserve0), Some(reserve1))) => {
+                pair.reserve0 = reserve0;
+                pair.reserve1 = reserve1;
+            }
+            (Some(ReserveEvent::Sync), _) => {
+                return Err(Error::InvalidPoolEvent(format!(
+                    "sync without reserves: {record}"
+                )));
+            }
+            // the reserves are set by the sync that follows
+            _ => {}
+        }
+
+        if position.is_some() {
+            pair.last = position;
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,401 @@
+//! Fixed point math of Uniswap V3 pools
+//!
+//! Ports of the `TickMath`, `SqrtPriceMath` and `SwapMath` libraries of the
+//! core contracts, rounding the same way so quotes match the pools to the wei.
+//! Square root prices are Q64.96 fixed point numbers of `token1 / token0` in
+//! raw units, multiply a price by `10^(decimals0 - decimals1)` for human units.
+
+use ethers_core::types::{U256, U512};
+
+use crate::core::error::{Error, Result};
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// The square root price at [`MIN_TICK`]
+pub const MIN_SQRT_RATIO: U256 = U256([4295128739, 0, 0, 0]);
+/// The square root price at [`MAX_TICK`]
+pub const MAX_SQRT_RATIO: U256 = U256([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
+/// `2^96`, one in Q64.96
+pub const Q96: U256 = U256([0, 1 << 32, 0, 0]);
+
+/// Fees are in hundredths of a basis point
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// `2^128 / sqrt(1.0001)^(2^i)` in Q128.128, for every bit `i` of a tick
+const RATIOS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// The square root price at `tick`, `sqrt(1.0001^tick) * 2^96`
+pub fn sqrt_ratio_at_tick(tick: i32) -> Result<U256> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(Error::InvalidTick(tick));
+    }
+
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(RATIOS[0])
+    } else {
+        U256::one() << 128
+    };
+    for (bit, factor) in RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Q128.128 to Q64.96, rounding up
+    Ok((ratio >> 32) + U256::from(u8::from(ratio.low_u32() != 0)))
+}
+
+/// The greatest tick whose square root price is at most `sqrt_price_x96`
+pub fn tick_at_sqrt_ratio(sqrt_price_x96: U256) -> Result<i32> {
+    if sqrt_price_x96 < MIN_SQRT_RATIO || sqrt_price_x96 >= MAX_SQRT_RATIO {
+        return Err(Error::InvalidSqrtPrice(sqrt_price_x96.to_string()));
+    }
+
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_ratio_at_tick(mid)? <= sqrt_price_x96 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok(low)
+}
+
+/// The price of token0 in token1 at a square root price
+pub fn price_at_sqrt_ratio(sqrt_price_x96: U256) -> f64 {
+    let sqrt_price = to_f64(sqrt_price_x96) / to_f64(Q96);
+    sqrt_price * sqrt_price
+}
+
+/// The square root price of a price of token0 in token1, as precise as an
+/// `f64` allows
+pub fn sqrt_ratio_at_price(price: f64) -> Result<U256> {
+    let sqrt_price = price.sqrt() * to_f64(Q96);
+    let sqrt_price_x96 =
+        U256::from_dec_str(&format!("{sqrt_price:.0}"))
+            .ok()
+            .filter(|sqrt_price_x96| {
+                price.is_finite()
+                    && *sqrt_price_x96 >= MIN_SQRT_RATIO
+                    && *sqrt_price_x96 < MAX_SQRT_RATIO
+            });
+
+    sqrt_price_x96.ok_or_else(|| Error::InvalidSqrtPrice(sqrt_price.to_string()))
+}
+
+/// `1.0001^tick`
+pub fn price_at_tick(tick: i32) -> Result<f64> {
+    Ok(price_at_sqrt_ratio(sqrt_ratio_at_tick(tick)?))
+}
+
+/// The greatest tick whose price is at most `price`
+pub fn tick_at_price(price: f64) -> Result<i32> {
+    tick_at_sqrt_ratio(sqrt_ratio_at_price(price)?)
+}
+
+/// The amount of token0 between two square root prices for `liquidity`
+pub fn amount0_delta(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    if sqrt_ratio_a_x96.is_zero() {
+        return Err(Error::InvalidSqrtPrice(sqrt_ratio_a_x96.to_string()));
+    }
+
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = sqrt_ratio_b_x96 - sqrt_ratio_a_x96;
+    if round_up {
+        Ok(div_rounding_up(
+            mul_div_rounding_up(numerator1, numerator2, sqrt_ratio_b_x96)?,
+            sqrt_ratio_a_x96,
+        ))
+    } else {
+        Ok(mul_div(numerator1, numerator2, sqrt_ratio_b_x96)? / sqrt_ratio_a_x96)
+    }
+}
+
+/// The amount of token1 between two square root prices for `liquidity`
+pub fn amount1_delta(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    let delta = sqrt_ratio_b_x96 - sqrt_ratio_a_x96;
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), delta, Q96)
+    } else {
+        mul_div(U256::from(liquidity), delta, Q96)
+    }
+}
+
+/// The square root price after adding `amount_in` to the pool
+pub fn next_sqrt_price_from_input(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    if liquidity == 0 {
+        return Err(Error::MathError("no liquidity to swap against"));
+    }
+
+    if zero_for_one {
+        next_sqrt_price_from_amount0(sqrt_price_x96, liquidity, amount_in, true)
+    } else {
+        next_sqrt_price_from_amount1(sqrt_price_x96, liquidity, amount_in, true)
+    }
+}
+
+/// The square root price after taking `amount_out` from the pool
+pub fn next_sqrt_price_from_output(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    if liquidity == 0 {
+        return Err(Error::MathError("no liquidity to swap against"));
+    }
+
+    if zero_for_one {
+        next_sqrt_price_from_amount1(sqrt_price_x96, liquidity, amount_out, false)
+    } else {
+        next_sqrt_price_from_amount0(sqrt_price_x96, liquidity, amount_out, false)
+    }
+}
+
+/// Rounds up, so the price moves at least as far as the amount of token0
+fn next_sqrt_price_from_amount0(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    if amount.is_zero() {
+        return Ok(sqrt_price_x96);
+    }
+
+    let numerator1 = U256::from(liquidity) << 96;
+    let product = amount.checked_mul(sqrt_price_x96);
+    if add {
+        if let Some(denominator) = product.and_then(|product| numerator1.checked_add(product)) {
+            return mul_div_rounding_up(numerator1, sqrt_price_x96, denominator);
+        }
+
+        let denominator = (numerator1 / sqrt_price_x96)
+            .checked_add(amount)
+            .ok_or(Error::MathError("next sqrt price overflows"))?;
+        Ok(div_rounding_up(numerator1, denominator))
+    } else {
+        let product = product
+            .filter(|product| numerator1 > *product)
+            .ok_or(Error::MathError("amount out exceeds the liquidity"))?;
+        mul_div_rounding_up(numerator1, sqrt_price_x96, numerator1 - product)
+    }
+}
+
+/// Rounds down, so the price moves at most as far as the amount of token1
+fn next_sqrt_price_from_amount1(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    let liquidity = U256::from(liquidity);
+    let fits_u160 = amount.bits() <= 160;
+    if add {
+        let quotient = if fits_u160 {
+            (amount << 96) / liquidity
+        } else {
+            mul_div(amount, Q96, liquidity)?
+        };
+        sqrt_price_x96
+            .checked_add(quotient)
+            .ok_or(Error::MathError("next sqrt price overflows"))
+    } else {
+        let quotient = if fits_u160 {
+            div_rounding_up(amount << 96, liquidity)
+        } else {
+            mul_div_rounding_up(amount, Q96, liquidity)?
+        };
+        if sqrt_price_x96 <= quotient {
+            return Err(Error::MathError("amount out exceeds the liquidity"));
+        }
+        Ok(sqrt_price_x96 - quotient)
+    }
+}
+
+/// One step of a swap within a range of constant liquidity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapStep {
+    /// The square root price after the step, at most the target
+    pub sqrt_price_next_x96: U256,
+    /// The amount paid in, without the fee
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// Swaps `amount_remaining` in, or out if not `exact_in`, from the current
+/// square root price towards the target one
+pub fn compute_swap_step(
+    sqrt_price_current_x96: U256,
+    sqrt_price_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    exact_in: bool,
+    fee_pips: u32,
+) -> Result<SwapStep> {
+    if fee_pips >= FEE_DENOMINATOR {
+        return Err(Error::MathError("fee of 100% or more"));
+    }
+
+    let zero_for_one = sqrt_price_current_x96 >= sqrt_price_target_x96;
+    let (current, target) = (sqrt_price_current_x96, sqrt_price_target_x96);
+    let mut amount_in = U256::zero();
+    let mut amount_out = U256::zero();
+
+    let sqrt_price_next_x96 = if exact_in {
+        let amount_remaining_less_fee = mul_div(
+            amount_remaining,
+            U256::from(FEE_DENOMINATOR - fee_pips),
+            U256::from(FEE_DENOMINATOR),
+        )?;
+        amount_in = if zero_for_one {
+            amount0_delta(target, current, liquidity, true)?
+        } else {
+            amount1_delta(current, target, liquidity, true)?
+        };
+        if amount_remaining_less_fee >= amount_in {
+            target
+        } else {
+            next_sqrt_price_from_input(
+                current,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )?
+        }
+    } else {
+        amount_out = if zero_for_one {
+            amount1_delta(target, current, liquidity, false)?
+        } else {
+            amount0_delta(current, target, liquidity, false)?
+        };
+        if amount_remaining >= amount_out {
+            target
+        } else {
+            next_sqrt_price_from_output(current, liquidity, amount_remaining, zero_for_one)?
+        }
+    };
+
+    let max = sqrt_price_next_x96 == target;
+    let next = sqrt_price_next_x96;
+    if zero_for_one {
+        if !(max && exact_in) {
+            amount_in = amount0_delta(next, current, liquidity, true)?;
+        }
+        if !max || exact_in {
+            amount_out = amount1_delta(next, current, liquidity, false)?;
+        }
+    } else {
+        if !(max && exact_in) {
+            amount_in = amount1_delta(current, next, liquidity, true)?;
+        }
+        if !max || exact_in {
+            amount_out = amount0_delta(current, next, liquidity, false)?;
+        }
+    }
+
+    if !exact_in && amount_out > amount_remaining {
+        amount_out = amount_remaining;
+    }
+
+    let fee_amount = if exact_in && next != target {
+        // the rest of the input is kept as the fee
+        amount_remaining - amount_in
+    } else {
+        mul_div_rounding_up(
+            amount_in,
+            U256::from(fee_pips),
+            U256::from(FEE_DENOMINATOR - fee_pips),
+        )?
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next_x96: next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// `a * b / denominator` with a 512-bit intermediate product
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    U256::try_from(a.full_mul(b) / U512::from(denominator))
+        .map_err(|_| Error::MathError("mul_div overflows"))
+}
+
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    let product = a.full_mul(b);
+    let denominator = U512::from(denominator);
+    let quotient =
+        product / denominator + U512::from(u8::from(!(product % denominator).is_zero()));
+
+    U256::try_from(quotient).map_err(|_| Error::MathError("mul_div overflows"))
+}
+
+fn div_rounding_up(a: U256, b: U256) -> U256 {
+    a / b + U256::from(u8::from(!(a % b).is_zero()))
+}
+
+fn sorted(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+fn to_f64(value: U256) -> f64 {
+    value
+        .0
+        .iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * 2f64.powi(64) + *limb as f64)
+}
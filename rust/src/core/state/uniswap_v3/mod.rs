@@ -0,0 +1,561 @@
+use std::collections::{BTreeMap, HashMap};
+
+use ethers_core::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use self::math::{
+    compute_swap_step, sqrt_ratio_at_tick, tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK,
+    MIN_SQRT_RATIO, MIN_TICK,
+};
+use super::{field, u256_field, PoolState};
+use crate::core::{
+    error::{Error, Result},
+    stream::{chain_of, json_u256, Position},
+    types::ChainId,
+};
+
+pub mod math;
+
+/// The liquidity of the positions starting or ending at a tick
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tick {
+    /// The liquidity of every position referencing the tick
+    pub liquidity_gross: u128,
+    /// The liquidity added when the price crosses the tick upwards
+    pub liquidity_net: i128,
+}
+
+/// The result of a simulated swap
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Swap {
+    /// The amount paid in, with the fee
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+    /// The state of the pool after the swap
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+}
+
+/// A Uniswap V3 pool and its liquidity by tick
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniV3Pool {
+    pub chain: ChainId,
+    pub address: Address,
+    pub token0: Option<Address>,
+    pub token1: Option<Address>,
+    /// The fee in hundredths of a basis point, unknown until the pool record
+    /// is applied
+    pub fee: Option<u32>,
+    pub tick_spacing: i32,
+    /// Zero until a price record is applied
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    /// The liquidity in range of the current tick
+    pub liquidity: u128,
+    /// The initialized ticks
+    pub ticks: BTreeMap<i32, Tick>,
+    /// Position of the last positions record applied
+    pub last_position: Option<Position>,
+    /// Position of the last prices record applied
+    pub last_price: Option<Position>,
+}
+
+impl UniV3Pool {
+    pub fn new(chain: ChainId, address: Address) -> Self {
+        Self {
+            chain,
+            address,
+            token0: None,
+            token1: None,
+            fee: None,
+            tick_spacing: 1,
+            sqrt_price_x96: U256::zero(),
+            tick: 0,
+            liquidity: 0,
+            ticks: BTreeMap::new(),
+            last_position: None,
+            last_price: None,
+        }
+    }
+
+    /// Adds liquidity between two ticks, or removes it if negative
+    pub fn update_position(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<()> {
+        if tick_lower >= tick_upper || tick_lower < MIN_TICK {
+            return Err(Error::InvalidTick(tick_lower));
+        }
+        if tick_upper > MAX_TICK {
+            return Err(Error::InvalidTick(tick_upper));
+        }
+
+        self.update_tick(tick_lower, liquidity_delta, false)?;
+        self.update_tick(tick_upper, liquidity_delta, true)?;
+
+        if self.is_priced() && (tick_lower..tick_upper).contains(&self.tick) {
+            self.liquidity = add_delta(self.liquidity, liquidity_delta)
+                .ok_or_else(|| self.underflow(tick_lower, tick_upper))?;
+        }
+
+        Ok(())
+    }
+
+    fn update_tick(&mut self, tick: i32, liquidity_delta: i128, upper: bool) -> Result<()> {
+        let entry = self.ticks.get(&tick).copied().unwrap_or_default();
+        let net_delta = if upper {
+            liquidity_delta.checked_neg()
+        } else {
+            Some(liquidity_delta)
+        };
+        let updated = add_delta(entry.liquidity_gross, liquidity_delta).and_then(|gross| {
+            Some(Tick {
+                liquidity_gross: gross,
+                liquidity_net: entry.liquidity_net.checked_add(net_delta?)?,
+            })
+        });
+
+        match updated {
+            Some(updated) if updated.liquidity_gross == 0 => {
+                self.ticks.remove(&tick);
+            }
+            Some(updated) => {
+                self.ticks.insert(tick, updated);
+            }
+            None => return Err(self.underflow(tick, tick)),
+        }
+
+        Ok(())
+    }
+
+    fn underflow(&self, tick_lower: i32, tick_upper: i32) -> Error {
+        Error::InvalidPoolEvent(format!(
+            "liquidity of {} between ticks {tick_lower} and {tick_upper} underflows, replay \
+             its positions from the pool creation",
+            self.name()
+        ))
+    }
+
+    /// The liquidity in range at `tick`, the sum of the net liquidity of the
+    /// initialized ticks at or below it
+    pub fn liquidity_at(&self, tick: i32) -> Result<u128> {
+        self.ticks
+            .range(..=tick)
+            .try_fold(0u128, |liquidity, (_, t)| {
+                add_delta(liquidity, t.liquidity_net)
+            })
+            .ok_or_else(|| self.underflow(MIN_TICK, tick))
+    }
+
+    /// Whether a price record was applied
+    pub fn is_priced(&self) -> bool {
+        !self.sqrt_price_x96.is_zero()
+    }
+
+    /// Simulates a swap of `amount` in if `exact_in`, or out otherwise,
+    /// selling token0 if `zero_for_one`
+    ///
+    /// The swap stops at `sqrt_price_limit_x96`, or when the liquidity runs
+    /// out, like the pool would. The pool is not changed, see
+    /// [`UniV3Pool::apply_swap`].
+    pub fn swap(
+        &self,
+        zero_for_one: bool,
+        amount: U256,
+        exact_in: bool,
+        sqrt_price_limit_x96: Option<U256>,
+    ) -> Result<Swap> {
+        let fee = self
+            .fee
+            .ok_or_else(|| Error::UnknownPool(format!("no pool record for {}", self.name())))?;
+        if !self.is_priced() {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        let limit = sqrt_price_limit_x96.unwrap_or(if zero_for_one {
+            MIN_SQRT_RATIO + 1
+        } else {
+            MAX_SQRT_RATIO - 1
+        });
+        let valid_limit = if zero_for_one {
+            limit < self.sqrt_price_x96 && limit > MIN_SQRT_RATIO
+        } else {
+            limit > self.sqrt_price_x96 && limit < MAX_SQRT_RATIO
+        };
+        if !valid_limit {
+            return Err(Error::InvalidSqrtPrice(limit.to_string()));
+        }
+
+        let mut remaining = amount;
+        let mut swap = Swap {
+            sqrt_price_x96: self.sqrt_price_x96,
+            tick: self.tick,
+            liquidity: self.liquidity,
+            ..Default::default()
+        };
+        while !remaining.is_zero() && swap.sqrt_price_x96 != limit {
+            let sqrt_price_start_x96 = swap.sqrt_price_x96;
+            let (tick_next, initialized) = self.next_initialized_tick(swap.tick, zero_for_one);
+            let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_next_x96 = sqrt_ratio_at_tick(tick_next)?;
+            let target = if (zero_for_one && sqrt_price_next_x96 < limit)
+                || (!zero_for_one && sqrt_price_next_x96 > limit)
+            {
+                limit
+            } else {
+                sqrt_price_next_x96
+            };
+
+            let step = compute_swap_step(
+                swap.sqrt_price_x96,
+                target,
+                swap.liquidity,
+                remaining,
+                exact_in,
+                fee,
+            )?;
+            remaining -= if exact_in {
+                step.amount_in + step.fee_amount
+            } else {
+                step.amount_out
+            };
+            swap.amount_in += step.amount_in + step.fee_amount;
+            swap.amount_out += step.amount_out;
+            swap.fee_amount += step.fee_amount;
+            swap.sqrt_price_x96 = step.sqrt_price_next_x96;
+
+            if swap.sqrt_price_x96 == sqrt_price_next_x96 {
+                if initialized {
+                    let net = self.ticks[&tick_next].liquidity_net;
+                    let net = if zero_for_one {
+                        net.checked_neg()
+                    } else {
+                        Some(net)
+                    };
+                    swap.liquidity = net
+                        .and_then(|net| add_delta(swap.liquidity, net))
+                        .ok_or_else(|| self.underflow(tick_next, tick_next))?;
+                }
+                swap.tick = if zero_for_one {
+                    tick_next - 1
+                } else {
+                    tick_next
+                };
+            } else if swap.sqrt_price_x96 != sqrt_price_start_x96 {
+                swap.tick = tick_at_sqrt_ratio(swap.sqrt_price_x96)?;
+            }
+        }
+
+        Ok(swap)
+    }
+
+    /// Moves the pool to the state after a simulated swap
+    pub fn apply_swap(&mut self, swap: &Swap) {
+        self.sqrt_price_x96 = swap.sqrt_price_x96;
+        self.tick = swap.tick;
+        self.liquidity = swap.liquidity;
+    }
+
+    /// The amount of the other token received for `amount_in` of `token_in`
+    pub fn get_amount_out(&self, token_in: Address, amount_in: U256) -> Result<U256> {
+        let swap = self.swap(self.zero_for_one(token_in, true)?, amount_in, true, None)?;
+        if swap.amount_in != amount_in {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        Ok(swap.amount_out)
+    }
+
+    /// The amount of the other token needed to receive `amount_out` of
+    /// `token_out`
+    pub fn get_amount_in(&self, token_out: Address, amount_out: U256) -> Result<U256> {
+        let swap = self.swap(
+            self.zero_for_one(token_out, false)?,
+            amount_out,
+            false,
+            None,
+        )?;
+        if swap.amount_out != amount_out {
+            return Err(Error::InsufficientLiquidity(self.name()));
+        }
+
+        Ok(swap.amount_in)
+    }
+
+    /// Whether token0 is sold when `token` is paid in, or taken out
+    fn zero_for_one(&self, token: Address, is_in: bool) -> Result<bool> {
+        if self.token0 == Some(token) {
+            Ok(is_in)
+        } else if self.token1 == Some(token) {
+            Ok(!is_in)
+        } else {
+            Err(Error::TokenNotInPool(format!("{token:?}"), self.name()))
+        }
+    }
+
+    /// The next initialized tick below or at `tick` if `lte`, above it
+    /// otherwise, within the 256 spaced ticks of one word of the tick bitmap
+    /// of the pool
+    ///
+    /// Swap steps end at word boundaries like on chain, so the rounding of
+    /// every step is the same.
+    fn next_initialized_tick(&self, tick: i32, lte: bool) -> (i32, bool) {
+        let spacing = self.tick_spacing.max(1);
+        let compressed = tick.div_euclid(spacing);
+
+        if lte {
+            let word_start = (compressed >> 8) << 8;
+            self.ticks
+                .range(word_start * spacing..=compressed * spacing)
+                .next_back()
+                .map_or((word_start * spacing, false), |(tick, _)| (*tick, true))
+        } else {
+            let next = compressed + 1;
+            let word_end = ((next >> 8) << 8) + 255;
+            self.ticks
+                .range(next * spacing..=word_end * spacing)
+                .next()
+                .map_or((word_end * spacing, false), |(tick, _)| (*tick, true))
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("{:?} on {}", self.address, self.chain)
+    }
+}
+
+/// The tick spacing of the fee tiers enabled by the Uniswap V3 factory
+pub fn default_tick_spacing(fee: u32) -> Option<i32> {
+    match fee {
+        100 => Some(1),
+        500 => Some(10),
+        3000 => Some(60),
+        10000 => Some(200),
+        _ => None,
+    }
+}
+
+/// The Uniswap V3 pools seen in streams of pools, positions and prices
+/// records
+///
+/// Pool records set the tokens, fee and tick spacing of a pool. Positions
+/// records add or remove liquidity between two ticks, and must be applied
+/// from the creation of the pool for the liquidity by tick to be complete.
+/// Prices records set the price, tick and liquidity in range, which is rebuilt
+/// from the ticks if a record has none.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Snapshot", into = "Snapshot")]
+pub struct UniV3State {
+    pools: HashMap<(ChainId, Address), UniV3Pool>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    pools: Vec<UniV3Pool>,
+}
+
+impl From<Snapshot> for UniV3State {
+    fn from(snapshot: Snapshot) -> Self {
+        Self {
+            pools: snapshot
+                .pools
+                .into_iter()
+                .map(|pool| ((pool.chain, pool.address), pool))
+                .collect(),
+        }
+    }
+}
+
+impl From<UniV3State> for Snapshot {
+    fn from(state: UniV3State) -> Self {
+        Self {
+            pools: state.pools.into_values().collect(),
+        }
+    }
+}
+
+impl UniV3State {
+    pub fn pool(&self, chain: ChainId, address: Address) -> Option<&UniV3Pool> {
+        self.pools.get(&(chain, address))
+    }
+
+    pub fn pool_mut(&mut self, chain: ChainId, address: Address) -> Option<&mut UniV3Pool> {
+        self.pools.get_mut(&(chain, address))
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &UniV3Pool> {
+        self.pools.values()
+    }
+
+    /// The position of the last positions record applied on `chain`, resume
+    /// the positions stream after it
+    pub fn last_position(&self, chain: ChainId) -> Option<Position> {
+        self.pools
+            .values()
+            .filter(|pool| pool.chain == chain)
+            .filter_map(|pool| pool.last_position)
+            .max()
+    }
+
+    /// The position of the last prices record applied on `chain`, resume the
+    /// prices stream after it
+    pub fn last_price(&self, chain: ChainId) -> Option<Position> {
+        self.pools
+            .values()
+            .filter(|pool| pool.chain == chain)
+            .filter_map(|pool| pool.last_price)
+            .max()
+    }
+
+    pub fn get_amount_out(
+        &self,
+        chain: ChainId,
+        pool: Address,
+        token_in: Address,
+        amount_in: U256,
+    ) -> Result<U256> {
+        self.existing(chain, pool)?
+            .get_amount_out(token_in, amount_in)
+    }
+
+    pub fn get_amount_in(
+        &self,
+        chain: ChainId,
+        pool: Address,
+        token_out: Address,
+        amount_out: U256,
+    ) -> Result<U256> {
+        self.existing(chain, pool)?
+            .get_amount_in(token_out, amount_out)
+    }
+
+    fn existing(&self, chain: ChainId, pool: Address) -> Result<&UniV3Pool> {
+        self.pool(chain, pool)
+            .ok_or_else(|| Error::UnknownPool(format!("{pool:?} on {chain}")))
+    }
+}
+
+impl PoolState for UniV3State {
+    fn apply(&mut self, record: &Value) -> Result<bool> {
+        let Some(address) = field::<Address>(record, &["pool_address", "address"]) else {
+            return Err(Error::InvalidPoolEvent(format!(
+                "no pool address: {record}"
+            )));
+        };
+        let chain = chain_of(record).unwrap_or_default();
+        let pool = self
+            .pools
+            .entry((chain, address))
+            .or_insert_with(|| UniV3Pool::new(chain, address));
+
+        if let Some(token0) = field(record, &["token0", "token0_address"]) {
+            pool.token0 = Some(token0);
+        }
+        if let Some(token1) = field(record, &["token1", "token1_address"]) {
+            pool.token1 = Some(token1);
+        }
+
+        // a pool record
+        if record.get("event").is_none() {
+            if let Some(fee) = field::<u32>(record, &["fee"]) {
+                pool.fee = Some(fee);
+                pool.tick_spacing = field(record, &["tick_spacing"])
+                    .or_else(|| default_tick_spacing(fee))
+                    .unwrap_or(pool.tick_spacing);
+            }
+        }
+
+        let position = Position::of(record);
+        let tick_range = (
+            field::<i32>(record, &["tick_lower"]),
+            field::<i32>(record, &["tick_upper"]),
+        );
+        if let (Some(tick_lower), Some(tick_upper)) = tick_range {
+            if position.is_some() && position <= pool.last_position {
+                return Ok(false);
+            }
+            // collected fees carry no liquidity
+            let Some(liquidity) =
+                i128_field(record, &["liquidity_delta", "liquidity", "amount"])
+            else {
+                return Ok(false);
+            };
+            let is_burn = record
+                .get("event")
+                .and_then(Value::as_str)
+                .is_some_and(|event| event.eq_ignore_ascii_case("burn"));
+            let liquidity_delta = if is_burn { -liquidity.abs() } else { liquidity };
+
+            pool.update_position(tick_lower, tick_upper, liquidity_delta)?;
+            if position.is_some() {
+                pool.last_position = position;
+            }
+            return Ok(true);
+        }
+
+        let price = match (
+            u256_field(record, &["sqrt_price_x96", "sqrt_price", "sqrtPriceX96"]),
+            field::<i32>(record, &["tick"]),
+        ) {
+            (Some(sqrt_price_x96), Some(tick)) => Some((sqrt_price_x96, tick)),
+            (Some(sqrt_price_x96), None) => {
+                Some((sqrt_price_x96, tick_at_sqrt_ratio(sqrt_price_x96)?))
+            }
+            (None, Some(tick)) => Some((sqrt_ratio_at_tick(tick)?, tick)),
+            (None, None) => None,
+        };
+        if let Some((sqrt_price_x96, tick)) = price {
+            if position.is_some() && position <= pool.last_price {
+                return Ok(false);
+            }
+
+            pool.sqrt_price_x96 = sqrt_price_x96;
+            pool.tick = tick;
+            pool.liquidity = match u256_field(record, &["liquidity"]) {
+                Some(liquidity) if liquidity.bits() <= 128 => liquidity.as_u128(),
+                Some(liquidity) => {
+                    return Err(Error::InvalidPoolEvent(format!(
+                        "liquidity {liquidity} overflows: {record}"
+                    )));
+                }
+                None => pool.liquidity_at(tick)?,
+            };
+            if position.is_some() {
+                pool.last_price = position;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Reads a signed liquidity sent as a JSON number or a decimal or hex string
+fn i128_field(record: &Value, names: &[&str]) -> Option<i128> {
+    let value = names.iter().find_map(|name| record.get(name))?;
+    if let Some(n) = value.as_i64() {
+        return Some(n.into());
+    }
+
+    let (is_negative, magnitude) = match value.as_str() {
+        Some(s) => match s.strip_prefix('-') {
+            Some(magnitude) => (true, json_u256(&Value::from(magnitude))?),
+            None => (false, json_u256(value)?),
+        },
+        None => (false, json_u256(value)?),
+    };
+    if magnitude.bits() > 127 {
+        return None;
+    }
+
+    let magnitude = magnitude.as_u128() as i128;
+    Some(if is_negative { -magnitude } else { magnitude })
+}
+
+fn add_delta(liquidity: u128, delta: i128) -> Option<u128> {
+    liquidity.checked_add_signed(delta)
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use arrow::{array::RecordBatch, buffer::Buffer, ipc::reader::StreamDecoder};
+use ethers_core::types::U256;
+use futures::StreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use super::{error::ResponseError, provider::ResponseStream, types::ChainId, utils::parse_u64};
+
+struct Lines {
+    stream: ResponseStream<Vec<u8>>,
+    buffer: Vec<u8>,
+    lines: VecDeque<Vec<u8>>,
+    done: bool,
+}
+
+impl Lines {
+    fn split(&mut self) {
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let mut line = self.buffer.drain(..=pos).collect::<Vec<_>>();
+            line.pop();
+            self.push(line);
+        }
+    }
+
+    fn push(&mut self, line: Vec<u8>) {
+        if !line.iter().all(u8::is_ascii_whitespace) {
+            self.lines.push_back(line);
+        }
+    }
+}
+
+/// Splits a raw [`Format::JsonStream`](crate::Format::JsonStream) response into
+/// one item per JSON line, regardless of how the transport chunked the bytes
+pub fn json_lines(stream: ResponseStream<Vec<u8>>) -> ResponseStream<Vec<u8>> {
+    let state = Lines {
+        stream,
+        buffer: Vec::new(),
+        lines: VecDeque::new(),
+        done: false,
+    };
+
+    let lines = futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(line) = state.lines.pop_front() {
+                return Some((Ok(line), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            match state.stream.next().await {
+                Some(Ok(chunk)) => {
+                    state.buffer.extend(chunk);
+                    state.split();
+                }
+                Some(Err(err)) => return Some((Err(err), state)),
+                None => {
+                    state.done = true;
+                    let rest = std::mem::take(&mut state.buffer);
+                    state.push(rest);
+                }
+            }
+        }
+    })
+    .boxed();
+
+    ResponseError::map_stream(lines).boxed()
+}
+
+/// Deserializes every line of a raw [`Format::JsonStream`](crate::Format::JsonStream)
+/// response into `T`
+pub fn json_records<T>(stream: ResponseStream<Vec<u8>>) -> ResponseStream<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    json_lines(stream)
+        .map(|line| line.and_then(|line| Ok(serde_json::from_slice::<T>(&line)?)))
+        .boxed()
+}
+
+/// Position of a record on its chain, used to order and de-duplicate records
+///
+/// Records that are not tied to a transaction or a log, like blocks, have
+/// their missing indices set to zero.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Position {
+    pub block_number: u64,
+    pub transaction_index: u64,
+    pub log_index: u64,
+}
+
+impl Position {
+    /// Reads the position of a JSON record, if it carries a block number
+    pub fn of(record: &Value) -> Option<Self> {
+        let field = |name: &str| record.get(name).and_then(json_u64);
+
+        Some(Self {
+            block_number: field("block_number").or_else(|| field("height"))?,
+            transaction_index: field("transaction_index").unwrap_or_default(),
+            log_index: field("log_index").unwrap_or_default(),
+        })
+    }
+}
+
+/// Reads the chain a JSON record belongs to
+pub fn chain_of(record: &Value) -> Option<ChainId> {
+    record
+        .get("chain")
+        .and_then(|chain| serde_json::from_value(chain.clone()).ok())
+}
+
+/// Reads an unsigned integer sent as a JSON number, a decimal string or a
+/// `0x`-prefixed hex string
+pub fn json_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => parse_u64(s).ok(),
+        _ => None,
+    }
+}
+
+/// Reads a 256-bit unsigned integer sent as a JSON number, a decimal string or
+/// a `0x`-prefixed hex string
+pub fn json_u256(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(n) => n.as_u64().map(U256::from),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(s).ok(),
+        },
+        _ => None,
+    }
+}
+
+/// Keeps consuming `stream` in a background task, buffering its items until
+/// they are polled
+///
+/// Providers deliver all responses over a shared connection, so a response
+/// that is not polled can stall the others. Detaching it avoids that while it
+/// waits for its turn.
+pub(crate) fn detach<T>(mut stream: ResponseStream<T>) -> ResponseStream<T>
+where
+    T: Send + 'static,
+{
+    let (sink, buffer) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(item) = stream.next().await {
+            if sink.send(item).is_err() {
+                break;
+            }
+        }
+    });
+
+    UnboundedReceiverStream::new(buffer).boxed()
+}
+
+struct Seam {
+    backfill: Option<ResponseStream<Vec<u8>>>,
+    live: ResponseStream<Vec<u8>>,
+    last: HashMap<Option<ChainId>, Position>,
+    caught_up: Arc<AtomicBool>,
+}
+
+/// Joins the JSON lines of a bounded `backfill` and a `live` response to the
+/// same request into one stream
+///
+/// Live records at or before the last position the backfill delivered on
+/// their chain are dropped, so the two may overlap without duplicates. Live
+/// records sharing a position with each other are all delivered. `caught_up`
+/// is set once every record of the backfill has been delivered.
+pub(crate) fn stitch(
+    backfill: ResponseStream<Vec<u8>>,
+    live: ResponseStream<Vec<u8>>,
+    caught_up: Arc<AtomicBool>,
+) -> ResponseStream<Value> {
+    let state = Seam {
+        backfill: Some(backfill),
+        live,
+        last: HashMap::new(),
+        caught_up,
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            let (line, is_live) = match &mut state.backfill {
+                Some(backfill) => match backfill.next().await {
+                    Some(line) => (line, false),
+                    None => {
+                        state.backfill = None;
+                        state.caught_up.store(true, Ordering::Release);
+                        continue;
+                    }
+                },
+                None => (state.live.next().await?, true),
+            };
+
+            let record = match line.and_then(|line| Ok(serde_json::from_slice::<Value>(&line)?)) {
+                Ok(record) => record,
+                Err(err) => return Some((Err(err), state)),
+            };
+
+            // only the seam is de-duplicated, records of one response may share
+            // a position
+            if let Some(position) = Position::of(&record) {
+                let last = state.last.entry(chain_of(&record)).or_default();
+                if !is_live {
+                    *last = position.max(*last);
+                } else if position <= *last {
+                    continue;
+                }
+            }
+
+            return Some((Ok(record), state));
+        }
+    })
+    .boxed()
+}
+
+/// A line read ahead of its response, with its position
+type Head = Option<(Position, Vec<u8>)>;
+
+struct Heads {
+    streams: Vec<(ResponseStream<Vec<u8>>, Head)>,
+}
+
+/// Merges the JSON lines of several block ordered responses into one block
+/// ordered response, one line per item
+///
+/// A line is only delivered once every response has delivered a later line
+/// or ended, so this is meant for bounded responses.
+pub(crate) fn merge_ordered(streams: Vec<ResponseStream<Vec<u8>>>) -> ResponseStream<Vec<u8>> {
+    let state = Heads {
+        streams: streams
+            .into_iter()
+            .map(|stream| (json_lines(stream), None))
+            .collect(),
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        for (stream, head) in &mut state.streams {
+            if head.is_none() {
+                match stream.next().await {
+                    Some(Ok(line)) => {
+                        let position = serde_json::from_slice::<Value>(&line)
+                            .ok()
+                            .and_then(|record| Position::of(&record))
+                            .unwrap_or_default();
+                        *head = Some((position, line));
+                    }
+                    Some(Err(err)) => return Some((Err(err), state)),
+                    None => {}
+                }
+            }
+        }
+
+        state.streams.retain(|(_, head)| head.is_some());
+        let (_, head) = state
+            .streams
+            .iter_mut()
+            .min_by_key(|(_, head)| head.as_ref().map(|(position, _)| *position))?;
+        let (_, mut line) = head.take()?;
+        line.push(b'\n');
+
+        Some((Ok(line), state))
+    })
+    .boxed()
+}
+
+/// Decodes a raw [`Format::ArrowStream`](crate::Format::ArrowStream) or
+/// [`Format::Arrow`](crate::Format::Arrow) response into record batches,
+/// regardless of how the transport chunked the bytes
+///
+/// The response may hold several IPC streams one after the other, each is
+/// decoded with its own schema.
+pub fn arrow_batches(stream: ResponseStream<Vec<u8>>) -> ResponseStream<RecordBatch> {
+    let state = (
+        ResponseError::map_stream(stream).boxed(),
+        StreamDecoder::new(),
+    );
+
+    futures::stream::unfold(state, |(mut stream, mut decoder)| async move {
+        let chunk = match stream.next().await? {
+            Ok(chunk) => chunk,
+            Err(err) => return Some((vec![Err(err)], (stream, decoder))),
+        };
+
+        let mut buffer = Buffer::from_vec(chunk);
+        let mut batches = Vec::new();
+        while !buffer.is_empty() {
+            match decoder.decode(&mut buffer) {
+                Ok(Some(batch)) => batches.push(Ok(batch)),
+                Ok(None) => {}
+                // past the end of an IPC stream, the decoder stops before the
+                // next stream without consuming it
+                Err(_) if decoder.finish().is_ok() => decoder = StreamDecoder::new(),
+                Err(err) => {
+                    batches.push(Err(err.into()));
+                    break;
+                }
+            }
+        }
+
+        Some((batches, (stream, decoder)))
+    })
+    .flat_map(futures::stream::iter)
+    .boxed()
+}
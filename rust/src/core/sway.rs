@@ -0,0 +1,605 @@
+//! Local decoding of Fuel logs with the JSON ABI of a Sway contract
+//!
+//! A [`SwayAbi`] is loaded from the `*-abi.json` file `forc build` writes
+//! (program ABI spec version 1). The type of a `LogData` receipt is picked by
+//! its log id, sent as `rb`, and its data is decoded into a [`SwayValue`],
+//! which can in turn be deserialized into your own Rust types.
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use pangea_client::{
+//!     requests::fuel::GetFuelLogsRequest, sway::SwayAbi, ChainId, ClientBuilder, WsProvider,
+//! };
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct SwapEvent {
+//!     amount_in: u64,
+//!     amount_out: u64,
+//! }
+//!
+//! # async fn run() -> pangea_client::Result<()> {
+//! let client = ClientBuilder::default().build::<WsProvider>().await?;
+//! let abi = SwayAbi::from_json(&std::fs::read_to_string("out/debug/amm-abi.json")?)?;
+//!
+//! let request = GetFuelLogsRequest {
+//!     chains: [ChainId::FUEL].into(),
+//!     ..Default::default()
+//! };
+//! let mut stream = client.decode_fuel_logs(request, abi).await?;
+//! while let Some(log) = stream.next().await {
+//!     let swap = log?.deserialize::<SwapEvent>()?;
+//!     println!("{:?}", swap.value);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use ethers_core::{types::U256, utils::hex};
+use fuel_core_types::{fuel_tx::Receipt, fuel_types::Bytes32};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use super::{
+    error::{Error, Result},
+    stream::json_u64,
+};
+
+/// A type of the Sway ABI, with its generic parameters resolved
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SwayType {
+    Unit,
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U256,
+    B256,
+    /// `str` and `std::string::String`
+    String,
+    /// `str[N]`
+    StringArray(usize),
+    /// `std::bytes::Bytes` and `raw untyped slice`
+    Bytes,
+    Array(Box<SwayType>, usize),
+    Vec(Box<SwayType>),
+    Tuple(Vec<SwayType>),
+    Struct {
+        name: String,
+        fields: Vec<(String, SwayType)>,
+    },
+    Enum {
+        name: String,
+        variants: Vec<(String, SwayType)>,
+    },
+}
+
+/// A value decoded from a Sway log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SwayValue {
+    Unit,
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U256(U256),
+    B256(Bytes32),
+    String(String),
+    Bytes(Vec<u8>),
+    Array(Vec<SwayValue>),
+    Tuple(Vec<SwayValue>),
+    Struct {
+        name: String,
+        fields: Vec<(String, SwayValue)>,
+    },
+    Enum {
+        name: String,
+        variant: String,
+        value: Box<SwayValue>,
+    },
+}
+
+impl SwayValue {
+    /// Converts the value into JSON the way `serde` expects Rust types to look:
+    /// structs become objects, enums are externally tagged and `Option` maps to
+    /// `null` or its value. `u256`, `b256` and bytes become `0x`-prefixed hex.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Unit => Value::Null,
+            Self::Bool(b) => Value::Bool(*b),
+            Self::U8(n) => Value::from(*n),
+            Self::U16(n) => Value::from(*n),
+            Self::U32(n) => Value::from(*n),
+            Self::U64(n) => Value::from(*n),
+            Self::U256(n) => Value::String(format!("{n:#x}")),
+            Self::B256(b) => Value::String(format!("0x{}", hex::encode(b))),
+            Self::String(s) => Value::String(s.clone()),
+            Self::Bytes(bytes) => Value::String(format!("0x{}", hex::encode(bytes))),
+            Self::Array(values) | Self::Tuple(values) => {
+                Value::Array(values.iter().map(Self::to_json).collect())
+            }
+            Self::Struct { fields, .. } => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_json()))
+                    .collect(),
+            ),
+            Self::Enum {
+                name,
+                variant,
+                value,
+            } if is_option(name) => match variant.as_str() {
+                "None" => Value::Null,
+                _ => value.to_json(),
+            },
+            Self::Enum { variant, value, .. } => match **value {
+                Self::Unit => Value::String(variant.clone()),
+                ref value => {
+                    Value::Object([(variant.clone(), value.to_json())].into_iter().collect())
+                }
+            },
+        }
+    }
+
+    /// Deserializes the value into `T`, see [`SwayValue::to_json`]
+    pub fn deserialize<T>(&self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_value(self.to_json())?)
+    }
+}
+
+fn is_option(name: &str) -> bool {
+    name == "Option" || name.ends_with("::Option")
+}
+
+/// A log decoded with a [`SwayAbi`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedFuelLog<T = SwayValue> {
+    /// The log id, `rb` of the receipt
+    pub log_id: u64,
+    pub value: T,
+    /// The receipt record as it was sent by the server
+    pub log: Value,
+}
+
+impl DecodedFuelLog {
+    /// Deserializes the value into `T`, see [`SwayValue::to_json`]
+    pub fn deserialize<T>(self) -> Result<DecodedFuelLog<T>>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(DecodedFuelLog {
+            log_id: self.log_id,
+            value: self.value.deserialize()?,
+            log: self.log,
+        })
+    }
+}
+
+/// The logged types of a Sway contract, by log id
+#[derive(Clone, Debug, Default)]
+pub struct SwayAbi {
+    logged_types: HashMap<u64, SwayType>,
+}
+
+impl SwayAbi {
+    /// Loads the JSON ABI written by `forc build`
+    pub fn from_json(json: &str) -> Result<Self> {
+        let abi = serde_json::from_str::<ProgramAbi>(json)?;
+        if abi
+            .encoding_version
+            .as_deref()
+            .is_some_and(|version| version != "1")
+        {
+            return Err(invalid_abi(format!(
+                "unsupported encoding version {:?}",
+                abi.encoding_version
+            )));
+        }
+
+        let resolver = Resolver {
+            concrete: abi
+                .concrete_types
+                .iter()
+                .map(|ty| (ty.concrete_type_id.as_str(), ty))
+                .collect(),
+            metadata: abi
+                .metadata_types
+                .iter()
+                .map(|ty| (ty.metadata_type_id, ty))
+                .collect(),
+        };
+
+        let logged_types = abi
+            .logged_types
+            .iter()
+            .map(|logged| {
+                let log_id = json_u64(&logged.log_id)
+                    .ok_or_else(|| invalid_abi(format!("invalid log id {}", logged.log_id)))?;
+                Ok((log_id, resolver.concrete(&logged.concrete_type_id)?))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { logged_types })
+    }
+
+    /// The ids of every logged type, to be used in `rb__in` filters
+    pub fn log_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.logged_types.keys().copied()
+    }
+
+    pub fn logged_type(&self, log_id: u64) -> Option<&SwayType> {
+        self.logged_types.get(&log_id)
+    }
+
+    /// Decodes the data of a log, returns `None` if the log id is unknown
+    pub fn decode(&self, log_id: u64, data: &[u8]) -> Result<Option<SwayValue>> {
+        let ty = match self.logged_type(log_id) {
+            Some(ty) => ty,
+            None => return Ok(None),
+        };
+
+        let mut reader = Reader { data };
+        let value = reader.read(ty)?;
+        if !reader.data.is_empty() {
+            return Err(Error::SwayDecode(format!(
+                "{} trailing bytes after log {log_id}",
+                reader.data.len()
+            )));
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Decodes a `LogData` receipt, returns `None` for other receipts and
+    /// unknown log ids
+    pub fn decode_receipt(&self, receipt: &Receipt) -> Result<Option<SwayValue>> {
+        match (receipt, receipt.rb(), receipt.data()) {
+            (Receipt::LogData { .. }, Some(log_id), Some(data)) => self.decode(log_id, data),
+            _ => Ok(None),
+        }
+    }
+
+    /// Decodes a JSON receipt record, as streamed by
+    /// [`get_fuel_logs_by_format`](crate::provider::FuelProvider::get_fuel_logs_by_format),
+    /// returns `None` if it has no data or its log id is unknown
+    pub fn decode_log(&self, log: Value) -> Result<Option<DecodedFuelLog>> {
+        let log_id = log.get("rb").and_then(json_u64);
+        let data = match log.get("data") {
+            Some(Value::String(data)) => hex::decode(data.trim_start_matches("0x")).ok(),
+            _ => None,
+        };
+        let (log_id, data) = match (log_id, data) {
+            (Some(log_id), Some(data)) => (log_id, data),
+            _ => return Ok(None),
+        };
+
+        Ok(self
+            .decode(log_id, &data)?
+            .map(|value| DecodedFuelLog { log_id, value, log }))
+    }
+}
+
+fn invalid_abi(msg: impl Into<String>) -> Error {
+    Error::InvalidSwayAbi(msg.into())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgramAbi {
+    #[serde(default)]
+    encoding_version: Option<String>,
+    #[serde(default)]
+    concrete_types: Vec<ConcreteType>,
+    #[serde(default)]
+    metadata_types: Vec<MetadataType>,
+    #[serde(default)]
+    logged_types: Vec<LoggedType>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConcreteType {
+    #[serde(rename = "type")]
+    type_field: String,
+    concrete_type_id: String,
+    #[serde(default)]
+    metadata_type_id: Option<u64>,
+    #[serde(default)]
+    type_arguments: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataType {
+    #[serde(rename = "type")]
+    type_field: String,
+    metadata_type_id: u64,
+    #[serde(default)]
+    components: Vec<Component>,
+    #[serde(default)]
+    type_parameters: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Component {
+    #[serde(default)]
+    name: String,
+    type_id: TypeId,
+    #[serde(default)]
+    type_arguments: Vec<Component>,
+}
+
+/// Components refer to concrete types by their hash and to metadata types by
+/// their index
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TypeId {
+    Concrete(String),
+    Metadata(u64),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LoggedType {
+    log_id: Value,
+    concrete_type_id: String,
+}
+
+struct Resolver<'a> {
+    concrete: HashMap<&'a str, &'a ConcreteType>,
+    metadata: HashMap<u64, &'a MetadataType>,
+}
+
+impl Resolver<'_> {
+    fn concrete(&self, id: &str) -> Result<SwayType> {
+        let ty = self
+            .concrete
+            .get(id)
+            .ok_or_else(|| invalid_abi(format!("unknown concrete type {id}")))?;
+
+        match ty.metadata_type_id {
+            Some(metadata_type_id) => {
+                let args = ty
+                    .type_arguments
+                    .iter()
+                    .map(|arg| self.concrete(arg))
+                    .collect::<Result<Vec<_>>>()?;
+                self.metadata(metadata_type_id, args)
+            }
+            None => primitive(&ty.type_field),
+        }
+    }
+
+    fn metadata(&self, id: u64, args: Vec<SwayType>) -> Result<SwayType> {
+        let ty = self
+            .metadata
+            .get(&id)
+            .ok_or_else(|| invalid_abi(format!("unknown metadata type {id}")))?;
+        let generics = ty
+            .type_parameters
+            .iter()
+            .copied()
+            .zip(args)
+            .collect::<HashMap<_, _>>();
+
+        let components = || {
+            ty.components
+                .iter()
+                .map(|component| {
+                    Ok((
+                        component.name.clone(),
+                        self.component(component, &generics)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        let type_field = ty.type_field.as_str();
+        if let Some(name) = type_field.strip_prefix("struct ") {
+            let ty = match name {
+                "std::vec::Vec" | "Vec" => {
+                    let item = ty
+                        .type_parameters
+                        .first()
+                        .and_then(|param| generics.get(param))
+                        .ok_or_else(|| {
+                            invalid_abi(format!("unresolved item type of {name}"))
+                        })?;
+                    SwayType::Vec(Box::new(item.clone()))
+                }
+                "std::bytes::Bytes" | "Bytes" => SwayType::Bytes,
+                "std::string::String" | "String" => SwayType::String,
+                _ => SwayType::Struct {
+                    name: name.to_string(),
+                    fields: components()?,
+                },
+            };
+            return Ok(ty);
+        }
+        if let Some(name) = type_field.strip_prefix("enum ") {
+            return Ok(SwayType::Enum {
+                name: name.to_string(),
+                variants: components()?,
+            });
+        }
+        if type_field.starts_with("generic ") {
+            return Err(invalid_abi(format!("unresolved {type_field}")));
+        }
+        if type_field.starts_with('(') {
+            let items = components()?.into_iter().map(|(_, ty)| ty).collect();
+            return Ok(SwayType::Tuple(items));
+        }
+        if let Some(len) = array_len(type_field) {
+            let (_, item) = components()?
+                .into_iter()
+                .next()
+                .ok_or_else(|| invalid_abi(format!("{type_field} has no item type")))?;
+            return Ok(SwayType::Array(Box::new(item), len));
+        }
+
+        primitive(type_field)
+    }
+
+    fn component(
+        &self,
+        component: &Component,
+        generics: &HashMap<u64, SwayType>,
+    ) -> Result<SwayType> {
+        match &component.type_id {
+            TypeId::Concrete(id) => self.concrete(id),
+            TypeId::Metadata(id) => match generics.get(id) {
+                Some(ty) => Ok(ty.clone()),
+                None => {
+                    let args = component
+                        .type_arguments
+                        .iter()
+                        .map(|arg| self.component(arg, generics))
+                        .collect::<Result<Vec<_>>>()?;
+                    self.metadata(*id, args)
+                }
+            },
+        }
+    }
+}
+
+/// Reads the length of an array type like `[_; 3]`
+fn array_len(type_field: &str) -> Option<usize> {
+    type_field
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .split(';')
+        .nth(1)?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn primitive(type_field: &str) -> Result<SwayType> {
+    let ty = match type_field {
+        "()" => SwayType::Unit,
+        "bool" => SwayType::Bool,
+        "u8" => SwayType::U8,
+        "u16" => SwayType::U16,
+        "u32" => SwayType::U32,
+        "u64" => SwayType::U64,
+        "u256" => SwayType::U256,
+        "b256" => SwayType::B256,
+        "str" => SwayType::String,
+        "raw untyped slice" => SwayType::Bytes,
+        _ => match type_field
+            .strip_prefix("str[")
+            .and_then(|len| len.strip_suffix(']'))
+            .and_then(|len| len.parse().ok())
+        {
+            Some(len) => SwayType::StringArray(len),
+            None => return Err(invalid_abi(format!("unsupported type {type_field}"))),
+        },
+    };
+
+    Ok(ty)
+}
+
+/// Reads values in the Sway ABI encoding (version 1), where integers are
+/// big-endian and dynamically sized values are prefixed by a `u64` length
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        if self.data.len() < len {
+            return Err(Error::SwayDecode(format!(
+                "expected {len} bytes, {} left",
+                self.data.len()
+            )));
+        }
+
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        Ok(u64::from_be_bytes(self.array()?) as usize)
+    }
+
+    fn read(&mut self, ty: &SwayType) -> Result<SwayValue> {
+        let value = match ty {
+            SwayType::Unit => SwayValue::Unit,
+            SwayType::Bool => match self.array::<1>()? {
+                [0] => SwayValue::Bool(false),
+                [1] => SwayValue::Bool(true),
+                [b] => return Err(Error::SwayDecode(format!("invalid bool {b}"))),
+            },
+            SwayType::U8 => SwayValue::U8(u8::from_be_bytes(self.array()?)),
+            SwayType::U16 => SwayValue::U16(u16::from_be_bytes(self.array()?)),
+            SwayType::U32 => SwayValue::U32(u32::from_be_bytes(self.array()?)),
+            SwayType::U64 => SwayValue::U64(u64::from_be_bytes(self.array()?)),
+            SwayType::U256 => SwayValue::U256(U256::from_big_endian(&self.array::<32>()?)),
+            SwayType::B256 => SwayValue::B256(Bytes32::new(self.array()?)),
+            SwayType::String => {
+                let len = self.len()?;
+                SwayValue::String(self.string(len)?)
+            }
+            SwayType::StringArray(len) => SwayValue::String(self.string(*len)?),
+            SwayType::Bytes => {
+                let len = self.len()?;
+                SwayValue::Bytes(self.take(len)?.to_vec())
+            }
+            SwayType::Array(ty, len) => SwayValue::Array(self.many(ty, *len)?),
+            SwayType::Vec(ty) => {
+                let len = self.len()?;
+                SwayValue::Array(self.many(ty, len)?)
+            }
+            SwayType::Tuple(types) => SwayValue::Tuple(
+                types
+                    .iter()
+                    .map(|ty| self.read(ty))
+                    .collect::<Result<_>>()?,
+            ),
+            SwayType::Struct { name, fields } => SwayValue::Struct {
+                name: name.clone(),
+                fields: fields
+                    .iter()
+                    .map(|(name, ty)| Ok((name.clone(), self.read(ty)?)))
+                    .collect::<Result<_>>()?,
+            },
+            SwayType::Enum { name, variants } => {
+                let discriminant = self.len()?;
+                let (variant, ty) = variants.get(discriminant).ok_or_else(|| {
+                    Error::SwayDecode(format!("invalid discriminant {discriminant} of {name}"))
+                })?;
+
+                SwayValue::Enum {
+                    name: name.clone(),
+                    variant: variant.clone(),
+                    value: Box::new(self.read(ty)?),
+                }
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn many(&mut self, ty: &SwayType, len: usize) -> Result<Vec<SwayValue>> {
+        (0..len).map(|_| self.read(ty)).collect()
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|err| Error::SwayDecode(err.to_string()))
+    }
+}
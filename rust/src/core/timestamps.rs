@@ -0,0 +1,181 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+
+use super::{
+    error::{Error, Result},
+    requests::BlockRange,
+    types::{query::Bound, ChainId},
+    utils::deserialize_u64,
+};
+
+/// Offset of the TAI64 epoch label used by Fuel block timestamps
+const TAI64_UNIX_OFFSET: u64 = (1 << 62) + 10;
+
+/// The subset of a block record needed to map time onto block heights
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub(crate) struct BlockTime {
+    #[serde(alias = "height", deserialize_with = "deserialize_u64")]
+    pub block_number: u64,
+    #[serde(alias = "time", deserialize_with = "deserialize_u64")]
+    pub timestamp: u64,
+}
+
+impl BlockTime {
+    /// Unix timestamp of the block, Fuel blocks are stamped in TAI64
+    pub fn unix_timestamp(&self) -> i64 {
+        match self.timestamp.checked_sub(TAI64_UNIX_OFFSET) {
+            Some(unix) if self.timestamp >= 1 << 62 => unix as i64,
+            _ => self.timestamp as i64,
+        }
+    }
+}
+
+fn from_latest(depth: u64) -> Bound {
+    match depth {
+        0 => Bound::Latest,
+        depth => Bound::FromLatest(depth),
+    }
+}
+
+/// Per-chain cache of the block timestamps seen while resolving
+/// client-side bounds
+///
+/// Every probe of the binary search is kept, so repeated lookups of nearby
+/// timestamps only need a handful of requests, and repeated lookups of the
+/// same timestamp none at all.
+#[derive(Debug, Default)]
+pub(crate) struct BlockTimes {
+    chains: Mutex<HashMap<ChainId, BTreeMap<u64, i64>>>,
+}
+
+impl BlockTimes {
+    /// Replaces the client-side bounds of `request` with block heights the
+    /// server understands, see [`Bound::is_client_side`]
+    pub async fn resolve_request<R, F, Fut>(&self, request: &mut R, fetch: F) -> Result<()>
+    where
+        R: BlockRange,
+        F: Fn(ChainId, Bound) -> Fut,
+        Fut: Future<Output = Result<Option<BlockTime>>>,
+    {
+        let (from_block, to_block) = request.block_range();
+        if !from_block.is_client_side() && !to_block.is_client_side() {
+            return Ok(());
+        }
+
+        let chains = request.chains();
+        let chain = match chains.iter().next() {
+            Some(chain) if chains.len() == 1 => *chain,
+            _ => return Err(Error::ClientSideBoundChains(chains.clone())),
+        };
+
+        let from_block = self.resolve_bound(chain, from_block, &fetch).await?;
+        request.set_from_block(from_block);
+        let to_block = self.resolve_bound(chain, to_block, &fetch).await?;
+        request.set_to_block(to_block);
+
+        Ok(())
+    }
+
+    async fn resolve_bound<F, Fut>(&self, chain: ChainId, bound: Bound, fetch: &F) -> Result<Bound>
+    where
+        F: Fn(ChainId, Bound) -> Fut,
+        Fut: Future<Output = Result<Option<BlockTime>>>,
+    {
+        let timestamp = match bound {
+            Bound::Finalized => return Ok(from_latest(chain.finality_depth())),
+            Bound::Safe => return Ok(from_latest(chain.safe_depth())),
+            Bound::Timestamp(timestamp) => timestamp,
+            Bound::FromLatestDuration(duration) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                now.saturating_sub(duration).as_secs() as i64
+            }
+            bound => return Ok(bound),
+        };
+
+        let height = self
+            .resolve(chain, timestamp, |bound| fetch(chain, bound))
+            .await?;
+        Ok(Bound::Exact(height as i64))
+    }
+
+    /// Finds the first block of `chain` produced at or after `timestamp`
+    ///
+    /// If the timestamp lies beyond the chain head, the height of the next
+    /// block to be produced is returned.
+    pub async fn resolve<F, Fut>(&self, chain: ChainId, timestamp: i64, fetch: F) -> Result<u64>
+    where
+        F: Fn(Bound) -> Fut,
+        Fut: Future<Output = Result<Option<BlockTime>>>,
+    {
+        let (mut lo, hi) = self.bracket(chain, timestamp);
+
+        let mut hi = match hi {
+            Some(hi) => hi,
+            None => {
+                let head = fetch(Bound::Latest)
+                    .await?
+                    .ok_or(Error::BlockNotFound(chain, Bound::Latest))?;
+                self.insert(chain, &head);
+
+                if head.unix_timestamp() < timestamp {
+                    return Ok(head.block_number + 1);
+                }
+                head.block_number
+            }
+        };
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let bound = Bound::Exact(mid as i64);
+            let block = fetch(bound)
+                .await?
+                .ok_or(Error::BlockNotFound(chain, bound))?;
+            self.insert(chain, &block);
+
+            if block.unix_timestamp() >= timestamp {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        Ok(lo)
+    }
+
+    /// Narrows the search window using the blocks seen so far
+    fn bracket(&self, chain: ChainId, timestamp: i64) -> (u64, Option<u64>) {
+        let chains = self.chains.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(blocks) = chains.get(&chain) else {
+            return (0, None);
+        };
+
+        let lo = blocks
+            .iter()
+            .rev()
+            .find(|(_, time)| **time < timestamp)
+            .map_or(0, |(height, _)| height + 1);
+        let hi = blocks
+            .iter()
+            .find(|(_, time)| **time >= timestamp)
+            .map(|(height, _)| *height);
+
+        (lo, hi)
+    }
+
+    fn insert(&self, chain: ChainId, block: &BlockTime) {
+        self.chains
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(chain)
+            .or_default()
+            .insert(block.block_number, block.unix_timestamp());
+    }
+}
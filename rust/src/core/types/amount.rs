@@ -0,0 +1,245 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use ethers_core::types::{U256, U512};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Error;
+
+/// The largest number of decimals an [`Amount`] can have, `10^77` is the
+/// largest power of ten that fits in a `U256`
+pub const MAX_DECIMALS: u8 = 77;
+
+/// An exact decimal amount, a raw integer value scaled by `10^-decimals`
+///
+/// `Amount::new(U256::from(1_500_000), 6)` is `1.5` of a token with 6 decimals.
+/// Amounts compare by value, so `1.5` with 6 decimals equals `1.50` with 2
+/// decimals.
+///
+/// Amounts are serialized as exact decimal strings like `"1.5"`, so filters
+/// match to the wei.
+#[derive(Clone, Copy, Default)]
+pub struct Amount {
+    raw: U256,
+    decimals: u8,
+}
+
+impl Amount {
+    /// Fails if `decimals` is larger than [`MAX_DECIMALS`]
+    pub fn new(raw: U256, decimals: u8) -> Result<Self, Error> {
+        if decimals > MAX_DECIMALS {
+            return Err(Error::InvalidAmount(format!(
+                "an amount can have at most {MAX_DECIMALS} decimals, got {decimals}"
+            )));
+        }
+
+        Ok(Self { raw, decimals })
+    }
+
+    /// Parses a decimal string like `"1.5"` into an amount with `decimals`
+    /// decimals, failing if it has more significant fractional digits
+    pub fn parse_units(value: &str, decimals: u8) -> Result<Self, Error> {
+        value.parse::<Self>()?.rescale(decimals).ok_or_else(|| {
+            Error::InvalidAmount(format!("{value} does not fit in {decimals} decimals"))
+        })
+    }
+
+    /// The integer value in the smallest unit, e.g. wei
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// The same amount with `decimals` decimals, if it can be represented
+    /// exactly
+    pub fn rescale(&self, decimals: u8) -> Option<Self> {
+        if decimals > MAX_DECIMALS {
+            return None;
+        }
+
+        let raw = if decimals >= self.decimals {
+            self.raw
+                .checked_mul(U256::exp10((decimals - self.decimals).into()))?
+        } else {
+            let (raw, rest) = self
+                .raw
+                .div_mod(U256::exp10((self.decimals - decimals).into()));
+            if !rest.is_zero() {
+                return None;
+            }
+            raw
+        };
+
+        Some(Self { raw, decimals })
+    }
+
+    /// The amount with its trailing fractional zeros removed
+    fn normalized(&self) -> (U256, u8) {
+        let ten = U256::from(10);
+        let (mut raw, mut decimals) = (self.raw, self.decimals);
+        while decimals > 0 && (raw % ten).is_zero() {
+            raw /= ten;
+            decimals -= 1;
+        }
+
+        (raw, decimals)
+    }
+}
+
+impl From<U256> for Amount {
+    fn from(raw: U256) -> Self {
+        Self { raw, decimals: 0 }
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(raw: u64) -> Self {
+        U256::from(raw).into()
+    }
+}
+
+impl PartialEq for Amount {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Eq for Amount {}
+
+impl Hash for Amount {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Amount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lhs = U512::from(self.raw) * U512::exp10(other.decimals.into());
+        let rhs = U512::from(other.raw) * U512::exp10(self.decimals.into());
+        lhs.cmp(&rhs)
+    }
+}
+
+/// Parses a decimal string like `"1.5"`, keeping as many decimals as it has
+/// fractional digits, or a `0x`-prefixed hex integer
+impl FromStr for Amount {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidAmount(s.to_string());
+        let value = s.trim();
+
+        if let Some(hex) = value.strip_prefix("0x") {
+            return U256::from_str_radix(hex, 16)
+                .map(Self::from)
+                .map_err(|_| invalid());
+        }
+
+        let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+        let digits = format!("{int}{frac}");
+        if digits.is_empty()
+            || !digits.bytes().all(|b| b.is_ascii_digit())
+            || frac.len() > MAX_DECIMALS as usize
+        {
+            return Err(invalid());
+        }
+
+        let raw = U256::from_dec_str(&digits).map_err(|_| invalid())?;
+        Self::new(raw, frac.len() as u8)
+    }
+}
+
+/// Formats the exact decimal value without trailing fractional zeros
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (raw, decimals) = self.normalized();
+        let decimals = decimals as usize;
+
+        let digits = format!("{:0>width$}", raw.to_string(), width = decimals + 1);
+        let (int, frac) = digits.split_at(digits.len() - decimals);
+        if frac.is_empty() {
+            write!(f, "{int}")
+        } else {
+            write!(f, "{int}.{frac}")
+        }
+    }
+}
+
+impl fmt::Debug for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Amount({self})")
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = Amount;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a non-negative decimal number or string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(v.into())
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                u64::try_from(v)
+                    .map(Amount::from)
+                    .map_err(|_| E::custom(format!("negative amount {v}")))
+            }
+
+            // `f64` is displayed without exponent, with the shortest digits that
+            // round-trip, so `0.1` stays `0.1`
+            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                self.visit_str(&v.to_string())
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
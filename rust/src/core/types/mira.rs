@@ -1,10 +1,18 @@
-use serde::{Deserialize, Serialize};
+use fuel_core_types::fuel_types::{AssetId, Bytes32};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use strum::AsRefStr;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+use crate::core::{
+    stream::Position,
+    types::{amount::Amount, ChainId},
+    utils::deserialize_u64,
+};
+
+#[derive(AsRefStr, Clone, Copy, Debug, Hash, Serialize, Deserialize, PartialEq, Eq)]
 pub enum EventType {
-    Mint,
-    Burn,
-    Swap,
+    Mint = 0,
+    Burn = 1,
+    Swap = 2,
 }
 
 impl EventType {
@@ -30,10 +38,12 @@ impl TryFrom<i32> for EventType {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+/// The direction of a swap, a buy of asset0 with asset1 or a sell of asset0
+/// for asset1
+#[derive(AsRefStr, Clone, Copy, Debug, Hash, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Side {
-    Buy,
-    Sell,
+    Buy = 0,
+    Sell = 1,
 }
 
 impl Side {
@@ -56,3 +66,107 @@ impl TryFrom<i32> for Side {
         }
     }
 }
+
+/// A record of the Mira v1 pools endpoint
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MiraPool {
+    pub chain: ChainId,
+    #[serde(alias = "height", deserialize_with = "deserialize_u64")]
+    pub block_number: u64,
+    pub pool_address: Bytes32,
+    pub asset0_address: AssetId,
+    pub asset1_address: AssetId,
+    /// Whether the pool trades on the stable `x^3 y + x y^3` curve instead of
+    /// the volatile `x y` one
+    #[serde(default)]
+    pub is_stable: bool,
+    #[serde(default)]
+    pub decimals0: Option<u8>,
+    #[serde(default)]
+    pub decimals1: Option<u8>,
+}
+
+/// A record of the Mira v1 liquidity endpoint, liquidity added by a mint or
+/// removed by a burn
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MiraLiquidity {
+    pub chain: ChainId,
+    #[serde(alias = "height", deserialize_with = "deserialize_u64")]
+    pub block_number: u64,
+    #[serde(default, deserialize_with = "deserialize_u64")]
+    pub transaction_index: u64,
+    #[serde(default, deserialize_with = "deserialize_u64")]
+    pub log_index: u64,
+    pub pool_address: Bytes32,
+    pub asset0_address: AssetId,
+    pub asset1_address: AssetId,
+    #[serde(deserialize_with = "deserialize_raw_enum")]
+    pub event_type: EventType,
+    /// In base units of asset0
+    pub amount0: Amount,
+    /// In base units of asset1
+    pub amount1: Amount,
+}
+
+impl MiraLiquidity {
+    pub fn position(&self) -> Position {
+        Position {
+            block_number: self.block_number,
+            transaction_index: self.transaction_index,
+            log_index: self.log_index,
+        }
+    }
+}
+
+/// A record of the Mira v1 swaps endpoint
+///
+/// A sell pays `amount0` of asset0 in for `amount1` of asset1 out, a buy pays
+/// `amount1` of asset1 in for `amount0` of asset0 out.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MiraSwap {
+    pub chain: ChainId,
+    #[serde(alias = "height", deserialize_with = "deserialize_u64")]
+    pub block_number: u64,
+    #[serde(default, deserialize_with = "deserialize_u64")]
+    pub transaction_index: u64,
+    #[serde(default, deserialize_with = "deserialize_u64")]
+    pub log_index: u64,
+    pub pool_address: Bytes32,
+    pub asset0_address: AssetId,
+    pub asset1_address: AssetId,
+    #[serde(deserialize_with = "deserialize_raw_enum")]
+    pub side: Side,
+    /// In base units of asset0
+    pub amount0: Amount,
+    /// In base units of asset1
+    pub amount1: Amount,
+}
+
+impl MiraSwap {
+    pub fn position(&self) -> Position {
+        Position {
+            block_number: self.block_number,
+            transaction_index: self.transaction_index,
+            log_index: self.log_index,
+        }
+    }
+}
+
+/// Deserializes an enum sent as its name or as its raw `i32`
+fn deserialize_raw_enum<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + TryFrom<i32, Error = crate::Error>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr<T> {
+        Raw(i32),
+        Name(T),
+    }
+
+    match Repr::<T>::deserialize(deserializer)? {
+        Repr::Raw(v) => T::try_from(v).map_err(de::Error::custom),
+        Repr::Name(value) => Ok(value),
+    }
+}
@@ -1,13 +1,20 @@
-use std::{collections::HashSet, fmt, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+    sync::{OnceLock, PoisonError, RwLock},
+};
 
-use num_derive::FromPrimitive as FromPrimitiveDerive;
 use num_traits::FromPrimitive;
 use serde::{
     de::{self, Visitor},
     Deserialize, Deserializer, Serialize, Serializer,
 };
-use strum::{AsRefStr, EnumString};
+use strum::AsRefStr;
+
+use crate::Error;
 
+pub mod amount;
 pub mod format;
 pub mod fuel;
 pub mod mira;
@@ -15,10 +22,9 @@ pub mod query;
 pub mod status;
 pub mod uniswap_v2;
 
-#[derive(
-    AsRefStr, EnumString, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, FromPrimitiveDerive,
-)]
+#[derive(AsRefStr, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 #[allow(clippy::upper_case_acronyms)]
+#[repr(u64)]
 pub enum ChainId {
     #[strum(to_string = "none")]
     Any = 0,
@@ -31,7 +37,7 @@ pub enum ChainId {
     BNB = 56,
     #[strum(to_string = "MATIC")]
     MATIC = 137,
-    #[strum(to_string = "MOVE")]
+    #[strum(to_string = "MEVM")]
     MEVM = 336,
     #[strum(to_string = "FUEL")]
     FUEL = 9889,
@@ -47,13 +53,108 @@ pub enum ChainId {
     FUELTESTNET = 2_147_483_646,
     #[strum(to_string = "BTC")]
     BTC = 2_147_483_647,
+    /// A chain without a variant, by its numeric id
+    ///
+    /// Use [`ChainId::from_id`] to build it, so known ids map to their variant.
+    /// Codes and names can be given to these chains with
+    /// [`ChainId::register`], `as_ref` is `"Other"` for all of them.
+    Other(u64),
+}
+
+struct ChainInfo {
+    code: String,
+    name: String,
+}
+
+/// The codes and names of the registered [`ChainId::Other`] chains
+fn registry() -> &'static RwLock<HashMap<u64, ChainInfo>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<u64, ChainInfo>>> = OnceLock::new();
+    REGISTRY.get_or_init(RwLock::default)
 }
 
 impl ChainId {
+    const KNOWN: [ChainId; 13] = [
+        Self::Any,
+        Self::ETH,
+        Self::OPT,
+        Self::BNB,
+        Self::MATIC,
+        Self::MEVM,
+        Self::FUEL,
+        Self::ARB,
+        Self::AVAX,
+        Self::BOB,
+        Self::SEPOLIA,
+        Self::FUELTESTNET,
+        Self::BTC,
+    ];
+
+    /// The chain with the numeric id `id`, [`ChainId::Other`] if it has no
+    /// variant
+    pub fn from_id(id: u64) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|chain| chain.id() == id)
+            .unwrap_or(Self::Other(id))
+    }
+
+    /// Registers the code and name of a chain without a variant, so it can be
+    /// parsed from and serialized as its code
+    pub fn register(
+        id: u64,
+        code: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let code = code.into();
+        let chain = Self::from_id(id);
+        if !matches!(chain, Self::Other(_)) {
+            return Err(Error::ChainAlreadyKnown(code, chain));
+        }
+        if let Ok(known) = code.parse::<Self>() {
+            if known != chain {
+                return Err(Error::ChainAlreadyKnown(code, known));
+            }
+        }
+
+        registry()
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                id,
+                ChainInfo {
+                    code,
+                    name: name.into(),
+                },
+            );
+
+        Ok(chain)
+    }
+
     pub fn is_any(&self) -> bool {
         matches!(self, Self::Any)
     }
 
+    pub const fn id(&self) -> u64 {
+        match self {
+            Self::Any => 0,
+            Self::ETH => 1,
+            Self::OPT => 10,
+            Self::BNB => 56,
+            Self::MATIC => 137,
+            Self::MEVM => 336,
+            Self::FUEL => 9889,
+            Self::ARB => 42161,
+            Self::AVAX => 43114,
+            Self::BOB => 60_808,
+            Self::SEPOLIA => 1115511,
+            Self::FUELTESTNET => 2_147_483_646,
+            Self::BTC => 2_147_483_647,
+            Self::Other(id) => *id,
+        }
+    }
+
+    /// The code chains are serialized as, the numeric id for unregistered
+    /// [`ChainId::Other`] chains
     pub fn chain_code(&self) -> String {
         match self {
             Self::Any => "ANY".to_string(),
@@ -69,6 +170,34 @@ impl ChainId {
             Self::BTC => "BTC".to_string(),
             Self::BOB => "BOB".to_string(),
             Self::FUELTESTNET => "FUELTESTNET".to_string(),
+            Self::Other(id) => Self::registered(*id, |info| info.code.clone())
+                .unwrap_or_else(|| id.to_string()),
+        }
+    }
+
+    /// Number of blocks behind the head after which a block is considered final
+    pub fn finality_depth(&self) -> u64 {
+        match self {
+            Self::ETH | Self::SEPOLIA => 64,
+            Self::OPT | Self::ARB | Self::BOB => 1200,
+            Self::MATIC => 256,
+            Self::BNB => 15,
+            Self::BTC => 6,
+            Self::AVAX | Self::MEVM | Self::FUEL | Self::FUELTESTNET | Self::Any => 0,
+            Self::Other(_) => 0,
+        }
+    }
+
+    /// Number of blocks behind the head after which a reorganization is unlikely
+    pub fn safe_depth(&self) -> u64 {
+        match self {
+            Self::ETH | Self::SEPOLIA => 32,
+            Self::OPT | Self::ARB | Self::BOB => 300,
+            Self::MATIC => 32,
+            Self::BNB => 3,
+            Self::BTC => 3,
+            Self::AVAX | Self::MEVM | Self::FUEL | Self::FUELTESTNET | Self::Any => 0,
+            Self::Other(_) => 0,
         }
     }
 
@@ -87,7 +216,67 @@ impl ChainId {
             Self::BTC => "Bitcoin".to_string(),
             Self::BOB => "Bob".to_string(),
             Self::FUELTESTNET => "Fuel Testnet".to_string(),
+            Self::Other(id) => Self::registered(*id, |info| info.name.clone())
+                .unwrap_or_else(|| format!("Chain {id}")),
+        }
+    }
+
+    fn registered<T>(id: u64, f: impl FnOnce(&ChainInfo) -> T) -> Option<T> {
+        registry()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&id)
+            .map(f)
+    }
+}
+
+impl fmt::Display for ChainId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.chain_code())
+    }
+}
+
+/// Parses a chain from its code, a registered code or its numeric id
+///
+/// `MOVE` is accepted for [`ChainId::MEVM`] and `none` for [`ChainId::Any`].
+impl FromStr for ChainId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = s.parse::<u64>() {
+            return Ok(Self::from_id(id));
         }
+
+        let chain = match s {
+            "MOVE" => Some(Self::MEVM),
+            "none" => Some(Self::Any),
+            _ => Self::KNOWN
+                .into_iter()
+                .find(|chain| chain.chain_code() == s),
+        };
+        if let Some(chain) = chain {
+            return Ok(chain);
+        }
+
+        registry()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|(_, info)| info.code == s)
+            .map(|(id, _)| Self::Other(*id))
+            .ok_or_else(|| Error::UnknownChain(s.to_string()))
+    }
+}
+
+/// Known ids map to their variant and others to [`ChainId::Other`], negative
+/// ids are not chains
+impl FromPrimitive for ChainId {
+    fn from_i64(n: i64) -> Option<Self> {
+        u64::try_from(n).ok().map(Self::from_id)
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(Self::from_id(n))
     }
 }
 
@@ -109,73 +298,27 @@ impl Visitor<'_> for ChainIdVisitor {
         formatter.write_str("a valid ChainId as an integer or string")
     }
 
-    fn visit_i32<E>(self, value: i32) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        ChainId::from_i32(value).ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"))
-    }
-
-    fn visit_u32<E>(self, value: u32) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        ChainId::from_i32(value as i32)
-            .ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"))
-    }
-
     fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        ChainId::from_i32(value as i32)
-            .ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"))
+        u64::try_from(value)
+            .map(ChainId::from_id)
+            .map_err(|_| serde::de::Error::custom("Invalid ChainId value"))
     }
 
     fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        ChainId::from_i32(value as i32)
-            .ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"))
-    }
-
-    fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        ChainId::from_i32(v as i32).ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"))
-    }
-
-    fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        ChainId::from_i32(v as i32).ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"))
-    }
-
-    fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        if let Ok(int_value) = value.parse::<i32>() {
-            return ChainId::from_i32(int_value)
-                .ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"));
-        }
-
-        ChainId::from_str(&value).map_err(|_| serde::de::Error::custom("Invalid ChainId value"))
+        Ok(ChainId::from_id(value))
     }
 
     fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        if let Ok(int_value) = value.parse::<i32>() {
-            return ChainId::from_i32(int_value)
-                .ok_or_else(|| serde::de::Error::custom("Invalid ChainId value"));
-        }
-
-        ChainId::from_str(value).map_err(|_| serde::de::Error::custom("Invalid ChainId value"))
+        ChainId::from_str(value).map_err(serde::de::Error::custom)
     }
 }
 
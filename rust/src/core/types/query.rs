@@ -1,10 +1,24 @@
-use std::ops::Mul;
+use std::{fmt, ops::Mul, str::FromStr, time::Duration};
 
+use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::Error;
+
+lazy_static! {
+    static ref FROM_LATEST: Regex = Regex::new(r#"^(latest|now)\s*-\s*(.+)$"#).unwrap();
+    static ref DURATION: Regex = Regex::new(r#"^(\d+\s*(ms|w|d|h|m|s)\s*)+$"#).unwrap();
+    static ref DURATION_PART: Regex = Regex::new(r#"(\d+)\s*(ms|w|d|h|m|s)"#).unwrap();
+    static ref RFC3339: Regex = Regex::new(
+        r#"^(\d{4})-(\d{2})-(\d{2})(?:[Tt ](\d{2}):(\d{2}):(\d{2})(?:\.\d+)?([Zz]|([+-])(\d{2}):(\d{2})))?$"#,
+    )
+    .unwrap();
+}
+
 #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum Bound {
-    /// The range starts/ends at this block height (inclusive)
+    /// The range starts at this block height (inclusive) or ends before it
+    /// (exclusive)
     Exact(i64),
     /// The range should start/end at the latest block height
     #[default]
@@ -13,15 +27,42 @@ pub enum Bound {
     FromLatest(u64),
     /// Real-time
     Subscribe,
+    /// The range starts/ends at the first block produced at or after this
+    /// unix timestamp (in seconds)
+    ///
+    /// Only the blocks endpoints understand time, so the client resolves this
+    /// bound into an [`Bound::Exact`] height before sending a request
+    Timestamp(i64),
+    /// The range starts/ends at the first block produced within this duration
+    /// before now, resolved like [`Bound::Timestamp`]
+    FromLatestDuration(Duration),
+    /// The latest block considered final on the chain, see
+    /// [`ChainId::finality_depth`](crate::ChainId::finality_depth)
+    Finalized,
+    /// The latest block unlikely to be reorganized on the chain, see
+    /// [`ChainId::safe_depth`](crate::ChainId::safe_depth)
+    Safe,
 }
 
 impl Bound {
     const LATEST: &'static str = "latest";
     const NONE: &'static str = "none";
+    const NOW: &'static str = "now";
+    const FINALIZED: &'static str = "finalized";
+    const SAFE: &'static str = "safe";
 
     pub const fn none() -> Self {
         Self::Subscribe
     }
+
+    /// Returns true if the server can not interpret the bound, and the client
+    /// has to resolve it into a block height first
+    pub const fn is_client_side(&self) -> bool {
+        matches!(
+            self,
+            Self::Timestamp(_) | Self::FromLatestDuration(_) | Self::Finalized | Self::Safe
+        )
+    }
 }
 
 impl PartialOrd for Bound {
@@ -31,7 +72,15 @@ impl PartialOrd for Bound {
         match (self, other) {
             (Self::Exact(lhs), Self::Exact(rhs)) => lhs.partial_cmp(rhs),
             (Self::FromLatest(lhs), Self::FromLatest(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Timestamp(lhs), Self::Timestamp(rhs)) => lhs.partial_cmp(rhs),
+            (Self::FromLatestDuration(lhs), Self::FromLatestDuration(rhs)) => rhs.partial_cmp(lhs),
             (Self::Latest, Self::Latest) => Some(Ordering::Equal),
+            (Self::Finalized, Self::Finalized) => Some(Ordering::Equal),
+            (Self::Safe, Self::Safe) => Some(Ordering::Equal),
+            (Self::Finalized, Self::Safe | Self::Latest) => Some(Ordering::Less),
+            (Self::Safe, Self::Latest) => Some(Ordering::Less),
+            (Self::Safe | Self::Latest, Self::Finalized) => Some(Ordering::Greater),
+            (Self::Latest, Self::Safe) => Some(Ordering::Greater),
             (Self::Subscribe, Self::Subscribe) => None,
             (_, Self::Subscribe) => Some(Ordering::Less),
             _ => None,
@@ -49,6 +98,11 @@ impl serde::Serialize for Bound {
             Self::FromLatest(n) => serializer.serialize_i64(-(*n as i64)),
             Self::Latest => serializer.serialize_str(Self::LATEST),
             Self::Subscribe => serializer.serialize_str(Self::NONE),
+            Self::Timestamp(_) | Self::FromLatestDuration(_) | Self::Finalized | Self::Safe => {
+                Err(serde::ser::Error::custom(format!(
+                    "bound `{self}` has to be resolved to a block height"
+                )))
+            }
         }
     }
 }
@@ -70,34 +124,8 @@ impl<'de> serde::Deserialize<'de> for Bound {
             where
                 E: serde::de::Error,
             {
-                let re = Regex::new(r#"^latest\s*-\s*(\d+)$"#).unwrap();
-                let from_latest = re.captures(v).and_then(|c| c.get(1).map(|m| m.as_str()));
-
-                match v {
-                    Bound::LATEST => return Ok(Bound::Latest),
-                    Bound::NONE => return Ok(Bound::Subscribe),
-                    _ => {}
-                }
-
-                if let Some(from_latest) = from_latest {
-                    if let Ok(from_latest) = from_latest.parse::<u64>() {
-                        if from_latest > 0 {
-                            return Ok(Bound::FromLatest(from_latest));
-                        }
-                    }
-                }
-
-                if let Ok(exact) = v.parse::<i64>() {
-                    return Ok(Bound::Exact(exact));
-                }
-
-                if let Ok(exact) = v.parse::<i64>() {
-                    if exact < 0 {
-                        return Ok(Bound::FromLatest(exact.unsigned_abs()));
-                    }
-                }
-
-                Err(E::invalid_value(serde::de::Unexpected::Str(v), &self))
+                v.parse()
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
             }
 
             fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
@@ -110,12 +138,99 @@ impl<'de> serde::Deserialize<'de> for Bound {
                     Ok(Bound::Exact(v))
                 }
             }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                i64::try_from(v)
+                    .map(Bound::Exact)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(v), &self))
+            }
         }
 
         deserializer.deserialize_any(Visitor)
     }
 }
 
+/// Parses the human-friendly bound notation also produced by [`Bound`]'s
+/// `Display` implementation
+///
+/// | input                           | bound                              |
+/// |---------------------------------|------------------------------------|
+/// | `17000000`                      | `Exact(17000000)`                  |
+/// | `latest`                        | `Latest`                           |
+/// | `latest-100`, `-100`            | `FromLatest(100)`                  |
+/// | `none`                          | `Subscribe`                        |
+/// | `now-6h`, `now-1h30m`           | `FromLatestDuration(..)`           |
+/// | `2024-01-01T00:00:00Z`, `@1704067200` | `Timestamp(1704067200)`      |
+/// | `finalized`, `safe`             | `Finalized`, `Safe`                |
+///
+/// Durations accept the units `w`, `d`, `h`, `m`, `s` and `ms`.
+impl FromStr for Bound {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidBound(s.to_string());
+        let v = s.trim();
+
+        match v {
+            Bound::LATEST | Bound::NOW => return Ok(Bound::Latest),
+            Bound::NONE => return Ok(Bound::Subscribe),
+            Bound::FINALIZED => return Ok(Bound::Finalized),
+            Bound::SAFE => return Ok(Bound::Safe),
+            _ => {}
+        }
+
+        if let Some(captures) = FROM_LATEST.captures(v) {
+            let offset = captures[2].trim();
+            return match &captures[1] {
+                Bound::LATEST => match offset.parse::<u64>() {
+                    Ok(from_latest) if from_latest > 0 => Ok(Bound::FromLatest(from_latest)),
+                    Ok(_) => Ok(Bound::Latest),
+                    Err(_) => Err(invalid()),
+                },
+                _ => parse_duration(offset)
+                    .map(Bound::FromLatestDuration)
+                    .ok_or_else(invalid),
+            };
+        }
+
+        if let Ok(exact) = v.parse::<i64>() {
+            if exact < 0 {
+                return Ok(Bound::FromLatest(exact.unsigned_abs()));
+            }
+            return Ok(Bound::Exact(exact));
+        }
+
+        if let Some(timestamp) = v.strip_prefix('@') {
+            return timestamp
+                .parse::<i64>()
+                .map(Bound::Timestamp)
+                .map_err(|_| invalid());
+        }
+
+        parse_rfc3339(v).map(Bound::Timestamp).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact(n) => write!(f, "{n}"),
+            Self::FromLatest(n) => write!(f, "{}-{n}", Self::LATEST),
+            Self::Latest => f.write_str(Self::LATEST),
+            Self::Subscribe => f.write_str(Self::NONE),
+            Self::Timestamp(timestamp) => f.write_str(&format_rfc3339(*timestamp)),
+            Self::FromLatestDuration(duration) => {
+                write!(f, "{}-{}", Self::NOW, format_duration(*duration))
+            }
+            Self::Finalized => f.write_str(Self::FINALIZED),
+            Self::Safe => f.write_str(Self::SAFE),
+        }
+    }
+}
+
 impl std::fmt::Debug for Bound {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -123,6 +238,142 @@ impl std::fmt::Debug for Bound {
             Self::FromLatest(n) => std::fmt::Debug::fmt(&(*n as i64).mul(-1), f),
             Self::Latest => f.write_str(Self::LATEST),
             Self::Subscribe => f.write_str(Self::NONE),
+            Self::Timestamp(_) | Self::FromLatestDuration(_) | Self::Finalized | Self::Safe => {
+                fmt::Display::fmt(self, f)
+            }
         }
     }
 }
+
+const DURATION_UNITS: [(&str, u64); 5] = [
+    ("w", 7 * 24 * 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("h", 60 * 60),
+    ("m", 60),
+    ("s", 1),
+];
+
+/// Parses durations like `6h`, `1h30m` or `500ms`
+fn parse_duration(s: &str) -> Option<Duration> {
+    if !DURATION.is_match(s) {
+        return None;
+    }
+
+    let total = DURATION_PART
+        .captures_iter(s)
+        .try_fold(Duration::ZERO, |total, captures| {
+            let value = captures[1].parse::<u64>().ok()?;
+            let duration = match &captures[2] {
+                "ms" => Duration::from_millis(value),
+                unit => {
+                    let (_, seconds) = DURATION_UNITS.iter().find(|(u, _)| *u == unit)?;
+                    Duration::from_secs(value.checked_mul(*seconds)?)
+                }
+            };
+            total.checked_add(duration)
+        });
+    total
+}
+
+/// Formats durations with the largest units first, e.g. `1h30m`
+fn format_duration(duration: Duration) -> String {
+    let mut seconds = duration.as_secs();
+    let millis = duration.subsec_millis();
+
+    let mut out = String::new();
+    for (unit, unit_seconds) in DURATION_UNITS {
+        if seconds >= unit_seconds {
+            out.push_str(&format!("{}{unit}", seconds / unit_seconds));
+            seconds %= unit_seconds;
+        }
+    }
+    if millis > 0 {
+        out.push_str(&format!("{millis}ms"));
+    }
+    if out.is_empty() {
+        out.push_str("0s");
+    }
+
+    out
+}
+
+/// Days since the unix epoch of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The proleptic Gregorian date of a number of days since the unix epoch
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Parses RFC 3339 date-times like `2024-01-01T00:00:00Z` or
+/// `2024-01-01T02:00:00.5+02:00`, and plain dates like `2024-01-01`, into a unix
+/// timestamp. Fractions of a second are truncated.
+fn parse_rfc3339(s: &str) -> Option<i64> {
+    let captures = RFC3339.captures(s)?;
+    let field = |i: usize| {
+        captures
+            .get(i)
+            .map_or(Some(0), |m| m.as_str().parse::<u32>().ok())
+    };
+
+    let (year, month, day) = (captures[1].parse::<i64>().ok()?, field(2)?, field(3)?);
+    let (hour, minute, second) = (field(4)?, field(5)?, field(6)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    // days past the end of the month, like 2024-02-31, roll over into the next
+    let days = days_from_civil(year, month, day);
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+
+    let offset = match captures.get(8) {
+        Some(sign) => {
+            let (hours, minutes) = (field(9)?, field(10)?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = (hours * 3600 + minutes * 60) as i64;
+            if sign.as_str() == "-" {
+                -offset
+            } else {
+                offset
+            }
+        }
+        None => 0,
+    };
+
+    Some(days * 86_400 + (hour * 3600 + minute * 60 + second) as i64 - offset)
+}
+
+/// Formats a unix timestamp as an RFC 3339 date-time in UTC
+fn format_rfc3339(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(86_400));
+    let seconds = timestamp.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
@@ -1,6 +1,9 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt, hash::Hash, marker::PhantomData};
 
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de::{self, DeserializeOwned, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use ethers_core::types::Address;
 
@@ -29,6 +32,99 @@ where
     serializer.serialize_none()
 }
 
+/// Serializes a set of Fuel ids like [`serialize_comma_separated`], each id as
+/// `0x` prefixed hex
+///
+/// The serde impls of the Fuel types write hex without the prefix.
+pub fn serialize_hex_comma_separated<S, T, I>(
+    value: T,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: IntoIterator<Item = I>,
+    I: fmt::LowerHex,
+{
+    let items = value
+        .into_iter()
+        .map(|item| format!("{item:#x}"))
+        .collect::<Vec<_>>();
+    serialize_comma_separated(items, serializer)
+}
+
+/// Deserializes a set written by [`serialize_comma_separated`], or a plain
+/// sequence
+///
+/// Every comma separated item is read as JSON if it is valid JSON, like a
+/// number, and as a string otherwise, like an address. A missing or `null`
+/// value is an empty set.
+pub fn deserialize_comma_separated<'de, D, T>(deserializer: D) -> Result<HashSet<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Eq + Hash,
+{
+    struct CommaSeparated<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for CommaSeparated<T>
+    where
+        T: DeserializeOwned + Eq + Hash,
+    {
+        type Value = HashSet<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a comma separated string or a sequence")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            v.split(',')
+                .filter(|item| !item.is_empty())
+                .map(|item| {
+                    serde_json::from_str(item)
+                        .or_else(|_| serde_json::from_value(item.into()))
+                        .map_err(|err| E::custom(format!("invalid item `{item}`: {err}")))
+                })
+                .collect()
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut set = HashSet::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(item) = seq.next_element()? {
+                set.insert(item);
+            }
+            Ok(set)
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(HashSet::new())
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(HashSet::new())
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    deserializer.deserialize_any(CommaSeparated(PhantomData))
+}
+
 pub fn deserialize_addresses<'de, D>(deserializer: D) -> Result<HashSet<Address>, D::Error>
 where
     D: Deserializer<'de>,
@@ -40,3 +136,30 @@ where
     }
     Ok(addresses)
 }
+
+/// Parses an unsigned integer written either in decimal or as `0x`-prefixed hex
+pub fn parse_u64(value: &str) -> Result<u64, std::num::ParseIntError> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+}
+
+/// Deserializes an unsigned integer sent as a JSON number, a decimal string or a
+/// `0x`-prefixed hex string
+pub fn deserialize_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Number(u64),
+        String(String),
+    }
+
+    match Repr::deserialize(deserializer)? {
+        Repr::Number(n) => Ok(n),
+        Repr::String(s) => parse_u64(&s).map_err(serde::de::Error::custom),
+    }
+}
@@ -5,6 +5,8 @@
 
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
+// `Error` carries websocket errors inline, boxing them would change its variants
+#![allow(clippy::result_large_err)]
 
 pub mod core;
 mod providers;
@@ -15,12 +17,21 @@ pub use ethers_core::types::Address;
 
 #[doc(inline)]
 pub use crate::core::{
+    abi,
     builder::ClientBuilder,
-    client::Client,
+    checkpoint,
+    client::{Client, ResolveBounds},
+    enrich,
     error::{Error, Result},
-    provider, requests,
-    types::{format::Format, query, ChainId},
+    multichain, plan, provider, requests, sink, state, sway,
+    types::{amount::Amount, format::Format, query, ChainId},
     utils,
 };
+#[cfg(feature = "cache")]
+#[doc(inline)]
+pub use crate::core::cache;
+#[cfg(feature = "datafusion")]
+#[doc(inline)]
+pub use crate::core::sql;
 #[doc(inline)]
 pub use crate::providers::{http::HttpProvider, ws::Operation, ws::WsProvider};
@@ -0,0 +1,87 @@
+use ethers_core::{
+    abi::Token,
+    types::{Address, H256, U256},
+};
+use pangea_client::abi::{topic0, EventDecoder};
+use serde_json::json;
+
+const TRANSFER: &str = "Transfer(address indexed from, address indexed to, uint256 value)";
+
+#[test]
+fn topic0_hashes_canonical_signature() {
+    let expected: H256 = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        .parse()
+        .unwrap();
+
+    assert_eq!(topic0(TRANSFER).unwrap(), expected);
+    assert_eq!(topic0(&format!("event {TRANSFER}")).unwrap(), expected);
+    assert_eq!(topic0("Transfer(address,address,uint256)").unwrap(), expected);
+}
+
+#[test]
+fn decoder_decodes_registered_events_only() {
+    let decoder = EventDecoder::from_signatures(&[TRANSFER]).unwrap();
+    let from = Address::repeat_byte(0x11);
+    let to = Address::repeat_byte(0x22);
+
+    let log = json!({
+        "block_number": 1,
+        "topic0": format!("{:?}", topic0(TRANSFER).unwrap()),
+        "topic1": format!("{:?}", H256::from(from)),
+        "topic2": format!("{:?}", H256::from(to)),
+        "data": format!("0x{:064x}", 1000),
+    });
+
+    let decoded = decoder.decode(log).unwrap().unwrap();
+    assert_eq!(decoded.name, "Transfer");
+    assert_eq!(
+        decoded.params,
+        vec![
+            Token::Address(from),
+            Token::Address(to),
+            Token::Uint(U256::from(1000)),
+        ]
+    );
+
+    let (_, _, value) = decoded.detokenize::<(Address, Address, U256)>().unwrap().params;
+    assert_eq!(value, U256::from(1000));
+
+    let unknown = json!({
+        "topic0": format!("{:?}", H256::repeat_byte(0xff)),
+        "data": "0x",
+    });
+    assert!(decoder.decode(unknown).unwrap().is_none());
+}
+
+#[test]
+fn decoder_tells_events_apart_by_their_indexed_parameters() {
+    let decoder = EventDecoder::from_signatures(&[
+        TRANSFER,
+        "Transfer(address indexed from, address indexed to, uint256 indexed tokenId)",
+    ])
+    .unwrap();
+    assert_eq!(decoder.topics0().len(), 1);
+    let from = Address::repeat_byte(0x11);
+    let to = Address::repeat_byte(0x22);
+
+    // an ERC-721 transfer, the token id is a topic
+    let log = json!({
+        "topic0": format!("{:?}", topic0(TRANSFER).unwrap()),
+        "topic1": format!("{:?}", H256::from(from)),
+        "topic2": format!("{:?}", H256::from(to)),
+        "topic3": format!("0x{:064x}", 7),
+        "data": "0x",
+    });
+    let decoded = decoder.decode(log).unwrap().unwrap();
+    assert_eq!(decoded.params[2], Token::Uint(U256::from(7)));
+
+    // an ERC-20 one, the value is in the data
+    let log = json!({
+        "topic0": format!("{:?}", topic0(TRANSFER).unwrap()),
+        "topic1": format!("{:?}", H256::from(from)),
+        "topic2": format!("{:?}", H256::from(to)),
+        "data": format!("0x{:064x}", 1000),
+    });
+    let decoded = decoder.decode(log).unwrap().unwrap();
+    assert_eq!(decoded.params[2], Token::Uint(U256::from(1000)));
+}
@@ -0,0 +1,80 @@
+use ethers_core::types::U256;
+use pangea_client::{
+    core::types::amount::MAX_DECIMALS, requests::erc20::GetErc20TransferssRequest, Amount,
+    Error,
+};
+use serde_json::json;
+
+#[test]
+fn amount_parses_and_formats_exactly() {
+    let amount = Amount::parse_units("1234.000000000000000001", 18).unwrap();
+    assert_eq!(
+        amount.raw(),
+        U256::from_dec_str("1234000000000000000001").unwrap()
+    );
+    assert_eq!(amount.decimals(), 18);
+    assert_eq!(amount.to_string(), "1234.000000000000000001");
+
+    assert_eq!(
+        Amount::new(U256::from(1_500_000), 6).unwrap().to_string(),
+        "1.5"
+    );
+    assert_eq!(
+        Amount::new(U256::from(5), 6).unwrap().to_string(),
+        "0.000005"
+    );
+    assert_eq!(Amount::from(42).to_string(), "42");
+    assert_eq!("0x10".parse::<Amount>().unwrap(), Amount::from(16));
+
+    assert!(Amount::parse_units("0.0000001", 6).is_err());
+    assert!("-1".parse::<Amount>().is_err());
+    assert!("1e18".parse::<Amount>().is_err());
+}
+
+#[test]
+fn amount_compares_by_value() {
+    let a = Amount::new(U256::from(1_500_000), 6).unwrap();
+    let b = Amount::new(U256::from(150), 2).unwrap();
+    assert_eq!(a, b);
+    assert!(Amount::parse_units("1.4", 18).unwrap() < b);
+    assert!(Amount::from(2) > a);
+    assert_eq!(a.rescale(18).unwrap().raw(), U256::exp10(17) * 15);
+}
+
+#[test]
+fn amount_filters_serialize_without_precision_loss() {
+    let request = GetErc20TransferssRequest {
+        value__gte: Some(Amount::parse_units("123456789.123456789123456789", 18).unwrap()),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["value__gte"], "123456789.123456789123456789");
+
+    let amount: Amount = serde_json::from_value(json["value__gte"].clone()).unwrap();
+    assert_eq!(
+        amount.rescale(18).unwrap().raw(),
+        U256::from_dec_str("123456789123456789123456789").unwrap()
+    );
+}
+
+#[test]
+fn amounts_serialize_as_exact_strings() {
+    let amount = Amount::parse_units("1.5", 18).unwrap();
+    assert_eq!(serde_json::to_value(amount).unwrap(), json!("1.5"));
+    // past the precision of an `f64`
+    assert_eq!(
+        serde_json::to_value(Amount::new(U256::exp10(20) + 1, 2).unwrap()).unwrap(),
+        json!("1000000000000000000.01")
+    );
+    assert_eq!(serde_json::to_value(Amount::from(7)).unwrap(), json!("7"));
+}
+
+#[test]
+fn too_many_decimals_are_an_error() {
+    assert!(Amount::new(U256::one(), MAX_DECIMALS).is_ok());
+    assert!(matches!(
+        Amount::new(U256::one(), MAX_DECIMALS + 1),
+        Err(Error::InvalidAmount(_))
+    ));
+}
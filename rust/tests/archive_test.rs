@@ -0,0 +1,143 @@
+#![cfg(feature = "archive")]
+#![allow(clippy::result_large_err)]
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use pangea_client::{
+    core::stream::json_lines,
+    provider::ResponseStream,
+    sink::{ArchiveReader, ArchiveSink, Compression, Rotation},
+    ChainId, Error,
+};
+use serde_json::json;
+
+fn lines() -> Vec<Vec<u8>> {
+    (0..5)
+        .map(|i| {
+            json!({ "chain": 1, "block_number": 10 + i / 2, "log_index": i % 2 })
+                .to_string()
+                .into_bytes()
+        })
+        .collect()
+}
+
+/// The lines delivered in chunks of 7 bytes, cutting across lines
+fn response() -> ResponseStream<Vec<u8>> {
+    let bytes = lines()
+        .into_iter()
+        .flat_map(|mut line| {
+            line.push(b'\n');
+            line
+        })
+        .collect::<Vec<_>>();
+    let chunks = bytes
+        .chunks(7)
+        .map(|chunk| Ok(chunk.to_vec()))
+        .collect::<Vec<_>>();
+    futures::stream::iter(chunks).boxed()
+}
+
+#[tokio::test]
+async fn tee_passes_the_response_on_and_replays_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let sink = ArchiveSink::new(dir.path(), ChainId::ETH, "logs")
+        .with_rotation(Rotation::default().rows(2))
+        .with_request(&json!({ "chains": "ETH" }))
+        .unwrap();
+
+    let passed = sink.tee(response()).map(Result::unwrap).concat().await;
+    let expected = response().map(Result::unwrap).concat().await;
+    assert_eq!(passed, expected);
+
+    let reader = ArchiveReader::open(dir.path().join("ETH").join("logs")).unwrap();
+    let files = reader.files();
+    assert_eq!(
+        files.iter().map(|file| file.range).collect::<Vec<_>>(),
+        [Some((10, 10)), Some((11, 11)), Some((12, 12))]
+    );
+    assert_eq!(files[0].name, "000000000010-000000000010.ndjson.gz");
+    assert!(files.iter().all(|file| file.sha256.len() == 64));
+    assert_eq!(files[0].request, Some(json!({ "chains": "ETH" })));
+
+    let replayed = json_lines(reader.stream())
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(replayed, lines());
+}
+
+#[tokio::test]
+async fn later_runs_extend_the_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    for _ in 0..2 {
+        ArchiveSink::new(dir.path(), ChainId::ETH, "logs")
+            .with_compression(Compression::Zstd)
+            .write_stream(response())
+            .await
+            .unwrap();
+    }
+
+    let reader = ArchiveReader::open(dir.path().join("ETH").join("logs")).unwrap();
+    let names = reader
+        .files()
+        .iter()
+        .map(|file| file.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "000000000010-000000000012.ndjson.zst",
+            "000000000010-000000000012-1.ndjson.zst",
+        ]
+    );
+
+    let replayed = json_lines(reader.stream())
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(replayed, [lines(), lines()].concat());
+}
+
+#[tokio::test]
+async fn corrupted_files_are_not_replayed() {
+    let dir = tempfile::tempdir().unwrap();
+    let files = ArchiveSink::new(dir.path(), ChainId::ETH, "logs")
+        .write_stream(response())
+        .await
+        .unwrap();
+    std::fs::write(&files[0], b"not gzip").unwrap();
+
+    let reader = ArchiveReader::open(dir.path().join("ETH").join("logs")).unwrap();
+    let replayed = reader.stream().collect::<Vec<_>>().await;
+    assert!(matches!(replayed[..], [Err(Error::ArchiveChecksum(_))]));
+}
+
+#[tokio::test]
+async fn tee_closes_old_files_while_the_response_is_quiet() {
+    let dir = tempfile::tempdir().unwrap();
+    let sink = ArchiveSink::new(dir.path(), ChainId::ETH, "logs")
+        .with_rotation(Rotation::default().age(Duration::from_millis(50)));
+
+    // two lines of block 10, then nothing for longer than the age of a file
+    let line = |block: u64| -> pangea_client::Result<Vec<u8>> {
+        Ok(format!("{}\n", json!({ "block_number": block })).into_bytes())
+    };
+    let response = futures::stream::iter([line(10), line(10)])
+        .chain(futures::stream::once(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            line(11)
+        }))
+        .boxed();
+    sink.tee(response).map(Result::unwrap).count().await;
+
+    let reader = ArchiveReader::open(dir.path().join("ETH").join("logs")).unwrap();
+    assert_eq!(
+        reader
+            .files()
+            .iter()
+            .map(|file| (file.range, file.records))
+            .collect::<Vec<_>>(),
+        [(Some((10, 10)), 2), (Some((11, 11)), 1)]
+    );
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use std::collections::HashSet;
+
+use ethers_core::types::{Address, H256};
+use futures::StreamExt;
+use pangea_client::{
+    provider::{ChainProvider, FuelProvider},
+    requests::{
+        fuel::GetFuelLogsRequest, logs::GetLogsRequest, uniswap_v2::GetPairsRequest, Batch,
+    },
+    Error, Format,
+};
+use serde_json::{json, Value};
+
+use common::Fake;
+
+#[test]
+fn large_sets_are_split_into_disjoint_batches() {
+    let addresses = (0..1050u64).map(Address::from_low_u64_be).collect::<HashSet<_>>();
+    let request = GetLogsRequest {
+        address__in: addresses.clone(),
+        topic0__in: HashSet::from([H256::repeat_byte(1)]),
+        ..Default::default()
+    };
+
+    let batches = request.split(500).unwrap();
+    assert_eq!(batches.len(), 3);
+
+    let mut seen = HashSet::new();
+    for batch in &batches {
+        assert!(batch.address__in.len() <= 500);
+        assert_eq!(batch.topic0__in, HashSet::from([H256::repeat_byte(1)]));
+        assert!(batch.address__in.iter().all(|address| seen.insert(*address)));
+    }
+    assert_eq!(seen, addresses);
+}
+
+#[test]
+fn small_sets_and_zero_size_are_not_split() {
+    let request = GetLogsRequest {
+        address__in: (0..10u64).map(Address::from_low_u64_be).collect(),
+        ..Default::default()
+    };
+
+    assert_eq!(request.clone().split(10).unwrap().len(), 1);
+    assert_eq!(request.split(0).unwrap().len(), 1);
+}
+
+#[test]
+fn sets_matching_several_values_of_a_record_are_not_split() {
+    let addresses = (0..10u64).map(Address::from_low_u64_be).collect::<HashSet<_>>();
+
+    let request = GetLogsRequest {
+        address__not_in: addresses.clone(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        request.split(4),
+        Err(Error::UnsplittableFilter("address__not_in", 10, 4))
+    ));
+
+    let request = GetPairsRequest {
+        tokens__in: addresses.clone(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        request.clone().split(4),
+        Err(Error::UnsplittableFilter("tokens__in", 10, 4))
+    ));
+    assert_eq!(request.split(10).unwrap().len(), 1);
+}
+
+#[test]
+fn every_request_with_sets_is_split() {
+    let request = GetPairsRequest {
+        pair_address__in: (0..10u64).map(Address::from_low_u64_be).collect(),
+        token0__in: (0..3u64).map(Address::from_low_u64_be).collect(),
+        ..Default::default()
+    };
+
+    let batches = request.split(2).unwrap();
+    assert_eq!(batches.len(), 10);
+    assert!(batches
+        .iter()
+        .all(|batch| batch.pair_address__in.len() <= 2 && batch.token0__in.len() <= 2));
+}
+
+#[tokio::test]
+async fn requests_needing_batches_are_only_sent_as_json_streams() {
+    let fake = Fake::new(|_, _| vec![]);
+    let requests = fake.requests.clone();
+    let client = fake.client().with_batch_size(4);
+
+    let request = GetFuelLogsRequest {
+        ra__in: (0..10).collect(),
+        ..Default::default()
+    };
+    let result = client
+        .get_fuel_logs_by_format(request.clone(), Format::Arrow, false)
+        .await;
+    assert!(matches!(result, Err(Error::UnbatchableFormat(3))));
+    assert!(requests.lock().unwrap().is_empty());
+
+    client
+        .get_fuel_logs_by_format(request, Format::JsonStream, false)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn batches_are_merged_in_block_order() {
+    // every address logs once, in the block of its number
+    let fake = Fake::new(|_, request| {
+        let mut blocks = request["address__in"]
+            .as_str()
+            .unwrap()
+            .split(',')
+            .map(|address| u64::from_str_radix(&address[26..], 16).unwrap())
+            .collect::<Vec<_>>();
+        blocks.sort();
+        blocks
+            .into_iter()
+            .map(|block_number| json!({ "chain": "ETH", "block_number": block_number, "log_index": 0 }))
+            .collect()
+    });
+    let requests = fake.requests.clone();
+    let client = fake.client().with_batch_size(4);
+
+    let request = GetLogsRequest {
+        address__in: (0..10u64).map(Address::from_low_u64_be).collect(),
+        ..Default::default()
+    };
+    let lines = client
+        .get_logs_by_format(request, Format::JsonStream, false)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(requests.lock().unwrap().len(), 3);
+    let blocks = lines
+        .iter()
+        .map(|line| serde_json::from_slice::<Value>(line).unwrap()["block_number"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(blocks, (0..10).map(Value::from).collect::<Vec<_>>());
+}
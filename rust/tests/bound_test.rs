@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use pangea_client::query::Bound;
+
+#[test]
+fn bound_parses_human_friendly_notation() {
+    let cases = [
+        ("17000000", Bound::Exact(17_000_000)),
+        ("latest", Bound::Latest),
+        ("latest-100", Bound::FromLatest(100)),
+        ("latest - 100", Bound::FromLatest(100)),
+        ("-100", Bound::FromLatest(100)),
+        ("none", Bound::Subscribe),
+        (
+            "now-6h",
+            Bound::FromLatestDuration(Duration::from_secs(6 * 3600)),
+        ),
+        (
+            "now-1h30m",
+            Bound::FromLatestDuration(Duration::from_secs(5400)),
+        ),
+        (
+            "now-500ms",
+            Bound::FromLatestDuration(Duration::from_millis(500)),
+        ),
+        ("2024-01-01T00:00:00Z", Bound::Timestamp(1_704_067_200)),
+        ("2024-01-01T02:00:00+02:00", Bound::Timestamp(1_704_067_200)),
+        ("2024-01-01", Bound::Timestamp(1_704_067_200)),
+        ("@1704067200", Bound::Timestamp(1_704_067_200)),
+        ("finalized", Bound::Finalized),
+        ("safe", Bound::Safe),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<Bound>().unwrap(), expected, "{input}");
+    }
+}
+
+#[test]
+fn bound_rejects_malformed_notation() {
+    for input in ["", "latest-", "now-6x", "now-h", "2024-13-01", "yesterday"] {
+        assert!(input.parse::<Bound>().is_err(), "{input}");
+    }
+}
+
+#[test]
+fn bound_rejects_impossible_dates() {
+    for input in [
+        "2024-02-30",
+        "2024-02-31T00:00:00Z",
+        "2023-02-29",
+        "2024-04-31",
+        "2024-01-01T00:00:00+24:00",
+    ] {
+        assert!(input.parse::<Bound>().is_err(), "{input}");
+    }
+
+    // leap days exist in leap years only
+    assert_eq!(
+        "2024-02-29".parse::<Bound>().unwrap(),
+        Bound::Timestamp(1_709_164_800)
+    );
+    assert_eq!(
+        "2000-02-29".parse::<Bound>().unwrap(),
+        Bound::Timestamp(951_782_400)
+    );
+}
+
+#[test]
+fn bound_display_round_trips() {
+    let bounds = [
+        Bound::Exact(17_000_000),
+        Bound::Latest,
+        Bound::FromLatest(100),
+        Bound::Subscribe,
+        Bound::FromLatestDuration(Duration::from_secs(6 * 3600)),
+        Bound::FromLatestDuration(Duration::from_millis(93_784_005)),
+        Bound::Timestamp(1_704_067_200),
+        Bound::Timestamp(-86_401),
+        Bound::Finalized,
+        Bound::Safe,
+    ];
+
+    for bound in bounds {
+        assert_eq!(
+            bound.to_string().parse::<Bound>().unwrap(),
+            bound,
+            "{bound}"
+        );
+    }
+
+    assert_eq!(
+        Bound::Timestamp(1_704_067_200).to_string(),
+        "2024-01-01T00:00:00Z"
+    );
+    assert_eq!(
+        Bound::FromLatestDuration(Duration::from_secs(5400)).to_string(),
+        "now-1h30m"
+    );
+}
+
+#[test]
+fn bound_json_round_trips() {
+    for bound in [
+        Bound::Exact(17_000_000),
+        Bound::Latest,
+        Bound::FromLatest(100),
+        Bound::Subscribe,
+    ] {
+        let json = serde_json::to_string(&bound).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Bound>(&json).unwrap(),
+            bound,
+            "{json}"
+        );
+    }
+
+    assert!(serde_json::to_string(&Bound::Finalized).is_err());
+}
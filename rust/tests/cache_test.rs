@@ -0,0 +1,305 @@
+#![cfg(feature = "cache")]
+#![allow(clippy::result_large_err)]
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use pangea_client::{
+    cache::CacheProvider,
+    core::stream::json_lines,
+    provider::{BtcProvider, ChainProvider, FuelProvider, StreamResponse},
+    query::Bound,
+    requests::{blocks, btc, fuel, logs, mira, transfers, txs, BlockRange},
+    ChainId, Error, Format,
+};
+use serde_json::{json, Value};
+
+const HEAD: i64 = 1000;
+
+/// Serves one log per block and records the ranges it was asked for
+#[derive(Default)]
+struct Fake {
+    requested: Arc<Mutex<Vec<(Bound, Bound)>>>,
+    /// Ends the next logs response with a server error
+    fail: Arc<AtomicBool>,
+}
+
+fn response(lines: Vec<Value>) -> StreamResponse<Vec<u8>> {
+    let chunks = lines
+        .into_iter()
+        .map(|line| Ok(format!("{line}\n").into_bytes()))
+        .collect::<Vec<_>>();
+    Ok(futures::stream::iter(chunks).boxed())
+}
+
+/// Implements the endpoints the cache does not use for `Fake`
+macro_rules! unused {
+    ($provider:ident { $($method:ident($request:ty)),* $(,)? }) => {
+        #[async_trait]
+        impl $provider for Fake {
+            $(
+                async fn $method(
+                    &self,
+                    _: $request,
+                    _: Format,
+                    _: bool,
+                ) -> StreamResponse<Vec<u8>> {
+                    unimplemented!()
+                }
+            )*
+        }
+    };
+}
+
+#[async_trait]
+impl ChainProvider for Fake {
+    async fn get_blocks_by_format(
+        &self,
+        _: blocks::GetBlocksRequest,
+        _: Format,
+        _: bool,
+    ) -> StreamResponse<Vec<u8>> {
+        response(vec![json!({ "chain": 1, "block_number": HEAD })])
+    }
+
+    async fn get_logs_by_format(
+        &self,
+        request: logs::GetLogsRequest,
+        _: Format,
+        _: bool,
+    ) -> StreamResponse<Vec<u8>> {
+        self.requested.lock().unwrap().push(request.block_range());
+        let Bound::Exact(from) = request.from_block else {
+            unimplemented!()
+        };
+        let to = match request.to_block {
+            Bound::Exact(to) => to,
+            _ => HEAD + 1,
+        };
+        let mut lines = (from..to)
+            .map(|block| json!({ "chain": 1, "block_number": block, "log_index": 0 }))
+            .collect::<Vec<_>>();
+        if self.fail.swap(false, Ordering::SeqCst) {
+            lines.push(json!({ "status": 500, "error": "internal error" }));
+        }
+        response(lines)
+    }
+
+    async fn get_txs_by_format(
+        &self,
+        _: txs::GetTxsRequest,
+        _: Format,
+        _: bool,
+    ) -> StreamResponse<Vec<u8>> {
+        unimplemented!()
+    }
+
+    async fn get_transfers_by_format(
+        &self,
+        _: transfers::GetTransfersRequest,
+        _: Format,
+        _: bool,
+    ) -> StreamResponse<Vec<u8>> {
+        unimplemented!()
+    }
+}
+
+unused!(FuelProvider {
+    get_fuel_blocks_by_format(fuel::GetFuelBlocksRequest),
+    get_fuel_logs_by_format(fuel::GetFuelLogsRequest),
+    get_fuel_logs_decoded_by_format(fuel::GetFuelLogsRequest),
+    get_fuel_txs_by_format(fuel::GetFuelTxsRequest),
+    get_fuel_receipts_by_format(fuel::GetFuelReceiptsRequest),
+    get_fuel_messages_by_format(fuel::GetFuelMessagesRequest),
+    get_fuel_unspent_utxos_by_format(fuel::GetUtxoRequest),
+    get_fuel_spark_markets_by_format(fuel::GetSparkMarketRequest),
+    get_fuel_spark_orders_by_format(fuel::GetSparkOrderRequest),
+    get_fuel_src20_by_format(fuel::GetSrc20),
+    get_fuel_src7_by_format(fuel::GetSrc7),
+    get_fuel_mira_v1_pools_by_format(mira::GetMiraPoolsRequest),
+    get_fuel_mira_v1_liquidity_by_format(mira::GetMiraLiquidityRequest),
+    get_fuel_mira_v1_swaps_by_format(mira::GetMiraSwapsRequest),
+});
+
+unused!(BtcProvider {
+    get_btc_blocks_by_format(btc::GetBtcBlocksRequest),
+    get_btc_txs_by_format(btc::GetBtcTxsRequest),
+});
+
+fn request(from: Bound, to: Bound) -> logs::GetLogsRequest {
+    logs::GetLogsRequest {
+        chains: HashSet::from([ChainId::ETH]),
+        from_block: from,
+        to_block: to,
+        ..Default::default()
+    }
+}
+
+async fn delivered(cache: &CacheProvider<Fake>, request: logs::GetLogsRequest) -> Vec<i64> {
+    let response = cache
+        .get_logs_by_format(request, Format::JsonStream, false)
+        .await
+        .unwrap();
+    json_lines(response)
+        .map(|line| {
+            let record = serde_json::from_slice::<Value>(&line.unwrap()).unwrap();
+            record["block_number"].as_i64().unwrap()
+        })
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn only_missing_ranges_are_fetched() {
+    let dir = tempfile::tempdir().unwrap();
+    let fake = Fake::default();
+    let requested = fake.requested.clone();
+    let cache = CacheProvider::new(fake, dir.path()).with_margin(10);
+
+    let first = delivered(&cache, request(Bound::Exact(0), Bound::Exact(100))).await;
+    assert_eq!(first, (0..100).collect::<Vec<_>>());
+
+    let second = delivered(&cache, request(Bound::Exact(50), Bound::Exact(150))).await;
+    assert_eq!(second, (50..150).collect::<Vec<_>>());
+
+    let third = delivered(&cache, request(Bound::Exact(20), Bound::Exact(120))).await;
+    assert_eq!(third, (20..120).collect::<Vec<_>>());
+
+    assert_eq!(
+        *requested.lock().unwrap(),
+        [
+            (Bound::Exact(0), Bound::Exact(100)),
+            (Bound::Exact(100), Bound::Exact(150)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn blocks_near_the_head_are_never_cached() {
+    let dir = tempfile::tempdir().unwrap();
+    let fake = Fake::default();
+    let requested = fake.requested.clone();
+    let cache = CacheProvider::new(fake, dir.path()).with_margin(10);
+
+    for _ in 0..2 {
+        let records = delivered(&cache, request(Bound::Exact(980), Bound::Latest)).await;
+        assert_eq!(records, (980..=HEAD).collect::<Vec<_>>());
+    }
+
+    // 980..991 is final and fetched once, the rest every time
+    assert_eq!(
+        *requested.lock().unwrap(),
+        [
+            (Bound::Exact(980), Bound::Exact(991)),
+            (Bound::Exact(991), Bound::Latest),
+            (Bound::Exact(991), Bound::Latest),
+        ]
+    );
+
+    let requested_before = requested.lock().unwrap().len();
+    delivered(&cache, request(Bound::Exact(995), Bound::Exact(998))).await;
+    assert_eq!(requested.lock().unwrap().len(), requested_before + 1);
+}
+
+#[tokio::test]
+async fn gaps_are_fetched_when_the_stream_reaches_them() {
+    let dir = tempfile::tempdir().unwrap();
+    let fake = Fake::default();
+    let requested = fake.requested.clone();
+    let cache = CacheProvider::new(fake, dir.path()).with_margin(10);
+    delivered(&cache, request(Bound::Exact(50), Bound::Exact(100))).await;
+    requested.lock().unwrap().clear();
+
+    let mut lines = json_lines(
+        cache
+            .get_logs_by_format(
+                request(Bound::Exact(0), Bound::Exact(150)),
+                Format::JsonStream,
+                false,
+            )
+            .await
+            .unwrap(),
+    );
+    assert!(requested.lock().unwrap().is_empty());
+
+    for _ in 0..100 {
+        lines.next().await.unwrap().unwrap();
+    }
+    assert_eq!(
+        *requested.lock().unwrap(),
+        [(Bound::Exact(0), Bound::Exact(50))]
+    );
+
+    assert_eq!(lines.count().await, 50);
+    assert_eq!(requested.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn dropped_responses_leave_no_temporary_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CacheProvider::new(Fake::default(), dir.path()).with_margin(10);
+
+    let mut lines = json_lines(
+        cache
+            .get_logs_by_format(
+                request(Bound::Exact(0), Bound::Exact(100)),
+                Format::JsonStream,
+                false,
+            )
+            .await
+            .unwrap(),
+    );
+    lines.next().await.unwrap().unwrap();
+    drop(lines);
+
+    let mut stored = Vec::new();
+    let mut dirs = vec![dir.path().to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                stored.push(path.file_name().unwrap().to_string_lossy().into_owned());
+            }
+        }
+    }
+    assert_eq!(stored, ["request.json"]);
+}
+
+#[tokio::test]
+async fn responses_ending_in_an_error_are_not_cached() {
+    let dir = tempfile::tempdir().unwrap();
+    let fake = Fake::default();
+    let requested = fake.requested.clone();
+    fake.fail.store(true, Ordering::SeqCst);
+    let cache = CacheProvider::new(fake, dir.path()).with_margin(10);
+
+    let lines = json_lines(
+        cache
+            .get_logs_by_format(
+                request(Bound::Exact(0), Bound::Exact(100)),
+                Format::JsonStream,
+                false,
+            )
+            .await
+            .unwrap(),
+    )
+    .collect::<Vec<_>>()
+    .await;
+    assert_eq!(lines.len(), 101);
+    assert!(matches!(lines[100], Err(Error::ErrorResponse(_))));
+
+    for _ in 0..2 {
+        let records = delivered(&cache, request(Bound::Exact(0), Bound::Exact(100))).await;
+        assert_eq!(records, (0..100).collect::<Vec<_>>());
+    }
+    assert_eq!(requested.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,50 @@
+use num_traits::FromPrimitive;
+use pangea_client::ChainId;
+
+#[test]
+fn unknown_chain_ids_round_trip() {
+    let chain: ChainId = serde_json::from_value(serde_json::json!(8453)).unwrap();
+    assert_eq!(chain, ChainId::Other(8453));
+    assert_eq!(chain.chain_code(), "8453");
+
+    let json = serde_json::to_value(chain).unwrap();
+    assert_eq!(serde_json::from_value::<ChainId>(json).unwrap(), chain);
+
+    assert_eq!(ChainId::from_id(1), ChainId::ETH);
+    assert_eq!("1".parse::<ChainId>().unwrap(), ChainId::ETH);
+    assert!("NOPE".parse::<ChainId>().is_err());
+}
+
+#[test]
+fn registered_chains_use_their_code_and_name() {
+    let chain = ChainId::register(7_000_001, "TESTCHAIN", "Test Chain").unwrap();
+    assert_eq!(chain, ChainId::Other(7_000_001));
+    assert_eq!(chain.chain_code(), "TESTCHAIN");
+    assert_eq!(chain.chain_name(), "Test Chain");
+    assert_eq!("TESTCHAIN".parse::<ChainId>().unwrap(), chain);
+    assert_eq!(serde_json::to_value(chain).unwrap(), "TESTCHAIN");
+
+    assert!(ChainId::register(1, "MAINNET", "Mainnet").is_err());
+    assert!(ChainId::register(7_000_002, "ETH", "Not Ethereum").is_err());
+}
+
+#[test]
+fn mevm_is_canonical_and_move_is_an_alias() {
+    assert_eq!(ChainId::MEVM.chain_code(), "MEVM");
+    assert_eq!(ChainId::MEVM.to_string(), "MEVM");
+    assert_eq!("MEVM".parse::<ChainId>().unwrap(), ChainId::MEVM);
+    assert_eq!("MOVE".parse::<ChainId>().unwrap(), ChainId::MEVM);
+}
+
+#[test]
+fn chains_keep_their_strum_names_and_numeric_ids() {
+    assert_eq!(ChainId::ETH.as_ref(), "ETH");
+    assert_eq!(ChainId::SEPOLIA.as_ref(), "SEPETH");
+    assert_eq!(ChainId::Any.as_ref(), "none");
+    assert_eq!(ChainId::Other(8453).as_ref(), "Other");
+
+    assert_eq!(ChainId::from_i32(10), Some(ChainId::OPT));
+    assert_eq!(ChainId::from_u64(8453), Some(ChainId::Other(8453)));
+    assert_eq!(ChainId::from_i64(-1), None);
+    assert_eq!(ChainId::BTC.id(), 2_147_483_647);
+}
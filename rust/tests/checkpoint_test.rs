@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use pangea_client::{
+    checkpoint::{CheckpointStore, FileCheckpointStore, Job},
+    query::Bound,
+    requests::{logs::GetLogsRequest, BlockRange},
+};
+
+#[tokio::test]
+async fn file_store_persists_checkpoints_per_job() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("checkpoints.json");
+
+    let store = FileCheckpointStore::new(&path);
+    assert_eq!(store.load("logs").await.unwrap(), None);
+
+    store.commit("logs", 100).await.unwrap();
+    store.commit("transfers", 7).await.unwrap();
+    store.commit("logs", 101).await.unwrap();
+
+    let reopened = FileCheckpointStore::new(&path);
+    assert_eq!(reopened.load("logs").await.unwrap(), Some(101));
+    assert_eq!(reopened.load("transfers").await.unwrap(), Some(7));
+}
+
+#[tokio::test]
+async fn job_resumes_after_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(FileCheckpointStore::new(
+        dir.path().join("checkpoints.json"),
+    ));
+    let job = Job::new("logs", store);
+
+    let request = GetLogsRequest {
+        from_block: Bound::Exact(10),
+        ..Default::default()
+    };
+
+    let resumed = job.resume(request.clone()).await.unwrap();
+    assert_eq!(resumed.block_range().0, Bound::Exact(10));
+
+    job.ack(41).await.unwrap();
+    let resumed = job.resume(request).await.unwrap();
+    assert_eq!(resumed.block_range().0, Bound::Exact(42));
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_store_persists_checkpoints_per_job() {
+    use pangea_client::checkpoint::SqliteCheckpointStore;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("checkpoints.db");
+
+    let store = SqliteCheckpointStore::open(&path).unwrap();
+    assert_eq!(store.load("logs").await.unwrap(), None);
+    store.commit("logs", 100).await.unwrap();
+    store.commit("logs", 101).await.unwrap();
+    drop(store);
+
+    let reopened = SqliteCheckpointStore::open(&path).unwrap();
+    assert_eq!(reopened.load("logs").await.unwrap(), Some(101));
+    assert_eq!(reopened.load("transfers").await.unwrap(), None);
+}
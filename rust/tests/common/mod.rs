@@ -0,0 +1,132 @@
+//! A provider answering requests with canned JSON records, to test the client
+//! offline
+
+#![allow(dead_code, clippy::result_large_err)]
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use pangea_client::{
+    provider::{ChainProvider, FuelProvider, Provider, StreamResponse, UniswapV2Provider},
+    requests::{blocks, fuel, logs, mira, transfers, txs, uniswap_v2},
+    Client, Format, Result,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+type Handler = dyn Fn(&str, &Value) -> Vec<Value> + Send + Sync;
+
+/// Answers every request with the records `handler` returns for the name of
+/// its endpoint and the request serialized to JSON
+pub struct Fake {
+    handler: Box<Handler>,
+    /// Every request sent, with the name of its endpoint
+    pub requests: Arc<Mutex<Vec<(&'static str, Value)>>>,
+    /// The number of records polled from the responses of every endpoint
+    pub polled: Arc<Mutex<HashMap<&'static str, usize>>>,
+}
+
+impl Fake {
+    pub fn new(handler: impl Fn(&str, &Value) -> Vec<Value> + Send + Sync + 'static) -> Self {
+        Self {
+            handler: Box::new(handler),
+            requests: Arc::default(),
+            polled: Arc::default(),
+        }
+    }
+
+    pub fn client(self) -> Client<Self> {
+        Client::new(self)
+    }
+
+    fn respond(
+        &self,
+        endpoint: &'static str,
+        request: impl Serialize,
+    ) -> StreamResponse<Vec<u8>> {
+        let request = serde_json::to_value(request)?;
+        let records = (self.handler)(endpoint, &request);
+        self.requests.lock().unwrap().push((endpoint, request));
+
+        let polled = self.polled.clone();
+        let lines = futures::stream::iter(records).map(move |record| {
+            *polled.lock().unwrap().entry(endpoint).or_default() += 1;
+            Ok(format!("{record}\n").into_bytes())
+        });
+        Ok(lines.boxed())
+    }
+}
+
+/// The block number a request starts at, zero for other bounds
+pub fn from_block(request: &Value) -> u64 {
+    request["from_block"].as_u64().unwrap_or_default()
+}
+
+/// Whether a request follows the chain live
+pub fn is_live(request: &Value) -> bool {
+    request["to_block"] == "none"
+}
+
+#[async_trait]
+impl Provider for Fake {
+    async fn try_new(_: String, _: bool, _: Option<String>, _: Option<String>) -> Result<Self> {
+        unimplemented!()
+    }
+
+    async fn get_status_by_format(&self, _: Format) -> StreamResponse<Vec<u8>> {
+        unimplemented!()
+    }
+}
+
+/// Implements the endpoints of `$provider` for `Fake`, named after the
+/// method without its `get_` prefix and `_by_format` suffix
+macro_rules! endpoints {
+    ($provider:ident { $($method:ident($request:ty) => $endpoint:literal),* $(,)? }) => {
+        #[async_trait]
+        impl $provider for Fake {
+            $(
+                async fn $method(
+                    &self,
+                    request: $request,
+                    _: Format,
+                    _: bool,
+                ) -> StreamResponse<Vec<u8>> {
+                    self.respond($endpoint, request)
+                }
+            )*
+        }
+    };
+}
+
+endpoints!(ChainProvider {
+    get_blocks_by_format(blocks::GetBlocksRequest) => "blocks",
+    get_logs_by_format(logs::GetLogsRequest) => "logs",
+    get_txs_by_format(txs::GetTxsRequest) => "txs",
+    get_transfers_by_format(transfers::GetTransfersRequest) => "transfers",
+});
+
+endpoints!(UniswapV2Provider {
+    get_pairs_by_format(uniswap_v2::GetPairsRequest) => "uniswap_v2_pairs",
+    get_prices_by_format(uniswap_v2::GetPricesRequest) => "uniswap_v2_prices",
+});
+
+endpoints!(FuelProvider {
+    get_fuel_blocks_by_format(fuel::GetFuelBlocksRequest) => "fuel_blocks",
+    get_fuel_logs_by_format(fuel::GetFuelLogsRequest) => "fuel_logs",
+    get_fuel_logs_decoded_by_format(fuel::GetFuelLogsRequest) => "fuel_logs_decoded",
+    get_fuel_txs_by_format(fuel::GetFuelTxsRequest) => "fuel_txs",
+    get_fuel_receipts_by_format(fuel::GetFuelReceiptsRequest) => "fuel_receipts",
+    get_fuel_messages_by_format(fuel::GetFuelMessagesRequest) => "fuel_messages",
+    get_fuel_unspent_utxos_by_format(fuel::GetUtxoRequest) => "fuel_unspent_utxos",
+    get_fuel_spark_markets_by_format(fuel::GetSparkMarketRequest) => "fuel_spark_markets",
+    get_fuel_spark_orders_by_format(fuel::GetSparkOrderRequest) => "fuel_spark_orders",
+    get_fuel_src20_by_format(fuel::GetSrc20) => "fuel_src20",
+    get_fuel_src7_by_format(fuel::GetSrc7) => "fuel_src7",
+    get_fuel_mira_v1_pools_by_format(mira::GetMiraPoolsRequest) => "mira_v1_pools",
+    get_fuel_mira_v1_liquidity_by_format(mira::GetMiraLiquidityRequest) => "mira_v1_liquidity",
+    get_fuel_mira_v1_swaps_by_format(mira::GetMiraSwapsRequest) => "mira_v1_swaps",
+});
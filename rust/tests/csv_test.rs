@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{RecordBatch, StringArray, UInt64Array},
+    datatypes::{DataType, Field, Schema},
+};
+use futures::StreamExt;
+use pangea_client::sink::{CellFormat, CsvSink};
+use serde_json::json;
+
+#[tokio::test]
+async fn records_are_written_with_formatted_cells() {
+    let records = vec![
+        Ok(json!({
+            "block_number": 1,
+            "from": "0x52908400098527886e0f7030069857d2e4169ee7",
+            "value": "0xde0b6b3a7640000",
+            "hash": format!("0x{}", "ab".repeat(32)),
+            "extra": true,
+        })),
+        Ok(json!({ "block_number": 2, "value": "0x1", "from": null })),
+    ];
+
+    let csv = CsvSink::new(Vec::new())
+        .columns(["block_number", "from", "value", "hash"])
+        .write_stream(futures::stream::iter(records).boxed())
+        .await
+        .unwrap();
+
+    let csv = String::from_utf8(csv).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "block_number,from,value,hash");
+    assert_eq!(
+        lines[1],
+        format!(
+            "1,0x52908400098527886E0F7030069857D2E4169EE7,1000000000000000000,0x{}",
+            "ab".repeat(32)
+        )
+    );
+    assert_eq!(lines[2], "2,,1,");
+}
+
+#[tokio::test]
+async fn arrow_batches_are_written_without_headers() {
+    let schema = Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("value", DataType::Utf8, false),
+    ]);
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(UInt64Array::from(vec![7, 8])),
+            Arc::new(StringArray::from(vec!["0x10", "0x20"])),
+        ],
+    )
+    .unwrap();
+
+    let csv = CsvSink::new(Vec::new())
+        .headers(false)
+        .delimiter(b';')
+        .format("value", CellFormat::Raw)
+        .write_batches(futures::stream::iter([Ok(batch)]).boxed())
+        .await
+        .unwrap();
+
+    assert_eq!(String::from_utf8(csv).unwrap(), "7;0x10\n8;0x20\n");
+}
+
+#[tokio::test]
+async fn columns_default_to_the_fields_of_the_first_record_by_name() {
+    let records = vec![
+        Ok(json!({ "value": "0x1", "block_number": 1, "from": null })),
+        Ok(json!({ "block_number": 2, "extra": true, "value": "0x2" })),
+    ];
+
+    let csv = CsvSink::new(Vec::new())
+        .write_stream(futures::stream::iter(records).boxed())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "block_number,from,value\n1,,1\n2,,2\n"
+    );
+}
@@ -0,0 +1,297 @@
+use ethers_core::types::{Address, U256};
+use pangea_client::{
+    state::{CurvePool, CurveState, PoolState},
+    ChainId, Error,
+};
+use serde_json::{json, Value};
+
+const BASE: &str = "0x0000000000000000000000000000000000000001";
+const META: &str = "0x0000000000000000000000000000000000000002";
+const DAI: &str = "0x00000000000000000000000000000000000000a0";
+const USDC: &str = "0x00000000000000000000000000000000000000a1";
+const USDT: &str = "0x00000000000000000000000000000000000000a2";
+const LP: &str = "0x00000000000000000000000000000000000000b0";
+const COIN: &str = "0x00000000000000000000000000000000000000c0";
+
+fn address(s: &str) -> Address {
+    s.parse().unwrap()
+}
+
+fn u256(s: &str) -> U256 {
+    U256::from_dec_str(s).unwrap()
+}
+
+fn units(amount: u64, decimals: usize) -> U256 {
+    U256::from(amount) * U256::exp10(decimals)
+}
+
+/// A 3pool with a 0.04% fee and A of 2000, and a meta pool of a coin
+/// against its LP token with A of 1000
+fn state() -> CurveState {
+    let mut state = CurveState::default();
+    state
+        .apply(&json!({
+            "chain": "ETH",
+            "block_number": 1,
+            "pool_address": BASE,
+            "token": LP,
+            "coins": [DAI, USDC, USDT],
+            "decimals": [18, 6, 6],
+            "balances": [
+                units(10_000_000, 18).to_string(),
+                units(10_000_000, 6).to_string(),
+                units(12_000_000, 6).to_string(),
+            ],
+            "total_supply": units(31_000_000, 18).to_string(),
+            "fee": 4_000_000,
+            "admin_fee": 5_000_000_000u64,
+            "initial_a": 200_000,
+            "future_a": 200_000,
+            "initial_a_time": 0,
+            "future_a_time": 0,
+        }))
+        .unwrap();
+    state
+        .apply(&json!({
+            "chain": "ETH",
+            "block_number": 2,
+            "pool_address": META,
+            "coins": [COIN, LP],
+            "decimals": [18, 18],
+            "base_pool": BASE,
+            "base_coins": [DAI, USDC, USDT],
+            "balances": [
+                units(5_000_000, 18).to_string(),
+                units(5_000_000, 18).to_string(),
+            ],
+            "fee": 4_000_000,
+            "admin_fee": 5_000_000_000u64,
+            "initial_a": 100_000,
+            "future_a": 100_000,
+        }))
+        .unwrap();
+    state
+}
+
+fn trade(
+    pool: &str,
+    sold: &str,
+    bought: &str,
+    tokens_sold: &str,
+    tokens_bought: &str,
+) -> Value {
+    json!({
+        "chain": "ETH",
+        "block_number": 10,
+        "transaction_index": 0,
+        "log_index": 0,
+        "pool_address": pool,
+        "sold_address": sold,
+        "bought_address": bought,
+        "sold_decimals": 6,
+        "bought_decimals": 6,
+        "tokens_sold": tokens_sold,
+        "tokens_bought": tokens_bought,
+    })
+}
+
+#[test]
+fn quotes_plain_pool() {
+    let state = state();
+    let dy = state
+        .get_dy(
+            ChainId::ETH,
+            address(BASE),
+            address(USDC),
+            address(DAI),
+            units(1000, 6),
+        )
+        .unwrap();
+    assert_eq!(dy, u256("999599946111957913825"));
+
+    let pool = state.pool(ChainId::ETH, address(BASE)).unwrap();
+    assert_eq!(
+        pool.get_dy(1, 2, units(1000, 6), None).unwrap(),
+        U256::from(999_689_775)
+    );
+    assert_eq!(pool.virtual_price().unwrap(), u256("1032256111438688645"));
+}
+
+#[test]
+fn quotes_meta_pool() {
+    let state = state();
+    let get_dy = |token_in: &str, token_out: &str, dx: U256| {
+        state
+            .get_dy(
+                ChainId::ETH,
+                address(META),
+                address(token_in),
+                address(token_out),
+                dx,
+            )
+            .unwrap()
+    };
+
+    assert_eq!(
+        get_dy(COIN, LP, units(1000, 18)),
+        u256("968394866607938774004")
+    );
+    assert_eq!(get_dy(COIN, USDC, units(1000, 18)), U256::from(999_393_547));
+    assert_eq!(
+        get_dy(USDC, COIN, units(1000, 6)),
+        u256("999399938727132111124")
+    );
+    // both coins of the base pool
+    assert_eq!(get_dy(USDC, USDT, units(1000, 6)), U256::from(999_689_775));
+
+    let meta = state.pool(ChainId::ETH, address(META)).unwrap();
+    assert!(matches!(
+        meta.get_dy(0, 1, units(1000, 18), None),
+        Err(Error::IncompletePool(_))
+    ));
+}
+
+#[test]
+fn amplification_ramps() {
+    let mut pool = CurvePool::new(ChainId::ETH, address(BASE), 100);
+    pool.initial_a = 10_000;
+    pool.future_a = 20_000;
+    pool.initial_a_time = 1000;
+    pool.future_a_time = 2000;
+
+    assert_eq!(pool.a_at(500), U256::from(10_000));
+    assert_eq!(pool.a_at(1500), U256::from(15_000));
+    assert_eq!(pool.a_at(1750), U256::from(17_500));
+    assert_eq!(pool.a_at(2500), U256::from(20_000));
+
+    pool.initial_a = 20_000;
+    pool.future_a = 10_000;
+    assert_eq!(pool.a_at(1250), U256::from(17_500));
+
+    pool.timestamp = 1500;
+    assert_eq!(pool.a(), U256::from(15_000));
+}
+
+#[test]
+fn trades_move_balances() {
+    let mut state = state();
+    let record = trade(BASE, USDC, USDT, "1000", "999");
+    assert!(state.apply(&record).unwrap());
+    assert!(!state.apply(&record).unwrap());
+
+    let pool = state.pool(ChainId::ETH, address(BASE)).unwrap();
+    assert_eq!(pool.balances[1], U256::from(10_001_000_000_000u64));
+    // the admin fee leaves the pool
+    assert_eq!(pool.balances[2], U256::from(11_999_000_800_121u64));
+}
+
+#[test]
+fn underlying_trades_move_both_pools() {
+    let mut state = state();
+    let mut record = trade(META, COIN, USDC, "1000", "999.393547");
+    record["sold_decimals"] = json!(18);
+    assert!(state.apply(&record).unwrap());
+
+    let meta = state.pool(ChainId::ETH, address(META)).unwrap();
+    assert_eq!(meta.balances[0], units(5_001_000, 18));
+    assert_eq!(meta.balances[1], u256("4999031411376916149273461"));
+
+    let base = state.pool(ChainId::ETH, address(BASE)).unwrap();
+    assert_eq!(
+        base.balances[1],
+        U256::from(10_000_000_000_000u64 - 999_393_547)
+    );
+    assert_eq!(base.total_supply, Some(u256("30999031605133392061225996")));
+}
+
+#[test]
+fn errors() {
+    let mut state = state();
+    let other = address("0x00000000000000000000000000000000000000ff");
+
+    assert!(matches!(
+        state.get_dy(
+            ChainId::ETH,
+            address(BASE),
+            other,
+            address(DAI),
+            U256::one()
+        ),
+        Err(Error::TokenNotInPool(..))
+    ));
+    assert!(matches!(
+        state.get_dy(
+            ChainId::ETH,
+            other,
+            address(USDC),
+            address(DAI),
+            U256::one()
+        ),
+        Err(Error::UnknownPool(_))
+    ));
+
+    // no balances for a pool only seen in the trades stream
+    assert!(matches!(
+        state.apply(&json!({
+            "chain": "ETH",
+            "block_number": 10,
+            "pool_address": other,
+            "coins": [DAI, USDC],
+        })),
+        Ok(true)
+    ));
+    let mut record = trade(BASE, USDC, DAI, "1", "1");
+    record["pool_address"] = json!(other);
+    assert!(matches!(
+        state.apply(&record),
+        Err(Error::IncompletePool(_))
+    ));
+}
+
+#[test]
+fn snapshot_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("curve.json");
+
+    let state = state();
+    state.snapshot(&path).unwrap();
+
+    assert_eq!(CurveState::restore(&path).unwrap(), state);
+}
+
+#[test]
+fn incomplete_pools_and_overflows_are_errors() {
+    let mut state = state();
+    let pool = state.pool(ChainId::ETH, address(BASE)).unwrap();
+    assert!(matches!(
+        pool.get_dy(1, 2, U256::MAX, None),
+        Err(Error::MathError(_))
+    ));
+    assert!(matches!(
+        pool.calc_token_amount(&[U256::MAX, U256::zero(), U256::zero()], true),
+        Err(Error::MathError(_))
+    ));
+
+    // a pool record without the `A` ramp
+    let other = "0x00000000000000000000000000000000000000ff";
+    state
+        .apply(&json!({
+            "chain": "ETH",
+            "block_number": 10,
+            "pool_address": other,
+            "coins": [DAI, USDC],
+            "decimals": [18, 6],
+            "balances": [units(1000, 18).to_string(), units(1000, 6).to_string()],
+            "total_supply": units(2000, 18).to_string(),
+        }))
+        .unwrap();
+    let pool = state.pool(ChainId::ETH, address(other)).unwrap();
+    assert!(matches!(
+        pool.get_dy(0, 1, units(1, 18), None),
+        Err(Error::IncompletePool(_))
+    ));
+    assert!(matches!(
+        pool.virtual_price(),
+        Err(Error::IncompletePool(_))
+    ));
+}
@@ -0,0 +1,305 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashSet;
+
+use std::{fmt::Debug, hash::Hash};
+
+use ethers_core::types::{Address, H256, U128, U256};
+use fuel_core_types::fuel_types::{self, AssetId, Bytes32, ContractId};
+use pangea_client::{
+    query::Bound,
+    requests::{
+        blocks, btc, curve, erc20, erc20::GetErc20TransferssRequest, fuel,
+        logs::GetLogsRequest, mira, transfers, txs::GetTxsRequest, uniswap_v2, uniswap_v3,
+        Encoding,
+    },
+    Amount, ChainId,
+};
+use proptest::{collection::hash_set, prelude::*};
+
+fn chains() -> impl Strategy<Value = HashSet<ChainId>> {
+    hash_set(
+        prop_oneof![
+            Just(ChainId::ETH),
+            Just(ChainId::ARB),
+            Just(ChainId::MEVM),
+            (100_000u64..200_000).prop_map(ChainId::from_id),
+        ],
+        1..4,
+    )
+}
+
+fn bound() -> impl Strategy<Value = Bound> {
+    prop_oneof![
+        (0i64..i64::MAX).prop_map(Bound::Exact),
+        (1u64..1_000_000).prop_map(Bound::FromLatest),
+        Just(Bound::Latest),
+        Just(Bound::Subscribe),
+    ]
+}
+
+fn addresses() -> impl Strategy<Value = HashSet<Address>> {
+    hash_set(any::<[u8; 20]>().prop_map(Address::from), 0..8)
+}
+
+fn hashes() -> impl Strategy<Value = HashSet<H256>> {
+    hash_set(any::<[u8; 32]>().prop_map(H256::from), 0..4)
+}
+
+fn u256() -> impl Strategy<Value = Option<U256>> {
+    proptest::option::of(any::<[u8; 32]>().prop_map(|bytes| U256::from_big_endian(&bytes)))
+}
+
+/// Amounts with any number of decimals
+fn amount() -> impl Strategy<Value = Option<Amount>> {
+    proptest::option::of((any::<[u8; 32]>(), 0u8..=77).prop_map(|(bytes, decimals)| {
+        Amount::new(U256::from_big_endian(&bytes), decimals).unwrap()
+    }))
+}
+
+fn fuel_ids<T>() -> impl Strategy<Value = HashSet<T>>
+where
+    T: From<[u8; 32]> + Eq + Hash + Debug,
+{
+    hash_set(any::<[u8; 32]>().prop_map(T::from), 0..4)
+}
+
+fn symbols() -> impl Strategy<Value = HashSet<String>> {
+    hash_set("[A-Za-z0-9]{1,8}", 0..4)
+}
+
+fn assert_round_trips<R>(request: &R)
+where
+    R: Encoding + PartialEq + std::fmt::Debug,
+{
+    let query = request.to_query_string().unwrap();
+    assert_eq!(&R::from_query_string(&query).unwrap(), request, "{query}");
+
+    let json = request.to_json().unwrap();
+    assert_eq!(&R::from_json(json.clone()).unwrap(), request, "{json}");
+}
+
+proptest! {
+    #[test]
+    fn logs_requests_round_trip(
+        chains in chains(),
+        from_block in bound(),
+        to_block in bound(),
+        address__in in addresses(),
+        address__not_in in addresses(),
+        topic0__in in hashes(),
+        topic3__in in hashes(),
+    ) {
+        assert_round_trips(&GetLogsRequest {
+            chains,
+            from_block,
+            to_block,
+            address__in,
+            address__not_in,
+            topic0__in,
+            topic3__in,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn txs_requests_round_trip(
+        chains in chains(),
+        from_block in bound(),
+        hash__in in hashes(),
+        from__in in addresses(),
+        value__gte in u256(),
+        gas__lte in u256(),
+    ) {
+        assert_round_trips(&GetTxsRequest {
+            chains,
+            from_block,
+            hash__in,
+            from__in,
+            value__gte,
+            gas__lte,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn erc20_transfers_requests_round_trip(
+        chains in chains(),
+        to__in in addresses(),
+        symbol__in in symbols(),
+        decimals__gte in proptest::option::of(any::<u8>()),
+        value__lte in amount(),
+    ) {
+        assert_round_trips(&GetErc20TransferssRequest {
+            chains,
+            to__in,
+            symbol__in,
+            decimals__gte,
+            value__lte,
+            ..Default::default()
+        });
+    }
+}
+
+/// Round-trips `$request` with its defaults, then with random chains, bounds
+/// and `$field`s, the other fields keeping their defaults
+macro_rules! round_trips {
+    ($($name:ident: $request:path { $($field:ident in $strategy:expr),* $(,)? }),* $(,)?) => {
+        proptest! {
+            $(
+                #[test]
+                // requests without other fields are filled in whole
+                #[allow(clippy::needless_update)]
+                fn $name(
+                    chains in chains(),
+                    from_block in bound(),
+                    to_block in bound(),
+                    $($field in $strategy,)*
+                ) {
+                    // a path can not name the struct of a literal, an alias can
+                    type Request = $request;
+                    assert_round_trips(&Request::default());
+                    assert_round_trips(&Request {
+                        chains,
+                        from_block,
+                        to_block,
+                        $($field,)*
+                        ..Default::default()
+                    });
+                }
+            )*
+        }
+    };
+}
+
+round_trips! {
+    blocks_requests_round_trip: blocks::GetBlocksRequest {
+        hash__in in hashes(),
+    },
+    btc_blocks_requests_round_trip: btc::GetBtcBlocksRequest {},
+    btc_txs_requests_round_trip: btc::GetBtcTxsRequest {},
+    crv_tokens_requests_round_trip: curve::GetCrvTokenRequest {
+        address__in in addresses(),
+        symbol__in in symbols(),
+        decimals__gte in proptest::option::of(any::<u8>()),
+    },
+    crv_pools_requests_round_trip: curve::GetCrvPoolRequest {
+        pool_address__in in addresses(),
+        coins__in in addresses(),
+        fee__gte in u256(),
+        n_coins__lte in proptest::option::of(any::<u8>()),
+    },
+    crv_prices_requests_round_trip: curve::GetCrvPriceRequest {
+        pool_address__in in addresses(),
+        sold_symbol__in in symbols(),
+        price__gte in amount(),
+        tokens_bought__lte in amount(),
+    },
+    erc20_requests_round_trip: erc20::GetErc20Request {
+        address__in in addresses(),
+        name__in in symbols(),
+        decimals__lte in proptest::option::of(any::<u8>()),
+    },
+    erc20_approvals_requests_round_trip: erc20::GetErc20ApprovalsRequest {
+        owner__in in addresses(),
+        spender__in in addresses(),
+        value__gte in amount(),
+    },
+    fuel_blocks_requests_round_trip: fuel::GetFuelBlocksRequest {
+        da_block_number__gte in proptest::option::of(any::<u64>()),
+    },
+    fuel_logs_requests_round_trip: fuel::GetFuelLogsRequest {
+        id__in in fuel_ids::<ContractId>(),
+        rb__in in hash_set(any::<u64>(), 0..4),
+    },
+    fuel_txs_requests_round_trip: fuel::GetFuelTxsRequest {
+        metadata_contract_id__in in fuel_ids::<ContractId>(),
+        mint_asset_id__in in fuel_ids::<AssetId>(),
+        mint_amount__lte in proptest::option::of(any::<u64>()),
+    },
+    fuel_receipts_requests_round_trip: fuel::GetFuelReceiptsRequest {},
+    fuel_messages_requests_round_trip: fuel::GetFuelMessagesRequest {
+        sender__in in fuel_ids::<fuel_types::Address>(),
+        amount__gte in proptest::option::of(any::<u64>()),
+    },
+    spark_markets_requests_round_trip: fuel::GetSparkMarketRequest {
+        base_asset__in in fuel_ids::<AssetId>(),
+        market_id__in in fuel_ids::<ContractId>(),
+    },
+    spark_orders_requests_round_trip: fuel::GetSparkOrderRequest {
+        order_id__in in fuel_ids::<Bytes32>(),
+        asset__in in fuel_ids::<AssetId>(),
+        address__in in fuel_ids::<fuel_types::Address>(),
+    },
+    utxo_requests_round_trip: fuel::GetUtxoRequest {
+        unspent_at in bound(),
+        address__in in fuel_ids::<fuel_types::Address>(),
+    },
+    src20_requests_round_trip: fuel::GetSrc20 {
+        contract_id__in in fuel_ids::<ContractId>(),
+        symbol__in in symbols(),
+    },
+    src7_requests_round_trip: fuel::GetSrc7 {
+        asset__in in fuel_ids::<AssetId>(),
+        key__in in symbols(),
+    },
+    mira_pools_requests_round_trip: mira::GetMiraPoolsRequest {
+        pool_address__in in fuel_ids::<Bytes32>(),
+        assets__in in fuel_ids::<AssetId>(),
+    },
+    mira_liquidity_requests_round_trip: mira::GetMiraLiquidityRequest {
+        pool_address__in in fuel_ids::<Bytes32>(),
+        asset0_address__in in fuel_ids::<AssetId>(),
+    },
+    mira_swaps_requests_round_trip: mira::GetMiraSwapsRequest {
+        pool_address__in in fuel_ids::<Bytes32>(),
+        asset1_address__in in fuel_ids::<AssetId>(),
+    },
+    transfers_requests_round_trip: transfers::GetTransfersRequest {
+        transaction_hash__in in hashes(),
+        address__not_in in addresses(),
+        value__gte in u256(),
+    },
+    univ2_pairs_requests_round_trip: uniswap_v2::GetPairsRequest {
+        pair_address__in in addresses(),
+        tokens__in in addresses(),
+    },
+    univ2_prices_requests_round_trip: uniswap_v2::GetPricesRequest {
+        pair_address__not_in in addresses(),
+        reserve0__gte in proptest::option::of(any::<u128>().prop_map(U128::from)),
+        price__lte in amount(),
+        token1_symbol__in in symbols(),
+    },
+    univ3_fees_requests_round_trip: uniswap_v3::GetFeesRequest {
+        pool_address__in in addresses(),
+        amount0__gte in amount(),
+        tick_lower__gte in proptest::option::of(any::<i32>()),
+    },
+    univ3_pools_requests_round_trip: uniswap_v3::GetPoolsRequest {
+        factory_address__in in addresses(),
+        fee__lte in proptest::option::of(any::<i32>()),
+    },
+    univ3_positions_requests_round_trip: uniswap_v3::GetPositionsRequest {
+        recipient__in in addresses(),
+        amount1__lte in amount(),
+        tick__gte in proptest::option::of(any::<i32>()),
+    },
+    univ3_prices_requests_round_trip: uniswap_v3::GetPricesRequest {
+        pool_address__not_in in addresses(),
+        liquidity__gte in amount(),
+        tokens_symbol__in in symbols(),
+    },
+}
+
+#[test]
+fn malformed_query_strings_are_errors() {
+    assert!(GetLogsRequest::from_query_string("address__in=0x1234,nope").is_err());
+    assert!(GetLogsRequest::from_query_string("from_block=yesterday").is_err());
+
+    let request =
+        GetLogsRequest::from_query_string("?chains=ETH,ARB&from_block=-10&to_block=none")
+            .unwrap();
+    assert_eq!(request.chains, HashSet::from([ChainId::ETH, ChainId::ARB]));
+    assert_eq!(request.from_block, Bound::FromLatest(10));
+    assert_eq!(request.to_block, Bound::Subscribe);
+}
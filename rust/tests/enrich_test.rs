@@ -0,0 +1,124 @@
+mod common;
+
+use std::{collections::HashSet, sync::Arc};
+
+use ethers_core::types::H256;
+use futures::StreamExt;
+use pangea_client::{query::Bound, requests::logs::GetLogsRequest, Address, ChainId, Client};
+use serde_json::{json, Value};
+
+use common::{from_block, Fake};
+
+const BLOCKS: u64 = 1000;
+const SENDER: &str = "0x00000000000000000000000000000000000000a0";
+
+fn hash(block_number: u64) -> H256 {
+    H256::from_low_u64_be(block_number + 1)
+}
+
+/// The block number a request ends before
+fn to_block(request: &Value) -> u64 {
+    request["to_block"].as_u64().unwrap_or(BLOCKS)
+}
+
+/// One block and one transaction per block, and logs in blocks 0 and 500
+fn client() -> Arc<Client<Fake>> {
+    let client = Fake::new(|endpoint, request| {
+        let blocks = from_block(request)..to_block(request).min(BLOCKS);
+        match endpoint {
+            "blocks" => blocks
+                .map(|n| json!({ "chain": "ETH", "block_number": n, "timestamp": 1000 + n }))
+                .collect(),
+            "txs" => {
+                let hashes = request["hash__in"].as_str().map(|hashes| {
+                    hashes
+                        .split(',')
+                        .map(|hash| hash.parse::<H256>().unwrap())
+                        .collect::<HashSet<_>>()
+                });
+                blocks
+                    .filter(|n| {
+                        hashes
+                            .as_ref()
+                            .is_none_or(|hashes| hashes.contains(&hash(*n)))
+                    })
+                    .map(|n| {
+                        json!({
+                            "chain": "ETH",
+                            "block_number": n,
+                            "transaction_index": 0,
+                            "hash": hash(n),
+                            "from": SENDER,
+                            "value": "1000000000000000000",
+                        })
+                    })
+                    .collect()
+            }
+            "logs" => [0, 500]
+                .into_iter()
+                .filter(|n| blocks.contains(n))
+                .map(|n| {
+                    json!({
+                        "chain": "ETH",
+                        "block_number": n,
+                        "transaction_index": 0,
+                        "log_index": 0,
+                        "transaction_hash": hash(n),
+                    })
+                })
+                .collect(),
+            endpoint => unimplemented!("{endpoint}"),
+        }
+    });
+    Arc::new(client.client())
+}
+
+fn request() -> GetLogsRequest {
+    GetLogsRequest {
+        chains: HashSet::from([ChainId::ETH]),
+        from_block: Bound::Exact(0),
+        to_block: Bound::Exact(BLOCKS as i64),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn logs_are_joined_with_their_block_and_transaction() {
+    let client = client();
+    let mut logs = client.enrich_logs::<Value>(request(), 1000).await.unwrap();
+
+    let log = logs.next().await.unwrap().unwrap();
+    assert_eq!(log.block.unwrap().timestamp, 1000);
+    let transaction = log.transaction.unwrap();
+    assert_eq!(transaction.from, SENDER.parse::<Address>().unwrap());
+    assert_eq!(transaction.value.to_string(), "1");
+
+    let log = logs.next().await.unwrap().unwrap();
+    assert_eq!(log.log["block_number"], 500);
+    assert_eq!(log.block.unwrap().timestamp, 1500);
+    assert!(logs.next().await.is_none());
+}
+
+#[tokio::test]
+async fn only_the_blocks_and_transactions_of_the_logs_are_fetched() {
+    for window in [1, 1000] {
+        let client = client();
+        let logs = client
+            .enrich_logs::<Value>(request(), window)
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(logs.len(), 2);
+        assert!(logs
+            .iter()
+            .all(|log| log.block.is_some() && log.transaction.is_some()));
+
+        // far denser blocks and transactions than logs are not read
+        let polled = client.inner.polled.lock().unwrap();
+        assert_eq!(polled["blocks"], 2);
+        assert_eq!(polled["txs"], 2);
+    }
+}
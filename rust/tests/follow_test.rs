@@ -0,0 +1,86 @@
+mod common;
+
+use std::collections::HashSet;
+
+use futures::StreamExt;
+use pangea_client::{query::Bound, requests::transfers::GetTransfersRequest, ChainId};
+use serde_json::{json, Value};
+
+use common::{is_live, Fake};
+
+fn transfer(block_number: u64, transaction_index: u64, value: u64) -> Value {
+    json!({
+        "chain": "ETH",
+        "block_number": block_number,
+        "transaction_index": transaction_index,
+        "value": value,
+    })
+}
+
+async fn follow(backfill: Vec<Value>, live: Vec<Value>) -> Vec<Value> {
+    let client = Fake::new(move |_, request| {
+        if is_live(request) {
+            live.clone()
+        } else {
+            backfill.clone()
+        }
+    })
+    .client();
+
+    let request = GetTransfersRequest {
+        chains: HashSet::from([ChainId::ETH]),
+        from_block: Bound::Exact(1),
+        to_block: Bound::Subscribe,
+        ..Default::default()
+    };
+    client
+        .follow::<_, Value>(request)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn overlap_is_delivered_once() {
+    let records = follow(
+        vec![transfer(1, 0, 1), transfer(2, 0, 2)],
+        vec![transfer(2, 0, 2), transfer(3, 0, 3)],
+    )
+    .await;
+
+    assert_eq!(
+        records,
+        [transfer(1, 0, 1), transfer(2, 0, 2), transfer(3, 0, 3)]
+    );
+}
+
+#[tokio::test]
+async fn records_sharing_a_position_are_all_delivered() {
+    // native transfers carry no log index, several in one transaction share
+    // a position
+    let records = follow(
+        vec![transfer(1, 0, 1), transfer(1, 0, 2)],
+        vec![
+            transfer(1, 0, 2),
+            transfer(2, 0, 3),
+            transfer(2, 0, 4),
+            transfer(2, 1, 5),
+            transfer(2, 1, 6),
+        ],
+    )
+    .await;
+
+    assert_eq!(
+        records,
+        [
+            transfer(1, 0, 1),
+            transfer(1, 0, 2),
+            transfer(2, 0, 3),
+            transfer(2, 0, 4),
+            transfer(2, 1, 5),
+            transfer(2, 1, 6),
+        ]
+    );
+}
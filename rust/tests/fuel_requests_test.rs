@@ -0,0 +1,39 @@
+use std::collections::HashSet;
+
+use fuel_core_types::fuel_types::{AssetId, ContractId};
+use pangea_client::requests::{fuel::GetFuelLogsRequest, mira::GetMiraSwapsRequest, Encoding};
+
+const CONTRACT_ID: &str = "0x2e40f2b244b98ed6b8204b3de0156c6961f98525c8162f80162fcf53eebd90e7";
+const ASSET_ID: &str = "0xf8f8b6283d7fa5b672b530cbb84fcccb4ff8dc40f8176ef4544ddb1f1952ad07";
+
+#[test]
+fn fuel_ids_are_parsed_and_serialized_with_all_32_bytes() {
+    let contract_id: ContractId = CONTRACT_ID.parse().unwrap();
+    let request = GetFuelLogsRequest {
+        id__in: HashSet::from([contract_id]),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["id__in"], CONTRACT_ID);
+}
+
+#[test]
+fn mira_requests_take_fuel_asset_ids() {
+    let request: GetMiraSwapsRequest = serde_json::from_value(serde_json::json!({
+        "from_block": 0,
+        "to_block": "latest",
+        "asset0__in": [ASSET_ID],
+    }))
+    .unwrap();
+
+    let asset: AssetId = ASSET_ID.parse().unwrap();
+    assert_eq!(request.asset0_address__in, HashSet::from([asset]));
+
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["asset0_address__in"], ASSET_ID);
+    assert!(request
+        .to_query_string()
+        .unwrap()
+        .ends_with(&format!("&asset0_address__in={ASSET_ID}")));
+}
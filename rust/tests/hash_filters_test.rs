@@ -0,0 +1,71 @@
+mod common;
+
+use std::collections::HashSet;
+
+use ethers_core::types::H256;
+use pangea_client::{
+    query::Bound,
+    requests::{logs::GetLogsRequest, txs::GetTxsRequest},
+    ChainId,
+};
+use serde_json::{json, Value};
+
+use common::Fake;
+
+#[test]
+fn hash_filters_are_serialized_only_when_set() {
+    let hash = H256::repeat_byte(0xab);
+    let request = GetTxsRequest {
+        hash__in: HashSet::from([hash]),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["hash__in"], format!("{hash:?}"));
+
+    let json = serde_json::to_value(GetLogsRequest::default()).unwrap();
+    assert!(json.get("transaction_hash__in").is_none());
+}
+
+#[test]
+fn hash_filters_are_deserialized() {
+    let hash = H256::repeat_byte(0x01);
+    let request: GetLogsRequest = serde_json::from_value(serde_json::json!({
+        "transaction_hash__in": [hash],
+    }))
+    .unwrap();
+
+    assert_eq!(request.transaction_hash__in, HashSet::from([hash]));
+}
+
+#[tokio::test]
+async fn transactions_are_looked_up_by_hash() {
+    let hash = H256::repeat_byte(0xcd);
+    let client = Fake::new(|endpoint, request| match endpoint {
+        "txs" => {
+            vec![json!({ "chain": "ETH", "block_number": 90, "hash": request["hash__in"] })]
+        }
+        endpoint => unimplemented!("{endpoint}"),
+    })
+    .client();
+
+    let tx = client
+        .get_tx_by_hash::<Value>(ChainId::ETH, hash)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(tx["hash"], format!("{hash:?}"));
+
+    client
+        .get_tx_by_hash_from::<Value>(ChainId::ETH, hash, Bound::FromLatest(100))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let requests = client.inner.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].1["hash__in"], format!("{hash:?}"));
+    assert_eq!(requests[0].1["from_block"], 0);
+    assert_eq!(requests[1].1["from_block"], -100);
+    assert_eq!(requests[1].1["to_block"], "latest");
+}
@@ -0,0 +1,277 @@
+use fuel_core_types::fuel_types::{AssetId, Bytes32};
+use pangea_client::{
+    core::types::mira::{EventType, MiraSwap, Side},
+    state::{MiraPoolState, PoolState},
+    Amount, ChainId, Error,
+};
+use serde_json::{json, Value};
+
+const VOLATILE: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+const STABLE: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+const ETH: &str = "0x00000000000000000000000000000000000000000000000000000000000000a0";
+const USDC: &str = "0x00000000000000000000000000000000000000000000000000000000000000a1";
+const USDT: &str = "0x00000000000000000000000000000000000000000000000000000000000000a2";
+
+fn pool(s: &str) -> Bytes32 {
+    s.parse().unwrap()
+}
+
+fn asset(s: &str) -> AssetId {
+    s.parse().unwrap()
+}
+
+fn event(block_number: u64, pool: &str, asset0: &str, asset1: &str) -> Value {
+    json!({
+        "chain": "FUEL",
+        "block_number": block_number,
+        "transaction_index": 0,
+        "log_index": 0,
+        "pool_address": pool,
+        "asset0_address": asset0,
+        "asset1_address": asset1,
+    })
+}
+
+/// A volatile pool of 1000 ETH (9 decimals) against 2M USDC (6 decimals) and
+/// a stable pool of 1M USDC against 1M USDT (9 decimals)
+fn state() -> MiraPoolState {
+    let mut state = MiraPoolState::default();
+
+    let mut record = event(1, VOLATILE, ETH, USDC);
+    record["is_stable"] = json!(false);
+    state.apply(&record).unwrap();
+    let mut record = event(1, STABLE, USDC, USDT);
+    record["is_stable"] = json!(true);
+    record["decimals0"] = json!(6);
+    record["decimals1"] = json!(9);
+    state.apply(&record).unwrap();
+
+    let mut mint = event(2, VOLATILE, ETH, USDC);
+    mint["event_type"] = json!(0);
+    mint["amount0"] = json!(1_000_000_000_000u64);
+    mint["amount1"] = json!("2000000000000");
+    state.apply(&mint).unwrap();
+    let mut mint = event(2, STABLE, USDC, USDT);
+    mint["event_type"] = json!("Mint");
+    mint["amount0"] = json!(1_000_000_000_000u64);
+    mint["amount1"] = json!(1_000_000_000_000_000u64);
+    state.apply(&mint).unwrap();
+
+    state
+}
+
+#[test]
+fn typed_records() {
+    let mut record = event(3, VOLATILE, ETH, USDC);
+    record["side"] = json!(1);
+    record["amount0"] = json!("0x3b9aca00");
+    record["amount1"] = json!(1_992_013_962u64);
+
+    let swap: MiraSwap = serde_json::from_value(record.clone()).unwrap();
+    assert_eq!(swap.chain, ChainId::FUEL);
+    assert_eq!(swap.side, Side::Sell);
+    assert_eq!(swap.amount0, Amount::from(1_000_000_000));
+    assert_eq!(swap.pool_address, pool(VOLATILE));
+    assert_eq!(swap.position().block_number, 3);
+
+    // written back with the names of the variants
+    let value = serde_json::to_value(&swap).unwrap();
+    assert_eq!(value["side"], json!("Sell"));
+    assert_eq!(serde_json::from_value::<MiraSwap>(value).unwrap(), swap);
+
+    record["side"] = json!(7);
+    assert!(serde_json::from_value::<MiraSwap>(record).is_err());
+    assert!(matches!(EventType::try_from(2), Ok(EventType::Swap)));
+    assert_eq!(EventType::Burn as i32, 1);
+    assert_eq!(Side::Sell.as_ref(), "Sell");
+}
+
+#[test]
+fn quotes_volatile_pool() {
+    let state = state();
+    let quote = |asset_in: &str, amount_in: u64| {
+        state
+            .get_amount_out(ChainId::FUEL, pool(VOLATILE), asset(asset_in), amount_in)
+            .unwrap()
+    };
+
+    assert_eq!(quote(ETH, 1_000_000_000), 1_992_013_962);
+    let out = quote(USDC, 1_000_000_000);
+    assert_eq!(out, 498_251_621);
+    assert_eq!(
+        state
+            .get_amount_in(ChainId::FUEL, pool(VOLATILE), asset(ETH), out)
+            .unwrap(),
+        999_999_999
+    );
+}
+
+#[test]
+fn full_fee_quotes_nothing() {
+    let state = state();
+    for address in [VOLATILE, STABLE] {
+        let mut reserves = state.pool(ChainId::FUEL, pool(address)).unwrap().clone();
+        reserves.fee_bps = 10_000;
+
+        assert_eq!(reserves.get_amount_out(asset(USDC), 1_000_000).unwrap(), 0);
+        assert!(matches!(
+            reserves.get_amount_in(asset(USDC), 1_000_000),
+            Err(Error::InsufficientLiquidity(_))
+        ));
+    }
+}
+
+#[test]
+fn quotes_stable_pool() {
+    let state = state();
+
+    let out = state
+        .get_amount_out(ChainId::FUEL, pool(STABLE), asset(USDC), 1_000_000_000)
+        .unwrap();
+    // 0.05% fee, next to no slippage around the peg
+    assert_eq!(out, 999_499_999_500);
+    assert_eq!(
+        state
+            .get_amount_in(ChainId::FUEL, pool(STABLE), asset(USDT), out)
+            .unwrap(),
+        1_000_000_000
+    );
+
+    let out = state
+        .get_amount_out(ChainId::FUEL, pool(STABLE), asset(USDC), 500_000_000_000)
+        .unwrap();
+    assert_eq!(out, 472_404_021_929_024);
+}
+
+#[test]
+fn events_move_reserves() {
+    let mut state = state();
+
+    let mut swap = event(3, VOLATILE, ETH, USDC);
+    swap["side"] = json!("Sell");
+    swap["amount0"] = json!(1_000_000_000u64);
+    swap["amount1"] = json!(1_992_013_962u64);
+    assert!(state.apply(&swap).unwrap());
+    assert!(!state.apply(&swap).unwrap());
+
+    let reserves = state.pool(ChainId::FUEL, pool(VOLATILE)).unwrap();
+    assert_eq!(reserves.reserve0, 1_001_000_000_000);
+    assert_eq!(reserves.reserve1, 2_000_000_000_000 - 1_992_013_962);
+
+    let mut burn = event(4, VOLATILE, ETH, USDC);
+    burn["event_type"] = json!(1);
+    burn["amount0"] = json!(1_000_000_000u64);
+    burn["amount1"] = json!(1_000_000_000u64);
+    assert!(state.apply(&burn).unwrap());
+
+    let reserves = state.pool(ChainId::FUEL, pool(VOLATILE)).unwrap();
+    assert_eq!(reserves.reserve0, 1_000_000_000_000);
+    assert_eq!(
+        state.last_position(ChainId::FUEL).map(|p| p.block_number),
+        Some(4)
+    );
+
+    // more than the reserves, the stream was not replayed from the creation
+    let mut swap = event(5, VOLATILE, ETH, USDC);
+    swap["side"] = json!(0);
+    swap["amount0"] = json!(u64::MAX);
+    swap["amount1"] = json!(1);
+    assert!(matches!(
+        state.apply(&swap),
+        Err(Error::InvalidPoolEvent(_))
+    ));
+}
+
+#[test]
+fn streams_are_consumed_independently() {
+    let mut state = state();
+
+    // the whole liquidity stream, then the swaps stream from its start
+    let mut burn = event(5, VOLATILE, ETH, USDC);
+    burn["event_type"] = json!("Burn");
+    burn["amount0"] = json!(1_000_000_000u64);
+    burn["amount1"] = json!(2_000_000_000u64);
+    assert!(state.apply(&burn).unwrap());
+
+    let mut swap = event(3, VOLATILE, ETH, USDC);
+    swap["side"] = json!("Sell");
+    swap["amount0"] = json!(1_000_000_000u64);
+    swap["amount1"] = json!(1_992_013_962u64);
+    assert!(state.apply(&swap).unwrap());
+    assert!(!state.apply(&swap).unwrap());
+
+    let reserves = state.pool(ChainId::FUEL, pool(VOLATILE)).unwrap();
+    assert_eq!(reserves.reserve0, 1_000_000_000_000);
+    assert_eq!(
+        state
+            .last_liquidity_position(ChainId::FUEL)
+            .map(|p| p.block_number),
+        Some(5)
+    );
+    assert_eq!(
+        state
+            .last_swap_position(ChainId::FUEL)
+            .map(|p| p.block_number),
+        Some(3)
+    );
+    assert_eq!(
+        state.last_position(ChainId::FUEL).map(|p| p.block_number),
+        Some(5)
+    );
+}
+
+#[test]
+fn errors() {
+    let mut state = state();
+    let other = "0x00000000000000000000000000000000000000000000000000000000000000ff";
+
+    assert!(matches!(
+        state.get_amount_out(ChainId::FUEL, pool(VOLATILE), asset(other), 1),
+        Err(Error::TokenNotInPool(..))
+    ));
+    assert!(matches!(
+        state.get_amount_out(ChainId::FUEL, pool(other), asset(ETH), 1),
+        Err(Error::UnknownPool(_))
+    ));
+    assert!(matches!(
+        state.get_amount_in(ChainId::FUEL, pool(VOLATILE), asset(ETH), 1_000_000_000_000),
+        Err(Error::InsufficientLiquidity(_))
+    ));
+
+    // a stable pool without the decimals of its assets
+    let mut record = event(6, other, USDC, USDT);
+    record["is_stable"] = json!(true);
+    state.apply(&record).unwrap();
+    let mut mint = event(7, other, USDC, USDT);
+    mint["event_type"] = json!(0);
+    mint["amount0"] = json!(1_000);
+    mint["amount1"] = json!(1_000);
+    state.apply(&mint).unwrap();
+    assert!(matches!(
+        state.get_amount_out(ChainId::FUEL, pool(other), asset(USDC), 1),
+        Err(Error::IncompletePool(_))
+    ));
+
+    assert!(matches!(
+        state.apply(&json!({ "chain": "FUEL", "pool_address": other })),
+        Err(Error::InvalidPoolEvent(_))
+    ));
+    // amounts without a side or event type
+    let mut record = event(8, other, USDC, USDT);
+    record["amount0"] = json!(1);
+    assert!(matches!(
+        state.apply(&record),
+        Err(Error::InvalidPoolEvent(_))
+    ));
+}
+
+#[test]
+fn snapshot_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mira.json");
+
+    let state = state().with_fees(25, 4);
+    state.snapshot(&path).unwrap();
+
+    assert_eq!(MiraPoolState::restore(&path).unwrap(), state);
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use std::collections::HashSet;
+
+use futures::StreamExt;
+use pangea_client::{
+    multichain::ChainEvent, query::Bound, requests::logs::GetLogsRequest, ChainId,
+};
+use serde_json::{json, Value};
+
+use common::Fake;
+
+fn log(chain: &str, block_number: u64, log_index: u64) -> Value {
+    json!({
+        "chain": chain,
+        "block_number": block_number,
+        "transaction_index": 0,
+        "log_index": log_index,
+    })
+}
+
+/// The block both chains are at
+const HEAD: u64 = 20;
+
+/// The events of a request over ETH and ARB, split by chain
+async fn fan_out(
+    eth: Vec<Value>,
+    arb: Vec<Value>,
+) -> (Vec<ChainEvent<Value>>, Vec<ChainEvent<Value>>) {
+    let client =
+        Fake::new(
+            move |endpoint, request| match (endpoint, request["chains"].as_str()) {
+                ("blocks", Some(chain)) => {
+                    vec![json!({ "chain": chain, "block_number": HEAD })]
+                }
+                (_, Some("ETH")) => eth.clone(),
+                (_, Some("ARB")) => arb.clone(),
+                (_, chains) => panic!("{chains:?} requested at once"),
+            },
+        )
+        .client();
+
+    let request = GetLogsRequest {
+        chains: HashSet::from([ChainId::ETH, ChainId::ARB]),
+        from_block: Bound::Exact(0),
+        to_block: Bound::Exact(10),
+        ..Default::default()
+    };
+    let events = client
+        .fan_out::<_, Value>(request)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    // the head and the records of every chain
+    assert_eq!(client.inner.requests.lock().unwrap().len(), 4);
+
+    events
+        .into_iter()
+        .partition(|event| event.chain() == ChainId::ETH)
+}
+
+fn record(chain: ChainId, record: Value) -> ChainEvent<Value> {
+    ChainEvent::Record { chain, record }
+}
+
+fn watermark(chain: ChainId, block_number: u64) -> ChainEvent<Value> {
+    ChainEvent::Watermark {
+        chain,
+        block_number,
+    }
+}
+
+#[tokio::test]
+async fn records_are_tagged_with_their_chain_and_followed_by_watermarks() {
+    let (eth, arb) = fan_out(
+        vec![log("ETH", 1, 0), log("ETH", 1, 1), log("ETH", 3, 0)],
+        vec![log("ARB", 2, 0)],
+    )
+    .await;
+
+    assert_eq!(
+        eth,
+        [
+            record(ChainId::ETH, log("ETH", 1, 0)),
+            record(ChainId::ETH, log("ETH", 1, 1)),
+            watermark(ChainId::ETH, 1),
+            record(ChainId::ETH, log("ETH", 3, 0)),
+            watermark(ChainId::ETH, 3),
+            watermark(ChainId::ETH, HEAD),
+        ]
+    );
+    assert_eq!(
+        arb,
+        [
+            record(ChainId::ARB, log("ARB", 2, 0)),
+            watermark(ChainId::ARB, 2),
+            watermark(ChainId::ARB, HEAD),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn chains_without_records_are_marked_up_to_their_head() {
+    let (eth, arb) = fan_out(vec![log("ETH", 5, 0)], Vec::new()).await;
+
+    assert_eq!(
+        eth,
+        [
+            record(ChainId::ETH, log("ETH", 5, 0)),
+            watermark(ChainId::ETH, 5),
+            watermark(ChainId::ETH, HEAD),
+        ]
+    );
+    assert_eq!(arb, [watermark(ChainId::ARB, HEAD)]);
+}
+
+#[tokio::test]
+async fn records_at_the_head_are_marked_once() {
+    let (eth, _) = fan_out(vec![log("ETH", HEAD, 0)], Vec::new()).await;
+
+    assert_eq!(
+        eth,
+        [
+            record(ChainId::ETH, log("ETH", HEAD, 0)),
+            watermark(ChainId::ETH, HEAD),
+        ]
+    );
+}
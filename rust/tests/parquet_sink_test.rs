@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{RecordBatch, StringArray, UInt64Array},
+    datatypes::{DataType, Field, Schema},
+    ipc::writer::StreamWriter,
+};
+use futures::StreamExt;
+use pangea_client::{core::stream::arrow_batches, provider::ResponseStream};
+
+fn batch(blocks: &[u64]) -> RecordBatch {
+    let schema = Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("hash", DataType::Utf8, false),
+    ]);
+    let hashes = blocks
+        .iter()
+        .map(|b| format!("0x{b:x}"))
+        .collect::<Vec<_>>();
+
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(UInt64Array::from(blocks.to_vec())),
+            Arc::new(StringArray::from(hashes)),
+        ],
+    )
+    .unwrap()
+}
+
+fn ipc(batches: &[RecordBatch]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batches[0].schema()).unwrap();
+    for batch in batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    bytes
+}
+
+/// A response of `bytes` delivered in chunks of `size` bytes
+fn response(bytes: Vec<u8>, size: usize) -> ResponseStream<Vec<u8>> {
+    let chunks = bytes
+        .chunks(size)
+        .map(<[u8]>::to_vec)
+        .map(Ok)
+        .collect::<Vec<_>>();
+    futures::stream::iter(chunks).boxed()
+}
+
+#[tokio::test]
+async fn arrow_batches_are_decoded_across_chunks_and_streams() {
+    let mut bytes = ipc(&[batch(&[1, 2]), batch(&[3])]);
+    bytes.extend(ipc(&[batch(&[4, 5, 6])]));
+
+    let batches = arrow_batches(response(bytes, 7))
+        .map(|batch| batch.unwrap().num_rows())
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(batches, vec![2, 1, 3]);
+}
+
+#[cfg(feature = "parquet")]
+#[tokio::test]
+async fn parquet_files_roll_over_by_block_range() {
+    use std::fs::File;
+
+    use pangea_client::{
+        sink::{ParquetSink, Rotation},
+        ChainId,
+    };
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let dir = tempfile::tempdir().unwrap();
+    let bytes = ipc(&[batch(&[10, 11, 12, 15]), batch(&[19, 20, 25])]);
+
+    let files = ParquetSink::new(dir.path(), ChainId::ETH, "blocks")
+        .with_rotation(Rotation::default().blocks(5))
+        .write_stream(response(bytes, 64))
+        .await
+        .unwrap();
+
+    let names = files
+        .iter()
+        .map(|path| path.file_name().unwrap().to_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "000000000010-000000000012.parquet",
+            "000000000015-000000000019.parquet",
+            "000000000020-000000000020.parquet",
+            "000000000025-000000000025.parquet",
+        ]
+    );
+    assert!(files[0].starts_with(dir.path().join("ETH").join("blocks")));
+
+    let rows = ParquetRecordBatchReaderBuilder::try_new(File::open(&files[1]).unwrap())
+        .unwrap()
+        .build()
+        .unwrap()
+        .map(|batch| batch.unwrap().num_rows())
+        .sum::<usize>();
+    assert_eq!(rows, 2);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use std::collections::HashSet;
+
+use ethers_core::types::{Address, H256};
+use futures::StreamExt;
+use pangea_client::{
+    plan::AnyOf,
+    requests::{
+        logs::GetLogsRequest, transfers::GetTransfersRequest, uniswap_v3::GetPricesRequest,
+    },
+    ChainId, Error,
+};
+use serde_json::{json, Value};
+
+use common::Fake;
+
+#[test]
+fn any_topic_splits_into_one_request_per_topic() {
+    let me = H256::from_low_u64_be(0xbeef);
+    let query = GetLogsRequest::default()
+        .any_topic([1, 2], HashSet::from([me]))
+        .unwrap();
+
+    let branches = query.branches();
+    assert_eq!(branches.len(), 2);
+    assert_eq!(branches[0].topic1__in, HashSet::from([me]));
+    assert!(branches[0].topic2__in.is_empty());
+    assert_eq!(branches[1].topic2__in, HashSet::from([me]));
+    assert!(branches[1].topic1__in.is_empty());
+}
+
+#[test]
+fn any_topic_rejects_missing_topics() {
+    let query = GetLogsRequest::default().any_topic([1, 4], HashSet::new());
+    assert!(matches!(query, Err(Error::InvalidTopic(4))));
+}
+
+fn transfer(block_number: u64, value: u64) -> Value {
+    json!({ "chain": "ETH", "block_number": block_number, "value": value })
+}
+
+#[tokio::test]
+async fn union_only_drops_records_delivered_by_another_branch() {
+    // transfers carry no log index, several in one transaction share a position
+    let client = Fake::new(|_, request| {
+        if request.get("to__in").is_some() {
+            let to_me = [(1, 1), (1, 2), (3, 3), (5, 6), (5, 6)];
+            to_me.map(|(block, value)| transfer(block, value)).to_vec()
+        } else {
+            let from_me = [(2, 4), (3, 3), (3, 5), (5, 6)];
+            from_me
+                .map(|(block, value)| transfer(block, value))
+                .to_vec()
+        }
+    })
+    .client();
+
+    let me = Address::repeat_byte(0x42);
+    let request = GetTransfersRequest {
+        chains: HashSet::from([ChainId::ETH]),
+        ..Default::default()
+    };
+    let query = AnyOf::new(GetTransfersRequest {
+        to__in: HashSet::from([me]),
+        ..request.clone()
+    })
+    .or(GetTransfersRequest {
+        from__in: HashSet::from([me]),
+        ..request
+    });
+
+    let mut values = client
+        .get_any::<GetTransfersRequest, Value>(query)
+        .await
+        .unwrap()
+        .map(|record| record.unwrap()["value"].as_u64().unwrap())
+        .collect::<Vec<_>>()
+        .await;
+    values.sort();
+
+    // both records of block 1, the record of block 3 matched by both branches
+    // once next to the other record at its position, and both copies of the
+    // record of block 5
+    assert_eq!(values, [1, 2, 3, 4, 5, 6, 6]);
+}
+
+#[test]
+fn not_in_filters_are_serialized() {
+    let pool = Address::repeat_byte(0x42);
+    let request = GetPricesRequest {
+        pool_address__not_in: HashSet::from([pool]),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["pool_address__not_in"], format!("{pool:?}"));
+    assert!(json.get("pool_address__in").is_none());
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "datafusion")]
+
+use std::collections::HashSet;
+
+use datafusion::prelude::{col, lit};
+use ethers_core::types::{Address, H256};
+use pangea_client::{
+    query::Bound,
+    requests::{logs::GetLogsRequest, BlockRange},
+    sql::narrow,
+    ChainId,
+};
+
+fn logs() -> GetLogsRequest {
+    GetLogsRequest {
+        chains: HashSet::from([ChainId::ETH, ChainId::ARB]),
+        from_block: Bound::Exact(0),
+        to_block: Bound::Latest,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn block_ranges_are_narrowed() {
+    let request = narrow(
+        &logs(),
+        &[col("block_number").between(lit(100u64), lit(199u64))],
+    )
+    .unwrap();
+    assert_eq!(
+        request.block_range(),
+        (Bound::Exact(100), Bound::Exact(200))
+    );
+
+    let request = narrow(
+        &logs(),
+        &[
+            col("block_number").gt(lit(100u64)),
+            lit(150u64).gt(col("block_number")),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        request.block_range(),
+        (Bound::Exact(101), Bound::Exact(150))
+    );
+
+    let filters = [
+        col("block_number").gt_eq(lit(200u64)),
+        col("block_number").lt(lit(100u64)),
+    ];
+    assert_eq!(narrow(&logs(), &filters), None);
+}
+
+#[test]
+fn sets_and_chains_are_narrowed() {
+    let a = Address::from_low_u64_be(1);
+    let b = Address::from_low_u64_be(2);
+    let topic = H256::repeat_byte(0xdd);
+
+    let filters = [
+        col("chain").eq(lit("ETH")),
+        col("address").in_list(vec![lit(format!("{a:?}")), lit(format!("{b:?}"))], false),
+        col("address").eq(lit(format!("{b:?}"))),
+        col("topic0").eq(lit(format!("{topic:?}"))),
+        col("address").not_eq(lit(format!("{a:?}"))),
+    ];
+    let request = narrow(&logs(), &filters).unwrap();
+
+    assert_eq!(request.chains, HashSet::from([ChainId::ETH]));
+    assert_eq!(request.address__in, HashSet::from([b]));
+    assert_eq!(request.address__not_in, HashSet::from([a]));
+    assert_eq!(request.topic0__in, HashSet::from([topic]));
+}
+
+#[test]
+fn unsupported_filters_are_left_to_the_engine() {
+    let filters = [
+        col("data").eq(lit("0x")),
+        col("log_index").gt(lit(3u64)),
+        col("address").eq(lit("not an address")),
+    ];
+    assert_eq!(narrow(&logs(), &filters), Some(logs()));
+
+    let filters = [
+        col("address").eq(lit(format!("{:?}", Address::from_low_u64_be(1)))),
+        col("address").eq(lit(format!("{:?}", Address::from_low_u64_be(2)))),
+    ];
+    assert_eq!(narrow(&logs(), &filters), None);
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "sqlite")]
+
+use futures::StreamExt;
+use pangea_client::{checkpoint::Checkpointed, sink::SqliteSink};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+
+fn log(block_number: u64, log_index: u64, data: &str) -> Checkpointed<Value> {
+    Checkpointed::Record(json!({
+        "chain": 1,
+        "block_number": block_number,
+        "log_index": log_index,
+        "data": data,
+    }))
+}
+
+fn rows(sink: &SqliteSink) -> Vec<(i64, i64, String)> {
+    let mut statement = sink
+        .connection()
+        .prepare("SELECT block_number, log_index, data FROM logs ORDER BY 1, 2")
+        .unwrap();
+    statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn records_are_upserted_with_their_checkpoint() {
+    let mut sink = SqliteSink::from_connection(Connection::open_in_memory().unwrap(), "logs")
+        .unwrap()
+        .job("logs");
+
+    let events = vec![
+        log(1, 0, "0x01"),
+        log(1, 1, "0x02"),
+        Checkpointed::BlockEnd(1),
+        log(2, 0, "0x03"),
+        Checkpointed::BlockEnd(2),
+        // replayed after a restart
+        log(2, 0, "0x04"),
+        Checkpointed::BlockEnd(2),
+    ];
+    sink.write_stream(futures::stream::iter(events.into_iter().map(Ok)).boxed())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows(&sink),
+        [
+            (1, 0, "0x01".to_string()),
+            (1, 1, "0x02".to_string()),
+            (2, 0, "0x04".to_string()),
+        ]
+    );
+    assert_eq!(sink.checkpoint().unwrap(), Some(2));
+}
+
+#[tokio::test]
+async fn retractions_delete_rows_and_new_fields_add_columns() {
+    let mut sink =
+        SqliteSink::from_connection(Connection::open_in_memory().unwrap(), "logs").unwrap();
+
+    sink.write_block(
+        vec![
+            json!({ "chain": 1, "block_number": 5, "log_index": 0, "data": "0x01" }),
+            json!({ "chain": 1, "block_number": 5, "log_index": 1, "data": "0x02", "topic0": "0xaa" }),
+        ],
+        None,
+    )
+    .unwrap();
+    sink.write_block(
+        vec![json!({ "chain": 1, "block_number": 5, "log_index": 0, "removed": true })],
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(rows(&sink), [(5, 1, "0x02".to_string())]);
+    let topic0: String = sink
+        .connection()
+        .query_row("SELECT topic0 FROM logs", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(topic0, "0xaa");
+}
+
+#[test]
+fn records_without_a_natural_key_need_one() {
+    let mut sink =
+        SqliteSink::from_connection(Connection::open_in_memory().unwrap(), "tokens").unwrap();
+    assert!(sink
+        .write_block(vec![json!({ "symbol": "USDC" })], None)
+        .is_err());
+
+    let mut sink = SqliteSink::from_connection(Connection::open_in_memory().unwrap(), "tokens")
+        .unwrap()
+        .key(["symbol"]);
+    sink.write_block(vec![json!({ "symbol": "USDC", "decimals": 6 })], None)
+        .unwrap();
+    sink.write_block(vec![json!({ "symbol": "USDC", "decimals": 6 })], None)
+        .unwrap();
+
+    let count: i64 = sink
+        .connection()
+        .query_row("SELECT COUNT(*) FROM tokens", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn records_missing_part_of_the_key_are_upserted() {
+    let mut sink =
+        SqliteSink::from_connection(Connection::open_in_memory().unwrap(), "logs").unwrap();
+    sink.write_block(
+        vec![json!({ "chain": 1, "block_number": 5, "log_index": 0, "data": "0x01" })],
+        None,
+    )
+    .unwrap();
+
+    // a record without a log index, replayed
+    for _ in 0..2 {
+        sink.write_block(
+            vec![json!({ "chain": 1, "block_number": 6, "data": "0x02" })],
+            None,
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        rows(&sink),
+        [(5, 0, "0x01".to_string()), (6, 0, "0x02".to_string())]
+    );
+    let nullable: i64 = sink
+        .connection()
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('logs') WHERE pk > 0 AND \"notnull\" = 0",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(nullable, 0);
+}
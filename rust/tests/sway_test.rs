@@ -0,0 +1,95 @@
+use pangea_client::sway::{SwayAbi, SwayValue};
+use serde::Deserialize;
+use serde_json::json;
+
+const LOG_ID: u64 = 1_515_152_261_580_153_489;
+
+fn abi() -> SwayAbi {
+    let abi = json!({
+        "programType": "contract",
+        "specVersion": "1",
+        "encodingVersion": "1",
+        "concreteTypes": [
+            { "type": "()", "concreteTypeId": "unit" },
+            { "type": "u64", "concreteTypeId": "u64" },
+            { "type": "struct SwapEvent", "concreteTypeId": "swap", "metadataTypeId": 0 },
+        ],
+        "metadataTypes": [
+            {
+                "type": "struct SwapEvent",
+                "metadataTypeId": 0,
+                "components": [
+                    { "name": "amount_in", "typeId": "u64" },
+                    { "name": "recipient", "typeId": 2 },
+                    {
+                        "name": "fee",
+                        "typeId": 1,
+                        "typeArguments": [{ "name": "", "typeId": "u64" }],
+                    },
+                ],
+            },
+            {
+                "type": "enum std::option::Option",
+                "metadataTypeId": 1,
+                "components": [
+                    { "name": "None", "typeId": "unit" },
+                    { "name": "Some", "typeId": 3 },
+                ],
+                "typeParameters": [3],
+            },
+            { "type": "b256", "metadataTypeId": 2 },
+            { "type": "generic T", "metadataTypeId": 3 },
+        ],
+        "loggedTypes": [{ "logId": LOG_ID.to_string(), "concreteTypeId": "swap" }],
+    });
+
+    SwayAbi::from_json(&abi.to_string()).unwrap()
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct SwapEvent {
+    amount_in: u64,
+    recipient: String,
+    fee: Option<u64>,
+}
+
+#[test]
+fn decodes_logged_struct_with_generic_enum() {
+    let mut data = 1000u64.to_be_bytes().to_vec();
+    data.extend([0x11; 32]);
+    data.extend(1u64.to_be_bytes());
+    data.extend(5u64.to_be_bytes());
+
+    let log = json!({
+        "rb": LOG_ID.to_string(),
+        "data": format!("0x{}", data.iter().map(|b| format!("{b:02x}")).collect::<String>()),
+    });
+
+    let decoded = abi().decode_log(log).unwrap().unwrap();
+    assert_eq!(decoded.log_id, LOG_ID);
+    match &decoded.value {
+        SwayValue::Struct { name, fields } => {
+            assert_eq!(name, "SwapEvent");
+            assert_eq!(fields[0], ("amount_in".to_string(), SwayValue::U64(1000)));
+        }
+        value => panic!("expected a struct, got {value:?}"),
+    }
+
+    let swap = decoded.deserialize::<SwapEvent>().unwrap().value;
+    assert_eq!(
+        swap,
+        SwapEvent {
+            amount_in: 1000,
+            recipient: format!("0x{}", "11".repeat(32)),
+            fee: Some(5),
+        }
+    );
+}
+
+#[test]
+fn rejects_truncated_data_and_skips_unknown_logs() {
+    let abi = abi();
+
+    assert!(abi.decode(LOG_ID, &1000u64.to_be_bytes()).is_err());
+    assert_eq!(abi.decode(LOG_ID + 1, &[]).unwrap(), None);
+}
@@ -0,0 +1,230 @@
+mod common;
+
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::StreamExt;
+use pangea_client::{
+    provider::{ChainProvider, Provider, StreamResponse, UniswapV2Provider},
+    query::Bound,
+    requests::{logs::GetLogsRequest, uniswap_v2},
+    ChainId, Client, Error, Format, ResolveBounds, Result,
+};
+use serde_json::{json, Value};
+
+use common::{from_block, Fake};
+
+const BLOCKS: u64 = 100;
+
+/// A block every 10 seconds from 1000, up to block 99, and no logs
+fn client() -> Client<Fake> {
+    Fake::new(|endpoint, request| match endpoint {
+        "blocks" => {
+            let block_number = match request["from_block"].as_str() {
+                Some("latest") => BLOCKS - 1,
+                _ => from_block(request),
+            };
+            let block = json!({
+                "block_number": block_number,
+                "timestamp": 1000 + 10 * block_number,
+            });
+            (block_number < BLOCKS)
+                .then_some(block)
+                .into_iter()
+                .collect()
+        }
+        "logs" | "uniswap_v2_prices" => Vec::new(),
+        endpoint => unimplemented!("{endpoint}"),
+    })
+    .client()
+}
+
+/// The logs request sent from `from_block` once it is resolved, and the
+/// number of blocks fetched to resolve it
+async fn send(client: &Client<Fake>, from_block: Bound) -> (Value, usize) {
+    client.inner.requests.lock().unwrap().clear();
+
+    let request = GetLogsRequest {
+        chains: HashSet::from([ChainId::ETH]),
+        from_block,
+        to_block: Bound::Latest,
+        ..Default::default()
+    };
+    let mut logs = client
+        .get_logs_by_format(request, Format::JsonStream, false)
+        .await
+        .unwrap();
+    assert!(logs.next().await.is_none());
+
+    let requests = client.inner.requests.lock().unwrap();
+    let (_, logs) = requests
+        .iter()
+        .find(|(endpoint, _)| *endpoint == "logs")
+        .unwrap();
+    let probes = requests
+        .iter()
+        .filter(|(endpoint, _)| *endpoint == "blocks")
+        .count();
+    (logs.clone(), probes)
+}
+
+/// The block the logs from `timestamp` start at, and the number of blocks
+/// fetched to find it
+async fn resolve(client: &Client<Fake>, timestamp: i64) -> (u64, usize) {
+    let (request, probes) = send(client, Bound::Timestamp(timestamp)).await;
+    (from_block(&request), probes)
+}
+
+#[tokio::test]
+async fn timestamps_are_resolved_to_the_first_block_at_or_after_them() {
+    let client = client();
+
+    // the timestamp of a block
+    assert_eq!(resolve(&client, 1500).await.0, 50);
+    assert_eq!(resolve(&client, 1990).await.0, 99);
+    // between two blocks
+    assert_eq!(resolve(&client, 1505).await.0, 51);
+    assert_eq!(resolve(&client, 1491).await.0, 50);
+    // before the first block
+    assert_eq!(resolve(&client, 0).await.0, 0);
+    // after the head, the next block to be produced
+    assert_eq!(resolve(&client, 5000).await.0, BLOCKS);
+}
+
+#[tokio::test]
+async fn resolved_timestamps_are_cached() {
+    let client = client();
+
+    let (block_number, probes) = resolve(&client, 1234).await;
+    assert_eq!(block_number, 24);
+    assert!(probes > 0);
+
+    assert_eq!(resolve(&client, 1234).await, (24, 0));
+}
+
+#[tokio::test]
+async fn timestamps_need_a_single_chain() {
+    let client = client();
+    let request = GetLogsRequest {
+        chains: HashSet::from([ChainId::ETH, ChainId::ARB]),
+        from_block: Bound::Timestamp(1500),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        client
+            .get_logs_by_format(request, Format::JsonStream, false)
+            .await,
+        Err(Error::ClientSideBoundChains(_))
+    ));
+    assert!(client.inner.requests.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn relative_bounds_are_resolved_from_now_and_the_head() {
+    let client = client();
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let (request, _) = send(
+        &client,
+        Bound::FromLatestDuration(now - Duration::from_secs(1500)),
+    )
+    .await;
+    // a second may pass between now and the lookup
+    assert!((50..=51).contains(&from_block(&request)));
+
+    // finality is left to the server as a depth below the head
+    let (request, probes) = send(&client, Bound::Finalized).await;
+    assert_eq!(request["from_block"], json!(-64));
+    assert_eq!(probes, 0);
+    let (request, _) = send(&client, Bound::Safe).await;
+    assert_eq!(request["from_block"], json!(-32));
+}
+
+#[tokio::test]
+async fn dex_requests_resolve_timestamps_only_through_resolve_bounds() {
+    let client = client();
+    let request = uniswap_v2::GetPricesRequest {
+        chains: HashSet::from([ChainId::ETH]),
+        from_block: Bound::Timestamp(1500),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        client
+            .get_prices_by_format(request.clone(), Format::JsonStream, false)
+            .await,
+        Err(Error::UnresolvedBound(Bound::Timestamp(1500)))
+    ));
+    assert!(client.inner.requests.lock().unwrap().is_empty());
+
+    let mut resolved = request;
+    client.resolve_bounds(&mut resolved).await.unwrap();
+    assert_eq!(resolved.from_block, Bound::Exact(50));
+
+    // a depth below the head needs no blocks
+    let request = uniswap_v2::GetPricesRequest {
+        from_block: Bound::Finalized,
+        ..resolved
+    };
+    let mut prices = client
+        .get_prices_by_format(request, Format::JsonStream, false)
+        .await
+        .unwrap();
+    assert!(prices.next().await.is_none());
+    let requests = client.inner.requests.lock().unwrap();
+    let (_, prices) = requests.last().unwrap();
+    assert_eq!(prices["from_block"], json!(-64));
+}
+
+/// A provider of Uniswap V2 only, without blocks
+struct UniswapOnly;
+
+#[async_trait::async_trait]
+impl Provider for UniswapOnly {
+    async fn try_new(_: String, _: bool, _: Option<String>, _: Option<String>) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn get_status_by_format(&self, _: Format) -> StreamResponse<Vec<u8>> {
+        unimplemented!()
+    }
+}
+
+#[async_trait::async_trait]
+impl UniswapV2Provider for UniswapOnly {
+    async fn get_pairs_by_format(
+        &self,
+        _: uniswap_v2::GetPairsRequest,
+        _: Format,
+        _: bool,
+    ) -> StreamResponse<Vec<u8>> {
+        Ok(futures::stream::empty().boxed())
+    }
+
+    async fn get_prices_by_format(
+        &self,
+        _: uniswap_v2::GetPricesRequest,
+        _: Format,
+        _: bool,
+    ) -> StreamResponse<Vec<u8>> {
+        Ok(futures::stream::empty().boxed())
+    }
+}
+
+#[tokio::test]
+async fn dex_clients_need_no_blocks_endpoint() {
+    let client = Client::new(UniswapOnly);
+    let request = uniswap_v2::GetPricesRequest {
+        from_block: Bound::Safe,
+        ..Default::default()
+    };
+
+    let mut prices = client
+        .get_prices_by_format(request, Format::JsonStream, false)
+        .await
+        .unwrap();
+    assert!(prices.next().await.is_none());
+}
@@ -0,0 +1,177 @@
+use ethers_core::types::{Address, U256};
+use pangea_client::{
+    core::stream::Position,
+    state::{PoolState, UniV2State},
+    ChainId, Error,
+};
+use serde_json::{json, Value};
+
+const PAIR: &str = "0x0000000000000000000000000000000000000001";
+const TOKEN0: &str = "0x00000000000000000000000000000000000000a0";
+const TOKEN1: &str = "0x00000000000000000000000000000000000000a1";
+
+fn address(s: &str) -> Address {
+    s.parse().unwrap()
+}
+
+fn pair_record() -> Value {
+    json!({
+        "chain": "ETH",
+        "block_number": 1,
+        "pair_address": PAIR,
+        "token0": TOKEN0,
+        "token1": TOKEN1,
+    })
+}
+
+fn sync(block_number: u64, reserve0: u64, reserve1: u64) -> Value {
+    json!({
+        "chain": "ETH",
+        "block_number": block_number,
+        "transaction_index": 0,
+        "log_index": 1,
+        "address": PAIR,
+        "event": "Sync",
+        "reserve0": reserve0.to_string(),
+        "reserve1": format!("{reserve1:#x}"),
+    })
+}
+
+fn state() -> UniV2State {
+    let mut state = UniV2State::default();
+    state.apply(&pair_record()).unwrap();
+    state.apply(&sync(2, 1_000_000, 2_000_000)).unwrap();
+    state
+}
+
+#[test]
+fn quotes_from_reserves() {
+    let state = state();
+    let pair = state.pair(ChainId::ETH, address(PAIR)).unwrap();
+    assert_eq!(pair.reserve0, U256::from(1_000_000));
+    assert_eq!(pair.reserve1, U256::from(2_000_000));
+
+    // 1000 * 997 * 2_000_000 / (1_000_000 * 1000 + 1000 * 997)
+    let out = state
+        .get_amount_out(
+            ChainId::ETH,
+            address(PAIR),
+            address(TOKEN0),
+            U256::from(1000),
+        )
+        .unwrap();
+    assert_eq!(out, U256::from(1992));
+
+    // 1_000_000 * 1992 * 1000 / ((2_000_000 - 1992) * 997) + 1
+    let amount_in = state
+        .get_amount_in(
+            ChainId::ETH,
+            address(PAIR),
+            address(TOKEN1),
+            U256::from(1992),
+        )
+        .unwrap();
+    assert_eq!(amount_in, U256::from(1000));
+
+    assert!(matches!(
+        pair.get_amount_in(address(TOKEN1), U256::from(2_000_000)),
+        Err(Error::InsufficientLiquidity(_))
+    ));
+}
+
+#[test]
+fn full_fee_quotes_nothing() {
+    let mut pair = state().pair(ChainId::ETH, address(PAIR)).unwrap().clone();
+    pair.fee_bps = 10_000;
+
+    assert_eq!(
+        pair.get_amount_out(address(TOKEN0), U256::from(1000)).unwrap(),
+        U256::zero()
+    );
+    assert!(matches!(
+        pair.get_amount_in(address(TOKEN1), U256::from(1)),
+        Err(Error::InsufficientLiquidity(_))
+    ));
+}
+
+#[test]
+fn skips_replayed_events() {
+    let mut state = state();
+    assert!(state.apply(&sync(3, 1_500_000, 1_400_000)).unwrap());
+    assert!(!state.apply(&sync(2, 1, 1)).unwrap());
+    assert!(!state.apply(&sync(3, 1, 1)).unwrap());
+
+    let pair = state.pair(ChainId::ETH, address(PAIR)).unwrap();
+    assert_eq!(pair.reserve0, U256::from(1_500_000));
+    assert_eq!(
+        state.last_position(ChainId::ETH),
+        Some(Position {
+            block_number: 3,
+            transaction_index: 0,
+            log_index: 1,
+        })
+    );
+}
+
+#[test]
+fn swap_without_reserves_waits_for_sync() {
+    let mut state = state();
+    let mut swap = sync(3, 0, 0);
+    swap["event"] = json!(2);
+    swap["log_index"] = json!(0);
+    swap.as_object_mut().unwrap().remove("reserve0");
+    swap.as_object_mut().unwrap().remove("reserve1");
+    assert!(state.apply(&swap).unwrap());
+
+    let pair = state.pair(ChainId::ETH, address(PAIR)).unwrap();
+    assert_eq!(pair.reserve0, U256::from(1_000_000));
+
+    let mut sync = sync(4, 0, 0);
+    sync.as_object_mut().unwrap().remove("reserve1");
+    assert!(matches!(
+        state.apply(&sync),
+        Err(Error::InvalidPoolEvent(_))
+    ));
+}
+
+#[test]
+fn unknown_token_and_pool() {
+    let state = state();
+    let other = address("0x00000000000000000000000000000000000000ff");
+
+    assert!(matches!(
+        state.get_amount_out(ChainId::ETH, address(PAIR), other, U256::one()),
+        Err(Error::TokenNotInPool(..))
+    ));
+    assert!(matches!(
+        state.get_amount_out(ChainId::ETH, other, address(TOKEN0), U256::one()),
+        Err(Error::UnknownPool(_))
+    ));
+    assert!(matches!(
+        state.get_amount_out(ChainId::ARB, address(PAIR), address(TOKEN0), U256::one()),
+        Err(Error::UnknownPool(_))
+    ));
+}
+
+#[test]
+fn snapshot_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("univ2.json");
+
+    let state = state().with_fee(25);
+    state.snapshot(&path).unwrap();
+    let restored = UniV2State::restore(&path).unwrap();
+
+    assert_eq!(restored, state);
+    assert_eq!(
+        restored
+            .get_amount_out(
+                ChainId::ETH,
+                address(PAIR),
+                address(TOKEN0),
+                U256::from(1000)
+            )
+            .unwrap(),
+        U256::from(1992)
+    );
+}
@@ -0,0 +1,226 @@
+use ethers_core::types::{Address, U256};
+use pangea_client::{
+    state::{
+        uniswap_v3::math::{
+            price_at_tick, sqrt_ratio_at_tick, tick_at_price, tick_at_sqrt_ratio,
+            MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO, MIN_TICK, Q96,
+        },
+        PoolState, UniV3State,
+    },
+    ChainId, Error,
+};
+use serde_json::{json, Value};
+
+const POOL: &str = "0x0000000000000000000000000000000000000001";
+const TOKEN0: &str = "0x00000000000000000000000000000000000000a0";
+const TOKEN1: &str = "0x00000000000000000000000000000000000000a1";
+
+fn address(s: &str) -> Address {
+    s.parse().unwrap()
+}
+
+fn u256(s: &str) -> U256 {
+    U256::from_dec_str(s).unwrap()
+}
+
+fn pool_record() -> Value {
+    json!({
+        "chain": "ETH",
+        "block_number": 1,
+        "pool_address": POOL,
+        "token0": TOKEN0,
+        "token1": TOKEN1,
+        "fee": 3000,
+        "tick_spacing": 60,
+    })
+}
+
+fn position(block_number: u64, event: &str, lower: i32, upper: i32, liquidity: u128) -> Value {
+    json!({
+        "chain": "ETH",
+        "block_number": block_number,
+        "pool_address": POOL,
+        "event": event,
+        "tick_lower": lower,
+        "tick_upper": upper,
+        "liquidity": liquidity.to_string(),
+    })
+}
+
+/// 1e18 of liquidity between ticks -600 and 600, and 5e17 more between
+/// -1200 and -600, priced at tick 0
+fn state() -> UniV3State {
+    let mut state = UniV3State::default();
+    state.apply(&pool_record()).unwrap();
+    state
+        .apply(&position(2, "mint", -600, 600, 1_000_000_000_000_000_000))
+        .unwrap();
+    state
+        .apply(&position(3, "mint", -1200, -600, 500_000_000_000_000_000))
+        .unwrap();
+    state
+        .apply(&json!({
+            "chain": "ETH",
+            "block_number": 4,
+            "pool_address": POOL,
+            "event": "swap",
+            "sqrt_price_x96": Q96.to_string(),
+            "tick": 0,
+        }))
+        .unwrap();
+    state
+}
+
+#[test]
+fn tick_math() {
+    assert_eq!(sqrt_ratio_at_tick(MIN_TICK).unwrap(), MIN_SQRT_RATIO);
+    assert_eq!(sqrt_ratio_at_tick(MAX_TICK).unwrap(), MAX_SQRT_RATIO);
+    assert_eq!(sqrt_ratio_at_tick(0).unwrap(), Q96);
+    assert_eq!(
+        sqrt_ratio_at_tick(1).unwrap(),
+        u256("79232123823359799118286999568")
+    );
+    assert_eq!(
+        sqrt_ratio_at_tick(-1).unwrap(),
+        u256("79224201403219477170569942574")
+    );
+    assert!(matches!(
+        sqrt_ratio_at_tick(MAX_TICK + 1),
+        Err(Error::InvalidTick(_))
+    ));
+
+    for tick in [MIN_TICK, -600, -1, 0, 1, 60, MAX_TICK - 1] {
+        let sqrt_price = sqrt_ratio_at_tick(tick).unwrap();
+        assert_eq!(tick_at_sqrt_ratio(sqrt_price).unwrap(), tick);
+        if tick > MIN_TICK {
+            assert_eq!(tick_at_sqrt_ratio(sqrt_price - 1).unwrap(), tick - 1);
+        }
+    }
+    assert!(matches!(
+        tick_at_sqrt_ratio(MAX_SQRT_RATIO),
+        Err(Error::InvalidSqrtPrice(_))
+    ));
+
+    assert!((price_at_tick(0).unwrap() - 1.0).abs() < 1e-12);
+    assert!((price_at_tick(10_000).unwrap() - 1.0001f64.powi(10_000)).abs() < 1e-9);
+    assert_eq!(
+        tick_at_price(1.0001f64.powi(-600) * 1.000_01).unwrap(),
+        -600
+    );
+}
+
+#[test]
+fn liquidity_by_tick() {
+    let state = state();
+    let pool = state.pool(ChainId::ETH, address(POOL)).unwrap();
+
+    assert_eq!(pool.fee, Some(3000));
+    assert_eq!(pool.tick_spacing, 60);
+    assert_eq!(pool.ticks.len(), 3);
+    assert_eq!(pool.ticks[&-600].liquidity_gross, 1_500_000_000_000_000_000);
+    assert_eq!(pool.ticks[&-600].liquidity_net, 500_000_000_000_000_000);
+    assert_eq!(pool.liquidity, 1_000_000_000_000_000_000);
+    assert_eq!(pool.liquidity_at(-700).unwrap(), 500_000_000_000_000_000);
+    assert_eq!(pool.liquidity_at(600).unwrap(), 0);
+
+    let mut state = state;
+    state
+        .apply(&position(5, "burn", -1200, -600, 500_000_000_000_000_000))
+        .unwrap();
+    let pool = state.pool(ChainId::ETH, address(POOL)).unwrap();
+    assert!(!pool.ticks.contains_key(&-1200));
+    assert_eq!(pool.ticks[&-600].liquidity_net, 1_000_000_000_000_000_000);
+    assert!(!state
+        .apply(&position(5, "burn", -1200, -600, 500_000_000_000_000_000))
+        .unwrap());
+
+    assert!(matches!(
+        state.apply(&position(6, "burn", -1200, -600, 1)),
+        Err(Error::InvalidPoolEvent(_))
+    ));
+}
+
+#[test]
+fn quotes_within_a_tick_range() {
+    let state = state();
+    let amount_in = U256::exp10(15);
+
+    let out = state
+        .get_amount_out(ChainId::ETH, address(POOL), address(TOKEN0), amount_in)
+        .unwrap();
+    assert_eq!(out, u256("996006981039903"));
+    let out = state
+        .get_amount_out(ChainId::ETH, address(POOL), address(TOKEN1), amount_in)
+        .unwrap();
+    assert_eq!(out, u256("996006981039903"));
+
+    let needed = state
+        .get_amount_in(ChainId::ETH, address(POOL), address(TOKEN1), out)
+        .unwrap();
+    assert_eq!(needed, amount_in);
+}
+
+#[test]
+fn swaps_across_ticks() {
+    let mut state = state();
+    let pool = state.pool_mut(ChainId::ETH, address(POOL)).unwrap();
+
+    let swap = pool.swap(true, U256::exp10(16) * 4, true, None).unwrap();
+    assert_eq!(swap.amount_out, u256("38271541247902345"));
+    assert_eq!(swap.fee_amount, u256("120000000000001"));
+    assert_eq!(swap.tick, -963);
+    assert_eq!(swap.liquidity, 500_000_000_000_000_000);
+
+    let exact_out = pool.swap(true, swap.amount_out, false, None).unwrap();
+    assert_eq!(exact_out.amount_in, U256::exp10(16) * 4);
+
+    pool.apply_swap(&swap);
+    assert_eq!(pool.tick, -963);
+    assert_eq!(pool.sqrt_price_x96, u256("75505225483662083221495729851"));
+
+    // more than the liquidity below the price
+    assert!(matches!(
+        state.get_amount_out(
+            ChainId::ETH,
+            address(POOL),
+            address(TOKEN0),
+            U256::exp10(17)
+        ),
+        Err(Error::InsufficientLiquidity(_))
+    ));
+}
+
+#[test]
+fn unknown_token_and_pool() {
+    let state = state();
+    let other = address("0x00000000000000000000000000000000000000ff");
+
+    assert!(matches!(
+        state.get_amount_out(ChainId::ETH, address(POOL), other, U256::one()),
+        Err(Error::TokenNotInPool(..))
+    ));
+    assert!(matches!(
+        state.get_amount_in(ChainId::ETH, other, address(TOKEN0), U256::one()),
+        Err(Error::UnknownPool(_))
+    ));
+}
+
+#[test]
+fn snapshot_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("univ3.json");
+
+    let state = state();
+    state.snapshot(&path).unwrap();
+    let restored = UniV3State::restore(&path).unwrap();
+
+    assert_eq!(restored, state);
+    assert_eq!(
+        restored.last_price(ChainId::ETH).map(|p| p.block_number),
+        Some(4)
+    );
+    assert_eq!(
+        restored.last_position(ChainId::ETH).map(|p| p.block_number),
+        Some(3)
+    );
+}